@@ -0,0 +1,22 @@
+CREATE TABLE photos (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  room_id UUID REFERENCES rooms(id) ON DELETE CASCADE,
+  storage_key TEXT NOT NULL,
+  thumbnail_key TEXT NOT NULL,
+  content_type TEXT NOT NULL,
+  width INT NOT NULL,
+  height INT NOT NULL,
+  size_bytes INT NOT NULL,
+  position INT NOT NULL,
+  is_cover BOOLEAN NOT NULL DEFAULT false,
+  created_at TIMESTAMP DEFAULT now()
+);
+
+CREATE INDEX photos_hotel_idx ON photos (hotel_id, position);
+
+CREATE UNIQUE INDEX photos_hotel_cover_idx
+  ON photos (hotel_id) WHERE is_cover AND room_id IS NULL;
+
+CREATE UNIQUE INDEX photos_room_cover_idx
+  ON photos (room_id) WHERE is_cover AND room_id IS NOT NULL;
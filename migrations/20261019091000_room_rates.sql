@@ -0,0 +1,20 @@
+CREATE TABLE room_rates (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+  kind TEXT NOT NULL CHECK (kind IN ('date_range', 'day_of_week', 'date')),
+  start_date DATE,
+  end_date DATE,
+  days_of_week INT[],
+  price_per_night NUMERIC(10,2) NOT NULL CHECK (price_per_night > 0),
+  created_at TIMESTAMP DEFAULT now(),
+  CHECK (start_date IS NULL OR end_date IS NULL OR end_date >= start_date)
+);
+
+CREATE INDEX room_rates_room_idx ON room_rates (room_id);
+
+CREATE TABLE booking_nights (
+  booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+  night_date DATE NOT NULL,
+  price NUMERIC(10,2) NOT NULL,
+  PRIMARY KEY (booking_id, night_date)
+);
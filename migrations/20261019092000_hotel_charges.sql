@@ -0,0 +1,30 @@
+CREATE TABLE hotel_charges (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  category TEXT NOT NULL CHECK (category IN ('tax', 'fee')),
+  calculation TEXT NOT NULL CHECK (calculation IN ('percentage', 'fixed')),
+  amount NUMERIC(10,2) NOT NULL CHECK (amount >= 0),
+  basis TEXT NOT NULL CHECK (basis IN ('per_night', 'per_stay', 'per_guest')),
+  inclusive BOOLEAN NOT NULL DEFAULT false,
+  created_at TIMESTAMP DEFAULT now(),
+  CHECK (calculation = 'fixed' OR basis = 'per_stay')
+);
+
+CREATE INDEX hotel_charges_hotel_idx ON hotel_charges (hotel_id);
+
+ALTER TABLE bookings ADD COLUMN subtotal NUMERIC(10,2);
+UPDATE bookings SET subtotal = total_price;
+ALTER TABLE bookings ALTER COLUMN subtotal SET NOT NULL;
+
+CREATE TABLE booking_line_items (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+  category TEXT NOT NULL CHECK (category IN ('tax', 'fee', 'discount')),
+  name TEXT NOT NULL,
+  amount NUMERIC(10,2) NOT NULL,
+  inclusive BOOLEAN NOT NULL DEFAULT false,
+  position INT NOT NULL
+);
+
+CREATE INDEX booking_line_items_booking_idx ON booking_line_items (booking_id);
@@ -0,0 +1,20 @@
+-- Admins are promoted directly in the database; signup never grants the role.
+ALTER TABLE users DROP CONSTRAINT users_role_check;
+ALTER TABLE users ADD CONSTRAINT users_role_check
+  CHECK (role IN ('customer', 'owner', 'admin'));
+
+ALTER TABLE hotels ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD'
+  CHECK (currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE bookings ADD COLUMN currency TEXT;
+UPDATE bookings b SET currency = h.currency FROM hotels h WHERE h.id = b.hotel_id;
+ALTER TABLE bookings ALTER COLUMN currency SET NOT NULL;
+
+-- Units of `currency` per one unit of `base_currency`. Uploads replace the
+-- whole table, so every row shares the same base.
+CREATE TABLE exchange_rates (
+  currency TEXT PRIMARY KEY CHECK (currency ~ '^[A-Z]{3}$'),
+  base_currency TEXT NOT NULL,
+  rate NUMERIC(20,10) NOT NULL CHECK (rate > 0),
+  updated_at TIMESTAMP DEFAULT now()
+);
@@ -0,0 +1,22 @@
+-- A NULL room_id applies the rule to every room in the hotel.
+CREATE TABLE stay_restrictions (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  room_id UUID REFERENCES rooms(id) ON DELETE CASCADE,
+  kind TEXT NOT NULL CHECK (kind IN (
+    'min_nights',
+    'max_nights',
+    'closed_to_arrival',
+    'closed_to_departure',
+    'booking_horizon'
+  )),
+  value INT CHECK (value > 0),
+  start_date DATE,
+  end_date DATE,
+  days_of_week INT[],
+  created_at TIMESTAMP DEFAULT now(),
+  CHECK (start_date IS NULL OR end_date IS NULL OR end_date >= start_date),
+  CHECK ((kind IN ('closed_to_arrival', 'closed_to_departure')) = (value IS NULL))
+);
+
+CREATE INDEX stay_restrictions_hotel_idx ON stay_restrictions (hotel_id);
@@ -0,0 +1,15 @@
+-- Both dates are inclusive: a block from the 1st to the 5th takes the room
+-- out of service for the nights of the 1st through the 5th.
+CREATE TABLE room_blocks (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+  start_date DATE NOT NULL,
+  end_date DATE NOT NULL,
+  reason TEXT NOT NULL,
+  created_by UUID NOT NULL REFERENCES users(id),
+  created_at TIMESTAMP DEFAULT now(),
+  CHECK (end_date >= start_date)
+);
+
+CREATE INDEX room_blocks_room_idx ON room_blocks (room_id, start_date);
@@ -0,0 +1,9 @@
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+-- Last line of defence against double-booking, whichever code path writes.
+ALTER TABLE bookings ADD CONSTRAINT bookings_no_overlap
+  EXCLUDE USING gist (
+    room_id WITH =,
+    daterange(check_in_date, check_out_date) WITH &&
+  )
+  WHERE (status = 'confirmed');
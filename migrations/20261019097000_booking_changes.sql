@@ -0,0 +1,16 @@
+-- One row per successful modification, keeping what the booking was before.
+CREATE TABLE booking_changes (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+  changed_by UUID NOT NULL REFERENCES users(id),
+  previous_room_id UUID NOT NULL REFERENCES rooms(id),
+  previous_check_in_date DATE NOT NULL,
+  previous_check_out_date DATE NOT NULL,
+  previous_guests INT NOT NULL,
+  previous_total_price NUMERIC(10,2) NOT NULL,
+  new_total_price NUMERIC(10,2) NOT NULL,
+  price_difference NUMERIC(10,2) NOT NULL,
+  created_at TIMESTAMP DEFAULT now()
+);
+
+CREATE INDEX booking_changes_booking_idx ON booking_changes (booking_id);
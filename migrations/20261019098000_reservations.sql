@@ -0,0 +1,15 @@
+-- Groups the room bookings of one multi-room stay. Each room stays an
+-- ordinary booking, so it can be cancelled on its own.
+CREATE TABLE reservations (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id),
+  hotel_id UUID NOT NULL REFERENCES hotels(id),
+  check_in_date DATE NOT NULL,
+  check_out_date DATE NOT NULL,
+  created_at TIMESTAMP DEFAULT now(),
+  CHECK (check_out_date > check_in_date)
+);
+
+ALTER TABLE bookings ADD COLUMN reservation_id UUID REFERENCES reservations(id);
+
+CREATE INDEX bookings_reservation_idx ON bookings (reservation_id);
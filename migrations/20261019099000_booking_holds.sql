@@ -0,0 +1,19 @@
+ALTER TABLE bookings DROP CONSTRAINT bookings_status_check;
+ALTER TABLE bookings ADD CONSTRAINT bookings_status_check
+  CHECK (status IN ('held', 'confirmed', 'cancelled', 'expired'));
+
+ALTER TABLE bookings ADD COLUMN hold_expires_at TIMESTAMP;
+ALTER TABLE bookings ADD CONSTRAINT bookings_hold_expiry_check
+  CHECK (status <> 'held' OR hold_expires_at IS NOT NULL);
+
+-- A live hold takes the room just like a confirmed booking does.
+ALTER TABLE bookings DROP CONSTRAINT bookings_no_overlap;
+ALTER TABLE bookings ADD CONSTRAINT bookings_no_overlap
+  EXCLUDE USING gist (
+    room_id WITH =,
+    daterange(check_in_date, check_out_date) WITH &&
+  )
+  WHERE (status IN ('held', 'confirmed'));
+
+CREATE INDEX bookings_hold_expiry_idx ON bookings (hold_expires_at)
+  WHERE status = 'held';
@@ -0,0 +1,36 @@
+ALTER TABLE bookings DROP CONSTRAINT bookings_status_check;
+ALTER TABLE bookings ADD CONSTRAINT bookings_status_check
+  CHECK (status IN ('held', 'pending_payment', 'confirmed', 'cancelled', 'expired'));
+
+-- An unpaid booking keeps the room only until its payment deadline.
+ALTER TABLE bookings DROP CONSTRAINT bookings_hold_expiry_check;
+ALTER TABLE bookings ADD CONSTRAINT bookings_hold_expiry_check
+  CHECK (status NOT IN ('held', 'pending_payment') OR hold_expires_at IS NOT NULL);
+
+ALTER TABLE bookings DROP CONSTRAINT bookings_no_overlap;
+ALTER TABLE bookings ADD CONSTRAINT bookings_no_overlap
+  EXCLUDE USING gist (
+    room_id WITH =,
+    daterange(check_in_date, check_out_date) WITH &&
+  )
+  WHERE (status IN ('held', 'pending_payment', 'confirmed'));
+
+DROP INDEX bookings_hold_expiry_idx;
+CREATE INDEX bookings_hold_expiry_idx ON bookings (hold_expires_at)
+  WHERE status IN ('held', 'pending_payment');
+
+CREATE TABLE payments (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+  provider VARCHAR(50) NOT NULL,
+  provider_ref VARCHAR(255) NOT NULL,
+  amount NUMERIC(10,2) NOT NULL CHECK (amount >= 0),
+  currency CHAR(3) NOT NULL,
+  status VARCHAR(20) NOT NULL DEFAULT 'pending'
+    CHECK (status IN ('pending', 'succeeded', 'failed', 'expired', 'cancelled')),
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE (provider, provider_ref)
+);
+
+CREATE INDEX payments_booking_idx ON payments (booking_id);
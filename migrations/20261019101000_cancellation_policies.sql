@@ -0,0 +1,42 @@
+-- A policy with no free window is non-refundable once its penalty is 100%.
+CREATE TABLE cancellation_policies (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  name VARCHAR(100) NOT NULL,
+  free_cancellation_days INT CHECK (free_cancellation_days >= 0),
+  penalty_percent NUMERIC(5,2) NOT NULL CHECK (penalty_percent BETWEEN 0 AND 100),
+  created_at TIMESTAMP DEFAULT now()
+);
+
+CREATE INDEX cancellation_policies_hotel_idx ON cancellation_policies (hotel_id);
+
+ALTER TABLE rooms ADD COLUMN cancellation_policy_id UUID
+  REFERENCES cancellation_policies(id) ON DELETE SET NULL;
+
+ALTER TABLE room_rates ADD COLUMN cancellation_policy_id UUID
+  REFERENCES cancellation_policies(id) ON DELETE SET NULL;
+
+-- Terms the booking was made under, unaffected by later policy edits.
+CREATE TABLE booking_cancellation_policies (
+  booking_id UUID PRIMARY KEY REFERENCES bookings(id) ON DELETE CASCADE,
+  policy_id UUID,
+  name VARCHAR(100) NOT NULL,
+  free_cancellation_days INT,
+  penalty_percent NUMERIC(5,2) NOT NULL
+);
+
+-- Written on every cancellation of a paid booking. Nothing is sent to the
+-- provider when the penalty keeps the whole amount, so provider_ref is empty.
+CREATE TABLE refunds (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+  payment_id UUID NOT NULL REFERENCES payments(id),
+  provider_ref VARCHAR(255),
+  amount NUMERIC(10,2) NOT NULL CHECK (amount >= 0),
+  penalty_amount NUMERIC(10,2) NOT NULL CHECK (penalty_amount >= 0),
+  currency CHAR(3) NOT NULL,
+  reason VARCHAR(50) NOT NULL,
+  created_at TIMESTAMP DEFAULT now()
+);
+
+CREATE INDEX refunds_booking_idx ON refunds (booking_id);
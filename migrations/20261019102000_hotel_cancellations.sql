@@ -0,0 +1,14 @@
+ALTER TABLE bookings ADD COLUMN cancelled_by UUID REFERENCES users(id);
+ALTER TABLE bookings ADD COLUMN cancellation_reason TEXT;
+
+CREATE TABLE notifications (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  kind VARCHAR(50) NOT NULL,
+  booking_id UUID REFERENCES bookings(id) ON DELETE CASCADE,
+  message TEXT NOT NULL,
+  created_at TIMESTAMP DEFAULT now(),
+  read_at TIMESTAMP
+);
+
+CREATE INDEX notifications_user_idx ON notifications (user_id, created_at DESC);
@@ -0,0 +1,20 @@
+ALTER TABLE bookings DROP CONSTRAINT bookings_status_check;
+ALTER TABLE bookings ADD CONSTRAINT bookings_status_check
+  CHECK (status IN (
+    'held', 'pending_payment', 'confirmed', 'checked_in', 'checked_out',
+    'no_show', 'cancelled', 'expired'
+  ));
+
+ALTER TABLE bookings ADD COLUMN checked_in_at TIMESTAMP;
+ALTER TABLE bookings ADD COLUMN checked_out_at TIMESTAMP;
+ALTER TABLE bookings ADD COLUMN no_show_at TIMESTAMP;
+
+-- A guest in the room keeps it until they check out; an early check-out or a
+-- no-show frees the remaining nights.
+ALTER TABLE bookings DROP CONSTRAINT bookings_no_overlap;
+ALTER TABLE bookings ADD CONSTRAINT bookings_no_overlap
+  EXCLUDE USING gist (
+    room_id WITH =,
+    daterange(check_in_date, check_out_date) WITH &&
+  )
+  WHERE (status IN ('held', 'pending_payment', 'confirmed', 'checked_in'));
@@ -0,0 +1,13 @@
+CREATE TABLE idempotency_keys (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  key VARCHAR(255) NOT NULL,
+  fingerprint CHAR(64) NOT NULL,
+  -- NULL while the first request is still being handled.
+  response_status INTEGER,
+  response_body TEXT,
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  UNIQUE (user_id, key)
+);
+
+CREATE INDEX idempotency_keys_created_idx ON idempotency_keys (created_at);
@@ -0,0 +1,14 @@
+-- NULL means only max_occupancy applies.
+ALTER TABLE rooms ADD COLUMN max_adults INTEGER CHECK (max_adults > 0);
+ALTER TABLE rooms ADD COLUMN max_children INTEGER CHECK (max_children >= 0);
+
+CREATE TABLE booking_guest_details (
+  booking_id UUID PRIMARY KEY REFERENCES bookings(id) ON DELETE CASCADE,
+  lead_guest_name VARCHAR(255) NOT NULL,
+  email VARCHAR(255),
+  phone VARCHAR(50),
+  adults INTEGER NOT NULL CHECK (adults > 0),
+  children_ages INTEGER[] NOT NULL DEFAULT '{}',
+  estimated_arrival TIME,
+  special_requests TEXT
+);
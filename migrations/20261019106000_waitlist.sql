@@ -0,0 +1,21 @@
+CREATE TABLE waitlist_entries (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  -- Either a specific room or any room of a type in the hotel.
+  room_id UUID REFERENCES rooms(id) ON DELETE CASCADE,
+  room_type VARCHAR(100),
+  check_in_date DATE NOT NULL,
+  check_out_date DATE NOT NULL,
+  guests INTEGER NOT NULL CHECK (guests > 0),
+  status VARCHAR(20) NOT NULL DEFAULT 'waiting'
+    CHECK (status IN ('waiting', 'offered', 'booked', 'lapsed', 'cancelled')),
+  offered_booking_id UUID REFERENCES bookings(id) ON DELETE SET NULL,
+  offered_at TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  CHECK ((room_id IS NULL) <> (room_type IS NULL)),
+  CHECK (check_in_date < check_out_date)
+);
+
+CREATE INDEX waitlist_entries_waiting_idx ON waitlist_entries (hotel_id, created_at)
+  WHERE status = 'waiting';
@@ -0,0 +1,35 @@
+CREATE TABLE promo_codes (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  code VARCHAR(50) NOT NULL,
+  -- NULL for platform-wide codes.
+  hotel_id UUID REFERENCES hotels(id) ON DELETE CASCADE,
+  created_by UUID NOT NULL REFERENCES users(id),
+  discount_type VARCHAR(10) NOT NULL CHECK (discount_type IN ('percent', 'fixed')),
+  amount NUMERIC(10,2) NOT NULL CHECK (amount > 0),
+  -- Fixed discounts only apply to bookings charged in this currency.
+  currency CHAR(3),
+  min_nights INTEGER NOT NULL DEFAULT 1 CHECK (min_nights > 0),
+  valid_from DATE,
+  valid_until DATE,
+  max_uses INTEGER CHECK (max_uses > 0),
+  max_uses_per_user INTEGER CHECK (max_uses_per_user > 0),
+  uses INTEGER NOT NULL DEFAULT 0,
+  active BOOLEAN NOT NULL DEFAULT true,
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  CHECK (discount_type = 'fixed' OR amount <= 100),
+  CHECK ((discount_type = 'fixed') = (currency IS NOT NULL)),
+  CHECK (valid_from IS NULL OR valid_until IS NULL OR valid_from <= valid_until)
+);
+
+CREATE UNIQUE INDEX promo_codes_code_idx ON promo_codes (upper(code));
+
+CREATE TABLE promo_redemptions (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  promo_code_id UUID NOT NULL REFERENCES promo_codes(id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  booking_id UUID NOT NULL UNIQUE REFERENCES bookings(id) ON DELETE CASCADE,
+  amount NUMERIC(10,2) NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE INDEX promo_redemptions_code_user_idx ON promo_redemptions (promo_code_id, user_id);
@@ -0,0 +1,13 @@
+-- Every change to a customer's points balance; the balance is the sum.
+CREATE TABLE loyalty_transactions (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  booking_id UUID REFERENCES bookings(id) ON DELETE SET NULL,
+  kind VARCHAR(20) NOT NULL CHECK (kind IN ('earned', 'redeemed', 'reversed')),
+  -- Positive when earned or given back, negative when spent or taken back.
+  points INTEGER NOT NULL CHECK (points <> 0),
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE INDEX loyalty_transactions_user_idx ON loyalty_transactions (user_id, created_at);
+CREATE INDEX loyalty_transactions_booking_idx ON loyalty_transactions (booking_id);
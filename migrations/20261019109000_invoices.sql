@@ -0,0 +1,65 @@
+-- Who invoices are issued by. Hotels without billing details invoice under
+-- their own name.
+CREATE TABLE hotel_billing_details (
+  hotel_id UUID PRIMARY KEY REFERENCES hotels(id) ON DELETE CASCADE,
+  legal_name TEXT NOT NULL,
+  address TEXT,
+  tax_id VARCHAR(50),
+  email VARCHAR(255),
+  updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+-- Last number handed out per hotel and document kind, so numbering has no
+-- gaps and never repeats.
+CREATE TABLE invoice_sequences (
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  kind VARCHAR(20) NOT NULL,
+  last_number INTEGER NOT NULL DEFAULT 0,
+  PRIMARY KEY (hotel_id, kind)
+);
+
+-- Issued invoices and credit notes. Everything printed on the document is
+-- copied here so it never changes after issue.
+CREATE TABLE invoices (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  booking_id UUID NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+  kind VARCHAR(20) NOT NULL CHECK (kind IN ('invoice', 'credit_note')),
+  number INTEGER NOT NULL,
+  document_number VARCHAR(30) NOT NULL,
+  -- The invoice a credit note corrects.
+  invoice_id UUID REFERENCES invoices(id) ON DELETE CASCADE,
+  -- A void invoice was replaced after the booking changed.
+  status VARCHAR(10) NOT NULL DEFAULT 'issued' CHECK (status IN ('issued', 'void')),
+  reason TEXT,
+  seller_name TEXT NOT NULL,
+  seller_address TEXT,
+  seller_tax_id VARCHAR(50),
+  seller_email VARCHAR(255),
+  customer_name TEXT NOT NULL,
+  customer_email VARCHAR(255) NOT NULL,
+  lead_guest_name TEXT,
+  room_number VARCHAR(50) NOT NULL,
+  check_in_date DATE NOT NULL,
+  check_out_date DATE NOT NULL,
+  guests INTEGER NOT NULL,
+  currency CHAR(3) NOT NULL,
+  total NUMERIC(10,2) NOT NULL,
+  issued_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  UNIQUE (hotel_id, kind, number),
+  CHECK ((kind = 'credit_note') = (invoice_id IS NOT NULL))
+);
+
+CREATE INDEX invoices_booking_idx ON invoices (booking_id, issued_at);
+
+CREATE TABLE invoice_lines (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  kind VARCHAR(20) NOT NULL CHECK (kind IN ('night', 'discount', 'tax', 'fee', 'refund')),
+  description TEXT NOT NULL,
+  amount NUMERIC(10,2) NOT NULL,
+  -- Already part of the nightly rates, so shown but not added to the total.
+  inclusive BOOLEAN NOT NULL DEFAULT false,
+  UNIQUE (invoice_id, position)
+);
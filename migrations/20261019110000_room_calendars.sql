@@ -0,0 +1,29 @@
+-- Secret token that lets other platforms read a room's calendar without
+-- signing in. Rotating it invalidates the old feed URL.
+CREATE TABLE room_calendar_feeds (
+  room_id UUID PRIMARY KEY REFERENCES rooms(id) ON DELETE CASCADE,
+  token VARCHAR(64) NOT NULL UNIQUE,
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+-- Calendars of other platforms the room is also sold on. Their events are
+-- copied into room_blocks on every sync.
+CREATE TABLE room_calendar_imports (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  hotel_id UUID NOT NULL REFERENCES hotels(id) ON DELETE CASCADE,
+  room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+  name VARCHAR(100) NOT NULL,
+  url TEXT NOT NULL,
+  created_by UUID NOT NULL REFERENCES users(id),
+  last_synced_at TIMESTAMP,
+  last_error TEXT,
+  created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  UNIQUE (room_id, url)
+);
+
+-- Blocks created by an import, keyed by the event's UID in that calendar.
+ALTER TABLE room_blocks
+  ADD COLUMN import_id UUID REFERENCES room_calendar_imports(id) ON DELETE CASCADE,
+  ADD COLUMN external_uid TEXT,
+  ADD CONSTRAINT room_blocks_import_uid_key UNIQUE (import_id, external_uid),
+  ADD CHECK ((import_id IS NULL) = (external_uid IS NULL));
@@ -0,0 +1,110 @@
+-- Booking and availability changes are published on the hotel_events
+-- channel from triggers, so every code path that writes bookings or blocks
+-- is covered and listeners only hear about committed changes. Payloads are
+-- JSON with a type, hotelId and roomId; "to" dates are exclusive.
+
+CREATE FUNCTION publish_hotel_event(payload JSON) RETURNS VOID AS $$
+BEGIN
+  PERFORM pg_notify('hotel_events', payload::text);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION publish_booking_events() RETURNS TRIGGER AS $$
+DECLARE
+  -- Must match lifecycle::OCCUPYING and bookings_no_overlap.
+  occupying TEXT[] := ARRAY['held', 'pending_payment', 'confirmed', 'checked_in'];
+  was_occupying BOOLEAN := TG_OP <> 'INSERT' AND OLD.status = ANY(occupying);
+  is_occupying BOOLEAN := TG_OP <> 'DELETE' AND NEW.status = ANY(occupying);
+  moved BOOLEAN := TG_OP = 'UPDATE'
+    AND (OLD.room_id, OLD.check_in_date, OLD.check_out_date)
+      IS DISTINCT FROM (NEW.room_id, NEW.check_in_date, NEW.check_out_date);
+BEGIN
+  IF TG_OP <> 'DELETE' AND NEW.status = 'confirmed'
+     AND (TG_OP = 'INSERT' OR OLD.status IN ('held', 'pending_payment')) THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'booking.created',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'bookingId', NEW.id,
+      'checkInDate', NEW.check_in_date,
+      'checkOutDate', NEW.check_out_date,
+      'guests', NEW.guests
+    ));
+  END IF;
+
+  IF TG_OP = 'UPDATE' AND NEW.status = 'cancelled' AND OLD.status IS DISTINCT FROM 'cancelled' THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'booking.cancelled',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'bookingId', NEW.id,
+      'checkInDate', NEW.check_in_date,
+      'checkOutDate', NEW.check_out_date,
+      'reason', NEW.cancellation_reason
+    ));
+  END IF;
+
+  IF was_occupying AND (NOT is_occupying OR moved) THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'availability.changed',
+      'hotelId', OLD.hotel_id,
+      'roomId', OLD.room_id,
+      'from', OLD.check_in_date,
+      'to', OLD.check_out_date
+    ));
+  END IF;
+
+  IF is_occupying AND (NOT was_occupying OR moved) THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'availability.changed',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'from', NEW.check_in_date,
+      'to', NEW.check_out_date
+    ));
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER bookings_publish_events
+  AFTER INSERT OR UPDATE OR DELETE ON bookings
+  FOR EACH ROW EXECUTE FUNCTION publish_booking_events();
+
+CREATE FUNCTION publish_block_events() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP <> 'INSERT' THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'availability.changed',
+      'hotelId', OLD.hotel_id,
+      'roomId', OLD.room_id,
+      'from', OLD.start_date,
+      'to', OLD.end_date + 1
+    ));
+  END IF;
+
+  IF TG_OP <> 'DELETE' THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'availability.changed',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'from', NEW.start_date,
+      'to', NEW.end_date + 1
+    ));
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Calendar syncs rewrite imported blocks every run; only real moves count.
+CREATE TRIGGER room_blocks_publish_events
+  AFTER INSERT OR DELETE ON room_blocks
+  FOR EACH ROW EXECUTE FUNCTION publish_block_events();
+
+CREATE TRIGGER room_blocks_publish_moves
+  AFTER UPDATE ON room_blocks
+  FOR EACH ROW
+  WHEN ((OLD.room_id, OLD.start_date, OLD.end_date) IS DISTINCT FROM (NEW.room_id, NEW.start_date, NEW.end_date))
+  EXECUTE FUNCTION publish_block_events();
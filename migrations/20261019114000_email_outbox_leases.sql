@@ -0,0 +1,3 @@
+-- Set while a worker is sending the email, so another worker leaves it
+-- alone. A worker that dies mid-send only delays it until the lease ends.
+ALTER TABLE email_outbox ADD COLUMN locked_until TIMESTAMP;
@@ -0,0 +1,3 @@
+-- Set while a worker is posting the delivery, so another worker leaves it
+-- alone. A worker that dies mid-post only delays it until the lease ends.
+ALTER TABLE webhook_deliveries ADD COLUMN locked_until TIMESTAMP;
@@ -0,0 +1,5 @@
+-- Points given back when a modification leaves a booking cheaper than the
+-- points that were spent on it.
+ALTER TABLE loyalty_transactions DROP CONSTRAINT loyalty_transactions_kind_check;
+ALTER TABLE loyalty_transactions
+  ADD CONSTRAINT loyalty_transactions_kind_check CHECK (kind IN ('earned', 'redeemed', 'returned', 'reversed'));
@@ -0,0 +1,2 @@
+-- When the latest send was tried, so the wait before the next can be seen.
+ALTER TABLE email_outbox ADD COLUMN last_attempt_at TIMESTAMP;
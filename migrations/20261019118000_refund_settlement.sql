@@ -0,0 +1,10 @@
+-- Refunds are written pending with the cancellation and sent to the provider
+-- once it has committed, so no gateway call happens under the booking's lock.
+-- provider_ref is filled in when the provider accepts the refund.
+ALTER TABLE refunds
+  ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'succeeded'
+    CHECK (status IN ('pending', 'succeeded')),
+  ADD COLUMN last_error TEXT,
+  ADD COLUMN locked_until TIMESTAMP;
+
+CREATE INDEX refunds_pending_idx ON refunds (created_at) WHERE status = 'pending';
@@ -0,0 +1,6 @@
+-- When the provider was told to void an intent whose booking expired or was
+-- cancelled before it was captured.
+ALTER TABLE payments ADD COLUMN voided_at TIMESTAMP;
+
+CREATE INDEX payments_unvoided_idx ON payments (updated_at)
+  WHERE status IN ('expired', 'cancelled') AND voided_at IS NULL;
@@ -0,0 +1,63 @@
+-- NOTIFY payloads are capped at 8000 bytes and an overlong one fails the
+-- statement that fired the trigger, so a long hotel cancellation reason
+-- could block the cancellation itself. The event carries only the start of
+-- the reason; the booking has all of it.
+
+CREATE OR REPLACE FUNCTION publish_booking_events() RETURNS TRIGGER AS $$
+DECLARE
+  -- Must match lifecycle::OCCUPYING and bookings_no_overlap.
+  occupying TEXT[] := ARRAY['held', 'pending_payment', 'confirmed', 'checked_in'];
+  was_occupying BOOLEAN := TG_OP <> 'INSERT' AND OLD.status = ANY(occupying);
+  is_occupying BOOLEAN := TG_OP <> 'DELETE' AND NEW.status = ANY(occupying);
+  moved BOOLEAN := TG_OP = 'UPDATE'
+    AND (OLD.room_id, OLD.check_in_date, OLD.check_out_date)
+      IS DISTINCT FROM (NEW.room_id, NEW.check_in_date, NEW.check_out_date);
+BEGIN
+  IF TG_OP <> 'DELETE' AND NEW.status = 'confirmed'
+     AND (TG_OP = 'INSERT' OR OLD.status IN ('held', 'pending_payment')) THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'booking.created',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'bookingId', NEW.id,
+      'checkInDate', NEW.check_in_date,
+      'checkOutDate', NEW.check_out_date,
+      'guests', NEW.guests
+    ));
+  END IF;
+
+  IF TG_OP = 'UPDATE' AND NEW.status = 'cancelled' AND OLD.status IS DISTINCT FROM 'cancelled' THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'booking.cancelled',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'bookingId', NEW.id,
+      'checkInDate', NEW.check_in_date,
+      'checkOutDate', NEW.check_out_date,
+      'reason', left(NEW.cancellation_reason, 500)
+    ));
+  END IF;
+
+  IF was_occupying AND (NOT is_occupying OR moved) THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'availability.changed',
+      'hotelId', OLD.hotel_id,
+      'roomId', OLD.room_id,
+      'from', OLD.check_in_date,
+      'to', OLD.check_out_date
+    ));
+  END IF;
+
+  IF is_occupying AND (NOT was_occupying OR moved) THEN
+    PERFORM publish_hotel_event(json_build_object(
+      'type', 'availability.changed',
+      'hotelId', NEW.hotel_id,
+      'roomId', NEW.room_id,
+      'from', NEW.check_in_date,
+      'to', NEW.check_out_date
+    ));
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,53 @@
+use chrono::Duration;
+use sqlx::PgConnection;
+use std::env;
+use uuid::Uuid;
+
+use crate::{handlers::loyalty, lifecycle::BookingStatus, pricing::promotions};
+
+/// How long a hold keeps a room, from `HOLD_TTL_SECONDS` (default 15 minutes).
+pub fn hold_ttl() -> Duration {
+    let seconds = env::var("HOLD_TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15 * 60);
+
+    Duration::seconds(seconds)
+}
+
+/// Marks lapsed holds and unpaid bookings as expired, for one room or for
+/// every room. Payments still pending on them are marked for the settlement
+/// job to void with the provider, and loyalty points and promo code uses
+/// spent on them are given back. Returns how many bookings were released.
+pub async fn release_expired(conn: &mut PgConnection, room_id: Option<Uuid>) -> sqlx::Result<usize> {
+    let expired = sqlx::query_scalar!(
+        r#"
+        WITH expired AS (
+            UPDATE bookings
+            SET status = 'expired'
+            WHERE status = ANY($2)
+            AND hold_expires_at <= now() AT TIME ZONE 'utc'
+            AND ($1::uuid IS NULL OR room_id = $1)
+            RETURNING id
+        ),
+        voided AS (
+            UPDATE payments
+            SET status = 'expired', updated_at = now()
+            WHERE booking_id IN (SELECT id FROM expired)
+            AND status = 'pending'
+        )
+        SELECT id AS "id!" FROM expired
+        "#,
+        room_id,
+        &BookingStatus::sources(BookingStatus::Expired) as &[&str]
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for &booking_id in &expired {
+        loyalty::reverse_booking(conn, booking_id).await?;
+        promotions::release(conn, booking_id).await?;
+    }
+
+    Ok(expired.len())
+}
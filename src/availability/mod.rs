@@ -0,0 +1,4 @@
+pub mod holds;
+pub mod restrictions;
+
+pub use restrictions::{RestrictionKind, Violation};
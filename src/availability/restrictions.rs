@@ -0,0 +1,162 @@
+use chrono::{Datelike, NaiveDate};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RestrictionKind {
+    MinNights,
+    MaxNights,
+    ClosedToArrival,
+    ClosedToDeparture,
+    BookingHorizon,
+}
+
+impl RestrictionKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "min_nights" => Some(Self::MinNights),
+            "max_nights" => Some(Self::MaxNights),
+            "closed_to_arrival" => Some(Self::ClosedToArrival),
+            "closed_to_departure" => Some(Self::ClosedToDeparture),
+            "booking_horizon" => Some(Self::BookingHorizon),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MinNights => "min_nights",
+            Self::MaxNights => "max_nights",
+            Self::ClosedToArrival => "closed_to_arrival",
+            Self::ClosedToDeparture => "closed_to_departure",
+            Self::BookingHorizon => "booking_horizon",
+        }
+    }
+
+    /// Whether the rule carries a number of nights or days.
+    pub fn has_value(&self) -> bool {
+        !matches!(self, Self::ClosedToArrival | Self::ClosedToDeparture)
+    }
+}
+
+pub struct Restriction {
+    pub kind: RestrictionKind,
+    pub value: Option<i32>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub days_of_week: Option<Vec<i32>>,
+}
+
+impl Restriction {
+    fn applies_to(&self, date: NaiveDate) -> bool {
+        let in_range = self.start_date.is_none_or(|s| date >= s)
+            && self.end_date.is_none_or(|e| date <= e);
+
+        let on_day = match &self.days_of_week {
+            Some(days) => days.contains(&(date.weekday().number_from_monday() as i32)),
+            None => true,
+        };
+
+        in_range && on_day
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    MinStay,
+    MaxStay,
+    ClosedToArrival,
+    ClosedToDeparture,
+    BookingHorizon,
+}
+
+impl Violation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MinStay => "MIN_STAY_NOT_MET",
+            Self::MaxStay => "MAX_STAY_EXCEEDED",
+            Self::ClosedToArrival => "CLOSED_TO_ARRIVAL",
+            Self::ClosedToDeparture => "CLOSED_TO_DEPARTURE",
+            Self::BookingHorizon => "BEYOND_BOOKING_HORIZON",
+        }
+    }
+}
+
+/// Every rule the stay breaks, in a stable order.
+///
+/// Length-of-stay and horizon rules are matched against the arrival date,
+/// closed-to-departure against the departure date. Hotel-wide and room
+/// rules are combined, so the most restrictive one wins.
+pub fn check_stay(
+    rules: &[Restriction],
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    today: NaiveDate,
+) -> Vec<Violation> {
+    let nights = (check_out - check_in).num_days();
+    let lead_days = (check_in - today).num_days();
+
+    let mut violations = Vec::new();
+
+    for rule in rules {
+        let date = match rule.kind {
+            RestrictionKind::ClosedToDeparture => check_out,
+            _ => check_in,
+        };
+
+        if !rule.applies_to(date) {
+            continue;
+        }
+
+        let value = rule.value.unwrap_or(0) as i64;
+
+        let violation = match rule.kind {
+            RestrictionKind::MinNights if nights < value => Violation::MinStay,
+            RestrictionKind::MaxNights if nights > value => Violation::MaxStay,
+            RestrictionKind::ClosedToArrival => Violation::ClosedToArrival,
+            RestrictionKind::ClosedToDeparture => Violation::ClosedToDeparture,
+            RestrictionKind::BookingHorizon if lead_days > value => Violation::BookingHorizon,
+            _ => continue,
+        };
+
+        if !violations.contains(&violation) {
+            violations.push(violation);
+        }
+    }
+
+    violations.sort_by_key(|v| *v as u8);
+    violations
+}
+
+/// Hotel-wide rules plus the room's own rules.
+pub async fn load_restrictions(
+    conn: &mut PgConnection,
+    hotel_id: Uuid,
+    room_id: Uuid,
+) -> Vec<Restriction> {
+    sqlx::query!(
+        r#"
+        SELECT kind, value, start_date, end_date, days_of_week
+        FROM stay_restrictions
+        WHERE hotel_id = $1
+        AND (room_id IS NULL OR room_id = $2)
+        ORDER BY created_at, id
+        "#,
+        hotel_id,
+        room_id
+    )
+    .fetch_all(conn)
+    .await
+    .unwrap()
+    .into_iter()
+    .filter_map(|r| {
+        Some(Restriction {
+            kind: RestrictionKind::parse(&r.kind)?,
+            value: r.value,
+            start_date: r.start_date,
+            end_date: r.end_date,
+            days_of_week: r.days_of_week,
+        })
+    })
+    .collect()
+}
@@ -10,4 +10,16 @@ pub async fn create_pool() -> PgPool {
         .connect(&database_url)
         .await
         .expect("Failed to connect to Postgres")
-}
\ No newline at end of file
+}
+
+/// Whether a query failed because it would have violated `constraint`.
+pub fn violates_constraint(err: &sqlx::Error, constraint: &str) -> bool {
+    match err {
+        sqlx::Error::Database(e) => e.constraint() == Some(constraint),
+        _ => false,
+    }
+}
+
+/// Name of the exclusion constraint that keeps the occupying stays of one
+/// room (see `lifecycle::OCCUPYING`) from overlapping.
+pub const BOOKING_OVERLAP_CONSTRAINT: &str = "bookings_no_overlap";
@@ -0,0 +1,122 @@
+use serde::Deserialize;
+use sqlx::{postgres::PgListener, PgPool};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Postgres channel the `hotel_events` triggers publish on.
+const CHANNEL: &str = "hotel_events";
+
+/// Events a slow subscriber may fall behind by before it is told to resync.
+const BUFFER: usize = 1024;
+
+/// A booking or availability change at one hotel, as published by the
+/// database triggers.
+#[derive(Clone, Debug)]
+pub struct HotelEvent {
+    /// `None` for a resync, which goes to every subscriber.
+    pub hotel_id: Option<Uuid>,
+    pub kind: String,
+    /// The trigger's JSON payload, passed on to clients unchanged.
+    pub payload: String,
+}
+
+impl HotelEvent {
+    /// Tells a subscriber it may have missed events and should refetch.
+    pub fn resync() -> Self {
+        Self {
+            hotel_id: None,
+            kind: "resync".to_string(),
+            payload: r#"{"type":"resync"}"#.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    r#type: String,
+    hotel_id: Uuid,
+}
+
+/// Fans out the changes every server instance writes. Each instance keeps
+/// one LISTEN connection and re-broadcasts to its own subscribers.
+#[derive(Clone)]
+pub struct HotelEvents {
+    sender: broadcast::Sender<HotelEvent>,
+}
+
+impl HotelEvents {
+    pub fn subscribe(&self) -> broadcast::Receiver<HotelEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Starts listening for hotel events in the background.
+pub fn listen(pool: PgPool) -> HotelEvents {
+    let (sender, _) = broadcast::channel(BUFFER);
+
+    tokio::spawn(relay(pool, sender.clone()));
+
+    HotelEvents { sender }
+}
+
+async fn relay(pool: PgPool, sender: broadcast::Sender<HotelEvent>) {
+    let mut reconnecting = false;
+
+    loop {
+        let mut listener = match PgListener::connect_with(&pool).await {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("hotel events: {e}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+
+        if let Err(e) = listener.listen(CHANNEL).await {
+            eprintln!("hotel events: {e}");
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+
+        // Changes made while the listener was down were missed.
+        if reconnecting {
+            let _ = sender.send(HotelEvent::resync());
+        }
+
+        reconnecting = true;
+
+        loop {
+            let notification = match listener.try_recv().await {
+                Ok(Some(v)) => v,
+                // The connection dropped and will be re-established on the
+                // next call. Anything published meanwhile is lost, so every
+                // subscriber is told to reload.
+                Ok(None) => {
+                    let _ = sender.send(HotelEvent::resync());
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("hotel events: {e}");
+                    break;
+                }
+            };
+
+            let envelope: Envelope = match serde_json::from_str(notification.payload()) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("hotel events: bad payload: {e}");
+                    continue;
+                }
+            };
+
+            // No subscribers is not an error.
+            let _ = sender.send(HotelEvent {
+                hotel_id: Some(envelope.hotel_id),
+                kind: envelope.r#type,
+                payload: notification.payload().to_string(),
+            });
+        }
+    }
+}
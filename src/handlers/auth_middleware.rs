@@ -17,6 +17,7 @@ pub struct Claims {
     pub exp: usize,
 }
 
+#[derive(Clone)]
 pub struct AuthUser {
     pub user_id: Uuid,
     pub role: String,
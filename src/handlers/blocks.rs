@@ -0,0 +1,303 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    lifecycle,
+    models::{
+        blocks::{CreateRoomBlockRequest, RoomBlockResponse, AffectedBookingResponse,
+        DeleteRoomBlockResponse},
+        response::ApiResponse,
+    },
+};
+
+/// Takes a room out of service. Overlapping confirmed bookings reject the
+/// block with `BOOKINGS_AFFECTED` unless `force` is set; either way the
+/// affected bookings are returned so the owner can follow up on them.
+pub async fn create_block(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+    Json(payload): Json<CreateRoomBlockRequest>,
+) -> Response {
+
+    if auth.role != "owner" {
+        return error::<RoomBlockResponse>(StatusCode::FORBIDDEN, "FORBIDDEN").into_response();
+    }
+
+    let start_date = payload
+        .startDate
+        .as_deref()
+        .and_then(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok());
+    let end_date = payload
+        .endDate
+        .as_deref()
+        .and_then(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok());
+
+    let (start_date, end_date) = match (start_date, end_date) {
+        (Some(s), Some(e)) if s <= e => (s, e),
+        _ => return error::<RoomBlockResponse>(StatusCode::BAD_REQUEST, "INVALID_DATES").into_response(),
+    };
+
+    let reason = match payload.reason {
+        Some(v) if !v.trim().is_empty() => v,
+        _ => return error::<RoomBlockResponse>(StatusCode::BAD_REQUEST, "INVALID_REQUEST").into_response(),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let (hotel_id, room_id) = match lock_owned_room(&mut tx, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            return error::<RoomBlockResponse>(status, code).into_response();
+        }
+    };
+
+    // Block dates are inclusive, so the last blocked night ends the day after.
+    let affected = sqlx::query!(
+        r#"
+        SELECT id, user_id, check_in_date, check_out_date, guests
+        FROM bookings
+        WHERE room_id = $1
+        AND status = ANY($4)
+        AND check_in_date <= $3
+        AND check_out_date > $2
+        ORDER BY check_in_date
+        "#,
+        room_id,
+        start_date,
+        end_date,
+        &lifecycle::OCCUPYING[..] as &[&str]
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+
+    let affected: Vec<AffectedBookingResponse> = affected
+        .into_iter()
+        .map(|b| AffectedBookingResponse {
+            id: b.id.to_string(),
+            userId: b.user_id.to_string(),
+            checkInDate: b.check_in_date.to_string(),
+            checkOutDate: b.check_out_date.to_string(),
+            guests: b.guests,
+        })
+        .collect();
+
+    if !affected.is_empty() && !payload.force.unwrap_or(false) {
+        tx.rollback().await.unwrap();
+        return (
+            StatusCode::CONFLICT,
+            Json(ApiResponse::error_with_data("BOOKINGS_AFFECTED", affected)),
+        )
+            .into_response();
+    }
+
+    let block = sqlx::query!(
+        r#"
+        INSERT INTO room_blocks (hotel_id, room_id, start_date, end_date, reason, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, created_at
+        "#,
+        hotel_id,
+        room_id,
+        start_date,
+        end_date,
+        reason,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+
+    tx.commit().await.unwrap();
+
+    let response = RoomBlockResponse {
+        id: block.id.to_string(),
+        roomId: room_id.to_string(),
+        startDate: start_date.to_string(),
+        endDate: end_date.to_string(),
+        reason,
+        createdAt: block
+            .created_at
+            .map(|d| d.and_utc().to_rfc3339())
+            .unwrap_or_default(),
+        affectedBookings: affected,
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(response)),
+    )
+        .into_response()
+}
+
+pub async fn list_blocks(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<Vec<RoomBlockResponse>>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let blocks = sqlx::query!(
+        r#"
+        SELECT id, start_date, end_date, reason, created_at
+        FROM room_blocks
+        WHERE room_id = $1
+        ORDER BY start_date
+        "#,
+        room_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = blocks
+        .into_iter()
+        .map(|b| RoomBlockResponse {
+            id: b.id.to_string(),
+            roomId: room_id.to_string(),
+            startDate: b.start_date.to_string(),
+            endDate: b.end_date.to_string(),
+            reason: b.reason,
+            createdAt: b
+                .created_at
+                .map(|d| d.and_utc().to_rfc3339())
+                .unwrap_or_default(),
+            affectedBookings: Vec::new(),
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn delete_block(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id, block_id)): Path<(String, String, String)>,
+) -> (StatusCode, Json<ApiResponse<DeleteRoomBlockResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let block_id = match Uuid::parse_str(&block_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "BLOCK_NOT_FOUND"),
+    };
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let deleted = sqlx::query!(
+        "DELETE FROM room_blocks WHERE id = $1 AND room_id = $2",
+        block_id,
+        room_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        return error(StatusCode::NOT_FOUND, "BLOCK_NOT_FOUND");
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteRoomBlockResponse {
+            id: block_id.to_string(),
+        })),
+    )
+}
+
+/// Locks the room the same way `create_booking` does, so a block and a
+/// booking for the same room can never be written concurrently.
+async fn lock_owned_room(
+    tx: &mut Transaction<'_, Postgres>,
+    auth: &AuthUser,
+    hotel_id: &str,
+    room_id: &str,
+) -> Result<(Uuid, Uuid), (StatusCode, &'static str)> {
+    let hotel_id = Uuid::parse_str(hotel_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"))?;
+    let room_id = Uuid::parse_str(room_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"))?;
+
+    let room = sqlx::query!(
+        r#"
+        SELECT r.id, h.owner_id
+        FROM rooms r
+        JOIN hotels h ON h.id = r.hotel_id
+        WHERE r.id = $1 AND r.hotel_id = $2
+        FOR UPDATE OF r
+        "#,
+        room_id,
+        hotel_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .unwrap();
+
+    match room {
+        Some(r) if r.owner_id == auth.user_id => Ok((hotel_id, r.id)),
+        Some(_) => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => Err((StatusCode::NOT_FOUND, "ROOM_NOT_FOUND")),
+    }
+}
+
+pub(crate) async fn find_owned_room(
+    pool: &PgPool,
+    auth: &AuthUser,
+    hotel_id: &str,
+    room_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let hotel_id = Uuid::parse_str(hotel_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"))?;
+    let room_id = Uuid::parse_str(room_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"))?;
+
+    let room = sqlx::query!(
+        r#"
+        SELECT r.id, h.owner_id
+        FROM rooms r
+        JOIN hotels h ON h.id = r.hotel_id
+        WHERE r.id = $1 AND r.hotel_id = $2
+        "#,
+        room_id,
+        hotel_id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap();
+
+    match room {
+        Some(r) if r.owner_id == auth.user_id => Ok(r.id),
+        Some(_) => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => Err((StatusCode::NOT_FOUND, "ROOM_NOT_FOUND")),
+    }
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -1,33 +1,550 @@
 use axum::{extract::{State, Query, Path}, http::StatusCode, Json};
-use chrono::{NaiveDate, Utc, Duration};
-use sqlx::PgPool;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use sqlx::{types::BigDecimal, PgPool, Postgres, Transaction};
+use std::{collections::HashMap, sync::Arc};
 use uuid::Uuid;
-use sqlx::types::BigDecimal;
 
 use crate::{
-    handlers::auth_middleware::AuthUser,
+    availability::{holds, restrictions, Violation},
+    db,
+    invoices,
+    lifecycle::{self, BookingStatus},
+    mail::{outbox, Template},
+    handlers::{
+        auth_middleware::AuthUser,
+        guest_details::{self, GuestDetails, RoomOccupancy},
+        loyalty,
+        notifications,
+        payments::{self, CapturedPayment, StartedPayment},
+        waitlist,
+    },
     models::{
         bookings::{CreateBookingRequest, BookingResponse, BookingListQuery, BookingListResponse,
-        CancelBookingResponse},
+        CancelBookingResponse, CancelHotelBookingRequest, ConfirmBookingRequest, ConfirmBookingResponse, ModifyBookingRequest, ModifyBookingResponse, NightlyRateResponse,
+        QuoteResponse, LineItemResponse},
+        exchange_rates::CurrencyQuery,
+        cancellation_policies::CancellationTermsResponse,
+        payments::PaymentResponse,
         response::ApiResponse,
     },
+    payments::PaymentGateway,
+    pricing::{
+        cancellation::{self, Refund},
+        promotions,
+        CancellationPolicy,
+        price_stay,
+        Converter,
+        LineItem,
+        NightlyRate,
+        PromoCode,
+        Stay,
+        StayPrice,
+    },
+    webhooks::{self, Event},
 };
 
+/// Books a room and charges for it. The booking stays `pending_payment`
+/// until the gateway reports a capture, or expires with the payment window.
 pub async fn create_booking(
     auth: AuthUser,
     State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
     Json(payload): Json<CreateBookingRequest>,
 ) -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
 
+    let method = payload.paymentMethod.as_deref();
+
+    let started = match start_checkout(&pool, gateway.as_ref(), &auth, &payload, method).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let booked = match book_room(&mut tx, &auth, &payload, None, Checkout::Pay(&started)).await {
+        Ok(v) => v,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            payments::abandon(gateway.as_ref(), &started).await;
+            return error(status, code);
+        }
+    };
+
+    tx.commit().await.unwrap();
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(booking_response(&auth, &payload, booked))),
+    )
+}
+
+/// Holds a room for a few minutes while the guest finishes checking out.
+pub async fn hold_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(payload): Json<CreateBookingRequest>,
+) -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let booked = match book_room(&mut tx, &auth, &payload, None, Checkout::Hold).await {
+        Ok(v) => v,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            return error(status, code);
+        }
+    };
+
+    tx.commit().await.unwrap();
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(booking_response(&auth, &payload, booked))),
+    )
+}
+
+/// Pays for a live hold at the price it was held at. The room stays held
+/// under the same deadline while an asynchronous payment completes.
+pub async fn confirm_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
+    Path(booking_id): Path<String>,
+    payload: Option<Json<ConfirmBookingRequest>>,
+) -> (StatusCode, Json<ApiResponse<ConfirmBookingResponse>>) {
+
+    if auth.role != "customer" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let booking_id = match Uuid::parse_str(&booking_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let booking = sqlx::query!(
+        "SELECT user_id, status, hold_expires_at, total_price, currency FROM bookings WHERE id = $1",
+        booking_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let booking = match booking {
+        Some(b) => b,
+        None => return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND"),
+    };
+
+    if booking.user_id != auth.user_id {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    if let Err(code) = check_live_hold(booking.status.as_deref(), booking.hold_expires_at) {
+        return error(StatusCode::BAD_REQUEST, code);
+    }
+
+    let method = payload.as_ref().and_then(|Json(p)| p.paymentMethod.as_deref());
+
+    let started = match payments::create_intent(
+        gateway.as_ref(),
+        booking_id,
+        &booking.total_price,
+        &booking.currency,
+        method,
+    )
+    .await
+    {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    // The hold may have lapsed or been cancelled while the gateway was busy.
+    let current = sqlx::query!(
+        "SELECT status, hold_expires_at FROM bookings WHERE id = $1 FOR UPDATE",
+        booking_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+
+    if let Err(code) = check_live_hold(current.status.as_deref(), current.hold_expires_at) {
+        tx.rollback().await.unwrap();
+        payments::abandon(gateway.as_ref(), &started).await;
+        return error(StatusCode::BAD_REQUEST, code);
+    }
+
+    sqlx::query!(
+        "UPDATE bookings SET status = 'pending_payment' WHERE id = $1",
+        booking_id
+    )
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+
+    let payment = payments::record_payment(&mut tx, &started).await;
+
+    tx.commit().await.unwrap();
+
+    let captured = payment.status == "succeeded";
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(ConfirmBookingResponse {
+            id: booking_id.to_string(),
+            status: if captured { "confirmed" } else { "pending_payment" }.to_string(),
+            holdExpiresAt: if captured {
+                None
+            } else {
+                booking.hold_expires_at.map(|d| d.and_utc().to_rfc3339())
+            },
+            payment,
+        })),
+    )
+}
+
+/// Whether a booking can move on to payment: it must be held, and the hold
+/// must not have lapsed yet even if the sweeper has not caught up.
+fn check_live_hold(status: Option<&str>, hold_expires_at: Option<NaiveDateTime>) -> Result<(), &'static str> {
+    let status = BookingStatus::from_column(status);
+
+    if status == BookingStatus::Held && hold_expires_at.is_none_or(|e| e <= Utc::now().naive_utc()) {
+        return Err("HOLD_EXPIRED");
+    }
+
+    match status.transition(BookingStatus::PendingPayment) {
+        Err("INVALID_STATUS_TRANSITION") => Err("BOOKING_NOT_HELD"),
+        other => other,
+    }
+}
+
+/// How a booking written by `book_room` is secured.
+pub(crate) enum Checkout<'a> {
+    /// Keep the room for the hold TTL without charging yet.
+    Hold,
+    /// Charge with an intent from `start_checkout`; the room is kept for the
+    /// hold TTL while payment is pending.
+    Pay(&'a StartedPayment),
+}
+
+/// A booking row written by `book_room`, not yet committed.
+pub(crate) struct BookedRoom {
+    pub id: Uuid,
+    pub prepared: PreparedBooking,
+    pub status: &'static str,
+    pub hold_expires_at: Option<NaiveDateTime>,
+    pub payment: Option<PaymentResponse>,
+}
+
+/// Validates, prices and inserts one booking, then holds it or starts its
+/// payment. Shared by single bookings, holds and multi-room reservations; on
+/// error the caller must roll the transaction back.
+pub(crate) async fn book_room(
+    tx: &mut Transaction<'_, Postgres>,
+    auth: &AuthUser,
+    payload: &CreateBookingRequest,
+    reservation_id: Option<Uuid>,
+    checkout: Checkout<'_>,
+) -> Result<BookedRoom, (StatusCode, &'static str)> {
+
+    let prepared = prepare_booking(tx, auth, payload, None).await?;
+
+    check_bookable(&prepared)?;
+
+    let price = &prepared.price;
+
+    
+    // The intent was created for the stay as priced before this transaction;
+    // the rates may have moved since.
+    let (booking_id, status) = match checkout {
+        Checkout::Hold => (Uuid::new_v4(), "held"),
+        Checkout::Pay(started) if started.amount != price.total || started.currency != prepared.currency => {
+            return Err((StatusCode::CONFLICT, "PRICE_CHANGED"));
+        }
+        Checkout::Pay(started) => (started.booking_id, "pending_payment"),
+    };
+    let hold_expires_at = Utc::now().naive_utc() + holds::hold_ttl();
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO bookings (
+            id,
+            user_id,
+            room_id,
+            hotel_id,
+            reservation_id,
+            check_in_date,
+            check_out_date,
+            guests,
+            subtotal,
+            total_price,
+            currency,
+            status,
+            hold_expires_at
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
+        "#,
+        booking_id,
+        auth.user_id,
+        prepared.room_id,
+        prepared.hotel_id,
+        reservation_id,
+        prepared.check_in,
+        prepared.check_out,
+        payload.guests,
+        price.subtotal,
+        price.total,
+        prepared.currency,
+        status,
+        hold_expires_at
+    )
+    .execute(&mut **tx)
+    .await;
+
+    // The room lock makes this unreachable through book_room alone, but
+    // the constraint also guards every other path that writes bookings.
+    match inserted {
+        Ok(_) => {}
+        Err(e) if db::violates_constraint(&e, db::BOOKING_OVERLAP_CONSTRAINT) => {
+            return Err((StatusCode::BAD_REQUEST, "ROOM_NOT_AVAILABLE"));
+        }
+        Err(e) => {
+            eprintln!("booking insert failed: {e}");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"));
+        }
+    }
+
+    insert_price_details(tx, booking_id, price).await;
+
+    if let Some(promo) = &prepared.promo {
+        promotions::redeem(tx, promo.id, auth.user_id, booking_id, &promo.discount_for(&price.subtotal))
+            .await
+            .map_err(|code| (StatusCode::BAD_REQUEST, code))?;
+    }
+
+    if let Some(points) = prepared.loyalty_points {
+        loyalty::redeem(tx, auth.user_id, booking_id, points)
+            .await
+            .map_err(|code| (StatusCode::BAD_REQUEST, code))?;
+    }
+
+    if let Some(policy_id) = prepared.cancellation_policy_id {
+        sqlx::query!(
+            r#"
+            INSERT INTO booking_cancellation_policies (
+                booking_id,
+                policy_id,
+                name,
+                free_cancellation_days,
+                penalty_percent
+            )
+            SELECT $1, id, name, free_cancellation_days, penalty_percent
+            FROM cancellation_policies
+            WHERE id = $2
+            "#,
+            booking_id,
+            policy_id
+        )
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+    }
+
+    if let Some(details) = &prepared.guest_details {
+        guest_details::save(tx, booking_id, details).await;
+    }
+
+    let mut booked = BookedRoom {
+        id: booking_id,
+        prepared,
+        status,
+        hold_expires_at: Some(hold_expires_at),
+        payment: None,
+    };
+
+    if let Checkout::Pay(started) = checkout {
+        let payment = payments::record_payment(tx, started).await;
+
+        if payment.status == "succeeded" {
+            booked.status = "confirmed";
+            booked.hold_expires_at = None;
+        }
+
+        booked.payment = Some(payment);
+    }
+
+    Ok(booked)
+}
+
+/// Prices the booking in a throwaway transaction, as a quote does, and
+/// creates its payment intent before anything is written or locked.
+pub(crate) async fn start_checkout(
+    pool: &PgPool,
+    gateway: &dyn PaymentGateway,
+    auth: &AuthUser,
+    payload: &CreateBookingRequest,
+    method: Option<&str>,
+) -> Result<StartedPayment, (StatusCode, &'static str)> {
+    let mut tx = pool.begin().await.unwrap();
+
+    let prepared = prepare_booking(&mut tx, auth, payload, None).await;
+
+    tx.rollback().await.unwrap();
+
+    let prepared = prepared?;
+
+    check_bookable(&prepared)?;
+
+    payments::create_intent(gateway, Uuid::new_v4(), &prepared.price.total, &prepared.currency, method).await
+}
+
+fn check_bookable(prepared: &PreparedBooking) -> Result<(), (StatusCode, &'static str)> {
+    if let Some(violation) = prepared.violations.first() {
+        return Err((StatusCode::BAD_REQUEST, violation.code()));
+    }
+
+    if !prepared.available {
+        return Err((StatusCode::BAD_REQUEST, "ROOM_NOT_AVAILABLE"));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn booking_response(
+    auth: &AuthUser,
+    payload: &CreateBookingRequest,
+    booked: BookedRoom,
+) -> BookingResponse {
+    let prepared = booked.prepared;
+    let price = prepared.price;
+    let no_conversion = Converter::none();
+
+    BookingResponse {
+        id: booked.id.to_string(),
+        userId: auth.user_id.to_string(),
+        roomId: prepared.room_id.to_string(),
+        hotelId: prepared.hotel_id.to_string(),
+        checkInDate: prepared.check_in.to_string(),
+        checkOutDate: prepared.check_out.to_string(),
+        guests: payload.guests,
+        subtotal: price.subtotal.to_string(),
+        taxes: line_items_response(&price.taxes, &no_conversion, &prepared.currency),
+        fees: line_items_response(&price.fees, &no_conversion, &prepared.currency),
+        discounts: line_items_response(&price.discounts, &no_conversion, &prepared.currency),
+        totalPrice: price.total.to_string(),
+        currency: prepared.currency.clone(),
+        status: booked.status.to_string(),
+        holdExpiresAt: booked.hold_expires_at.map(|d| d.and_utc().to_rfc3339()),
+        bookingDate: Utc::now().to_rfc3339(),
+        nights: nightly_response(&price.nights, &no_conversion, &prepared.currency),
+        payment: booked.payment,
+        cancellationPolicy: cancellation_terms(&prepared.cancellation_policy),
+        guestDetails: prepared.guest_details.as_ref().map(GuestDetails::response),
+    }
+}
+
+pub async fn quote_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Query(query): Query<CurrencyQuery>,
+    Json(payload): Json<CreateBookingRequest>,
+) -> (StatusCode, Json<ApiResponse<QuoteResponse>>) {
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let converter = match Converter::load(&mut tx, query.currency.as_deref()).await {
+        Ok(v) => v,
+        Err(code) => {
+            tx.rollback().await.unwrap();
+            return error(StatusCode::BAD_REQUEST, code);
+        }
+    };
+
+    let prepared = prepare_booking(&mut tx, &auth, &payload, None).await;
+
+    // A quote never writes; the transaction only exists so the checks run
+    // exactly as they do in create_booking.
+    tx.rollback().await.unwrap();
+
+    let prepared = match prepared {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let price = prepared.price;
+    let currency = prepared.currency;
+
+    let response = QuoteResponse {
+        roomId: prepared.room_id.to_string(),
+        hotelId: prepared.hotel_id.to_string(),
+        checkInDate: payload.checkInDate,
+        checkOutDate: payload.checkOutDate,
+        guests: payload.guests,
+        available: prepared.available && prepared.violations.is_empty(),
+        restrictions: prepared.violations.iter().map(|v| v.code().to_string()).collect(),
+        nights: nightly_response(&price.nights, &converter, &currency),
+        subtotal: converter.convert(&price.subtotal, &currency).to_string(),
+        taxes: line_items_response(&price.taxes, &converter, &currency),
+        fees: line_items_response(&price.fees, &converter, &currency),
+        discounts: line_items_response(&price.discounts, &converter, &currency),
+        totalPrice: converter.convert(&price.total, &currency).to_string(),
+        displayCurrency: converter.display_currency(&currency),
+        currency,
+        cancellationPolicy: cancellation_terms(&prepared.cancellation_policy),
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub(crate) struct PreparedBooking {
+    pub room_id: Uuid,
+    pub hotel_id: Uuid,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    pub available: bool,
+    /// Stay restrictions the dates break; create_booking rejects on the first.
+    pub violations: Vec<Violation>,
+    pub price: StayPrice,
+    /// The hotel's currency, which the booking is always charged in.
+    pub currency: String,
+    /// Policy of the rate for the check-in night, else of the room.
+    pub cancellation_policy_id: Option<Uuid>,
+    /// Terms the booking would be made under; the standard ones without a
+    /// policy.
+    pub cancellation_policy: CancellationPolicy,
+    pub guest_details: Option<GuestDetails>,
+    /// Code the price was discounted with.
+    pub promo: Option<PromoCode>,
+    /// Loyalty points the price was discounted with; for a modification,
+    /// fewer than were spent when the new price cannot use them all.
+    pub loyalty_points: Option<i32>,
+}
+
+/// Validation, availability and pricing shared by `create_booking`,
+/// `quote_booking` and `modify_booking`, so a quote always matches what
+/// booking would charge. `existing` is a booking being changed, which must
+/// not count against its own availability. Locks the room row; the caller
+/// decides whether to commit or roll back.
+async fn prepare_booking(
+    tx: &mut Transaction<'_, Postgres>,
+    auth: &AuthUser,
+    payload: &CreateBookingRequest,
+    existing: Option<Uuid>,
+) -> Result<PreparedBooking, (StatusCode, &'static str)> {
+
     
     if auth.role != "customer" {
-        return forbidden();
+        return Err((StatusCode::FORBIDDEN, "FORBIDDEN"));
     }
 
     
     let room_id = match Uuid::parse_str(&payload.roomId) {
         Ok(v) => v,
-        Err(_) => return invalid_request(),
+        Err(_) => return Err((StatusCode::BAD_REQUEST, "INVALID_REQUEST")),
     };
 
     
@@ -36,16 +553,9 @@ pub async fn create_booking(
 
     let (check_in, check_out) = match (check_in, check_out) {
         (Some(ci), Some(co)) if ci < co && ci >= Utc::now().date_naive() => (ci, co),
-        _ => return invalid_dates(),
+        _ => return Err((StatusCode::BAD_REQUEST, "INVALID_DATES")),
     };
 
-    let nights = (check_out - check_in).num_days();
-    if nights <= 0 {
-        return invalid_dates();
-    }
-
-    let mut tx = pool.begin().await.unwrap();
-
     
     let room = sqlx::query!(
         r#"
@@ -54,7 +564,11 @@ pub async fn create_booking(
             r.hotel_id,
             r.price_per_night,
             r.max_occupancy,
-            h.owner_id
+            r.max_adults,
+            r.max_children,
+            r.cancellation_policy_id,
+            h.owner_id,
+            h.currency
         FROM rooms r
         JOIN hotels h ON h.id = r.hotel_id
         WHERE r.id = $1
@@ -62,197 +576,735 @@ pub async fn create_booking(
         "#,
         room_id
     )
-    .fetch_optional(&mut *tx)
+    .fetch_optional(&mut **tx)
     .await
     .unwrap();
 
     let room = match room {
         Some(r) => r,
-        None => {
-            tx.rollback().await.unwrap();
-            return room_not_found();
-        }
+        None => return Err((StatusCode::NOT_FOUND, "ROOM_NOT_FOUND")),
     };
 
+    // With the room locked, lapsed holds can be released before they would
+    // otherwise trip the overlap check and the exclusion constraint.
+    holds::release_expired(tx, Some(room_id)).await.unwrap();
+
     
     if room.owner_id == auth.user_id {
-        tx.rollback().await.unwrap();
-        return forbidden();
+        return Err((StatusCode::FORBIDDEN, "FORBIDDEN"));
     }
 
     
     if payload.guests > room.max_occupancy {
-        tx.rollback().await.unwrap();
-        return invalid_capacity();
+        return Err((StatusCode::BAD_REQUEST, "INVALID_CAPACITY"));
     }
 
+    let occupancy = RoomOccupancy {
+        max_adults: room.max_adults,
+        max_children: room.max_children,
+    };
+
+    let guest_details = match &payload.guestDetails {
+        Some(d) => match GuestDetails::parse(d, payload.guests, &occupancy) {
+            Ok(v) => Some(v),
+            Err(code) => return Err((StatusCode::BAD_REQUEST, code)),
+        },
+        None => None,
+    };
+
     
     let overlap = sqlx::query!(
         r#"
         SELECT id FROM bookings
         WHERE room_id = $1
-        AND status = 'confirmed'
+        AND status = ANY($5)
         AND NOT (
             check_out_date <= $2
             OR check_in_date >= $3
         )
+        AND ($4::uuid IS NULL OR id <> $4)
+        "#,
+        room_id,
+        check_in,
+        check_out,
+        existing,
+        &lifecycle::OCCUPYING[..] as &[&str]
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .unwrap();
+
+    // Block end dates are inclusive, unlike check-out dates.
+    let blocked = sqlx::query!(
+        r#"
+        SELECT id FROM room_blocks
+        WHERE room_id = $1
+        AND start_date < $3
+        AND end_date >= $2
+        LIMIT 1
         "#,
         room_id,
         check_in,
         check_out
     )
+    .fetch_optional(&mut **tx)
+    .await
+    .unwrap();
+
+    let rules = restrictions::load_restrictions(tx, room.hotel_id, room_id).await;
+    let violations = restrictions::check_stay(&rules, check_in, check_out, Utc::now().date_naive());
+
+    let promo = match payload.promoCode.as_deref() {
+        Some(code) => {
+            let nights = (check_out - check_in).num_days();
+            Some(prepare_promo(tx, auth, code, existing, room.hotel_id, &room.currency, nights).await?)
+        }
+        None => None,
+    };
+
+    let mut loyalty_points = payload.loyaltyPoints;
+
+    let points_value = match loyalty_points {
+        Some(points) => Some(prepare_points(tx, auth, points, existing, &room.currency).await?),
+        None => None,
+    };
+
+    
+    let stay = Stay {
+        hotel_id: room.hotel_id,
+        room_id,
+        base_price: &room.price_per_night,
+        check_in,
+        check_out,
+        guests: payload.guests,
+        promo: promo.as_ref(),
+        points_value: points_value.as_ref(),
+    };
+
+    let mut price = price_stay(tx, &stay).await;
+
+    if let Some(value) = &points_value {
+        let promo_discount = promo
+            .as_ref()
+            .map(|p| p.discount_for(&price.subtotal))
+            .unwrap_or_else(|| BigDecimal::from(0));
+
+        let left = &price.subtotal - promo_discount;
+
+        if *value > left {
+            if existing.is_none() {
+                return Err((StatusCode::BAD_REQUEST, "POINTS_EXCEED_PRICE"));
+            }
+
+            // A change that makes the stay cheaper keeps the points it can
+            // still use; modify_booking returns the rest.
+            let points = loyalty::points_within(tx, &left, &room.currency)
+                .await
+                .map_err(|code| (StatusCode::BAD_REQUEST, code))?;
+
+            loyalty_points = (points > 0).then_some(points);
+
+            let capped = match loyalty_points {
+                Some(points) => Some(
+                    loyalty::points_value(tx, points, &room.currency)
+                        .await
+                        .map_err(|code| (StatusCode::BAD_REQUEST, code))?,
+                ),
+                None => None,
+            };
+
+            price = price_stay(tx, &Stay { points_value: capped.as_ref(), ..stay }).await;
+        }
+    }
+
+    let cancellation_policy_id = price
+        .nights
+        .first()
+        .and_then(|n| n.cancellation_policy_id)
+        .or(room.cancellation_policy_id);
+
+    let cancellation_policy = match cancellation_policy_id {
+        Some(id) => cancellation::load_policy(tx, id).await,
+        None => None,
+    };
+
+    Ok(PreparedBooking {
+        room_id,
+        hotel_id: room.hotel_id,
+        check_in,
+        check_out,
+        available: overlap.is_none() && blocked.is_none(),
+        violations,
+        price,
+        currency: room.currency,
+        cancellation_policy_id,
+        cancellation_policy: cancellation_policy.unwrap_or_else(CancellationPolicy::standard),
+        guest_details,
+        promo,
+        loyalty_points,
+    })
+}
+
+/// Finds the promo code a booking asks for and checks it applies to the
+/// stay. A booking being modified keeps the code it was redeemed with, judged
+/// as of the redemption date and without counting another use.
+async fn prepare_promo(
+    tx: &mut Transaction<'_, Postgres>,
+    auth: &AuthUser,
+    code: &str,
+    existing: Option<Uuid>,
+    hotel_id: Uuid,
+    currency: &str,
+    nights: i64,
+) -> Result<PromoCode, (StatusCode, &'static str)> {
+    let redeemed = match existing {
+        Some(id) => promotions::load_redeemed(tx, id).await,
+        None => None,
+    };
+
+    if let Some((promo, redeemed_on)) = redeemed {
+        if promo.code.eq_ignore_ascii_case(code.trim()) {
+            promo
+                .check(hotel_id, currency, nights, redeemed_on)
+                .map_err(|code| (StatusCode::BAD_REQUEST, code))?;
+            return Ok(promo);
+        }
+    }
+
+    let promo = promotions::find_code(tx, code)
+        .await
+        .ok_or((StatusCode::BAD_REQUEST, "INVALID_PROMO_CODE"))?;
+
+    promo
+        .check(hotel_id, currency, nights, Utc::now().date_naive())
+        .map_err(|code| (StatusCode::BAD_REQUEST, code))?;
+
+    if !promotions::has_uses_left(tx, promo.id, auth.user_id).await {
+        return Err((StatusCode::BAD_REQUEST, "PROMO_CODE_EXHAUSTED"));
+    }
+
+    Ok(promo)
+}
+
+/// Checks the customer has the points a booking asks to spend and works out
+/// what they are worth in the hotel's currency. Points already spent on a
+/// booking being modified count as available to it.
+async fn prepare_points(
+    tx: &mut Transaction<'_, Postgres>,
+    auth: &AuthUser,
+    points: i32,
+    existing: Option<Uuid>,
+    currency: &str,
+) -> Result<BigDecimal, (StatusCode, &'static str)> {
+    if points < 1 {
+        return Err((StatusCode::BAD_REQUEST, "INVALID_REQUEST"));
+    }
+
+    let spent_here = match existing {
+        Some(id) => loyalty::redeemed_on(tx, id).await.unwrap_or(0),
+        None => 0,
+    };
+
+    if loyalty::balance(tx, auth.user_id).await + (spent_here as i64) < points as i64 {
+        return Err((StatusCode::BAD_REQUEST, "INSUFFICIENT_POINTS"));
+    }
+
+    loyalty::points_value(tx, points, currency)
+        .await
+        .map_err(|code| (StatusCode::BAD_REQUEST, code))
+}
+
+/// Stores the nightly breakdown and line items a booking was charged.
+async fn insert_price_details(
+    tx: &mut Transaction<'_, Postgres>,
+    booking_id: Uuid,
+    price: &StayPrice,
+) {
+    for night in &price.nights {
+        sqlx::query!(
+            r#"
+            INSERT INTO booking_nights (booking_id, night_date, price)
+            VALUES ($1, $2, $3)
+            "#,
+            booking_id,
+            night.date,
+            night.price
+        )
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+    }
+
+    let line_items = [("tax", &price.taxes), ("fee", &price.fees), ("discount", &price.discounts)];
+
+    let mut position = 0;
+    for (category, items) in line_items {
+        for item in items {
+            sqlx::query!(
+                r#"
+                INSERT INTO booking_line_items (
+                    booking_id,
+                    category,
+                    name,
+                    amount,
+                    inclusive,
+                    position
+                )
+                VALUES ($1,$2,$3,$4,$5,$6)
+                "#,
+                booking_id,
+                category,
+                item.name,
+                item.amount,
+                item.inclusive,
+                position
+            )
+            .execute(&mut **tx)
+            .await
+            .unwrap();
+
+            position += 1;
+        }
+    }
+}
+
+pub(crate) fn cancellation_terms(policy: &CancellationPolicy) -> CancellationTermsResponse {
+    CancellationTermsResponse {
+        name: policy.name.clone(),
+        freeCancellationDays: policy.free_cancellation_days,
+        penaltyPercent: policy.penalty_percent.as_ref().map(|p| p.to_string()),
+        nonRefundable: policy.is_non_refundable(),
+    }
+}
+
+fn nightly_response(
+    nights: &[NightlyRate],
+    converter: &Converter,
+    currency: &str,
+) -> Vec<NightlyRateResponse> {
+    nights
+        .iter()
+        .map(|n| NightlyRateResponse {
+            date: n.date.to_string(),
+            price: converter.convert(&n.price, currency).to_string(),
+        })
+        .collect()
+}
+
+fn line_items_response(
+    items: &[LineItem],
+    converter: &Converter,
+    currency: &str,
+) -> Vec<LineItemResponse> {
+    items
+        .iter()
+        .map(|i| LineItemResponse {
+            name: i.name.clone(),
+            amount: converter.convert(&i.amount, currency).to_string(),
+            inclusive: i.inclusive,
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct StoredLineItems {
+    taxes: Vec<LineItemResponse>,
+    fees: Vec<LineItemResponse>,
+    discounts: Vec<LineItemResponse>,
+}
+
+/// Stored tax, fee and discount line items per booking, in the order they
+/// were charged.
+async fn load_line_items(
+    pool: &PgPool,
+    booking_ids: &[Uuid],
+    converter: &Converter,
+) -> HashMap<Uuid, StoredLineItems> {
+    let items = sqlx::query!(
+        r#"
+        SELECT li.booking_id, li.category, li.name, li.amount, li.inclusive, b.currency
+        FROM booking_line_items li
+        JOIN bookings b ON b.id = li.booking_id
+        WHERE li.booking_id = ANY($1)
+        ORDER BY li.position
+        "#,
+        booking_ids
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    let mut grouped: HashMap<Uuid, StoredLineItems> = HashMap::new();
+
+    for item in items {
+        let entry = grouped.entry(item.booking_id).or_default();
+        let response = LineItemResponse {
+            name: item.name,
+            amount: converter.convert(&item.amount, &item.currency).to_string(),
+            inclusive: item.inclusive,
+        };
+
+        match item.category.as_str() {
+            "tax" => entry.taxes.push(response),
+            "fee" => entry.fees.push(response),
+            "discount" => entry.discounts.push(response),
+            _ => {}
+        }
+    }
+
+    grouped
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
+
+
+pub async fn list_bookings(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Query(filters): Query<BookingListQuery>,
+) -> (StatusCode, Json<ApiResponse<Vec<BookingListResponse>>>) {
+
+    if auth.role != "customer" {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("FORBIDDEN")),
+        );
+    }
+
+    let mut conn = pool.acquire().await.unwrap();
+
+    let converter = match Converter::load(&mut conn, filters.currency.as_deref()).await {
+        Ok(v) => v,
+        Err(code) => return error(StatusCode::BAD_REQUEST, code),
+    };
+
+    drop(conn);
+
+    let bookings = sqlx::query!(
+        r#"
+        SELECT
+            b.id,
+            b.reservation_id,
+            b.room_id,
+            b.hotel_id,
+            h.name AS hotel_name,
+            r.room_number,
+            r.room_type,
+            b.check_in_date,
+            b.check_out_date,
+            b.guests,
+            b.subtotal,
+            b.total_price,
+            b.currency,
+            b.status,
+            b.hold_expires_at,
+            b.booking_date,
+            b.cancelled_at,
+            CASE
+                WHEN b.cancelled_by IS NULL THEN NULL
+                WHEN b.cancelled_by = b.user_id THEN 'customer'
+                ELSE 'hotel'
+            END AS cancelled_by,
+            b.cancellation_reason,
+            b.checked_in_at,
+            b.checked_out_at
+        FROM bookings b
+        JOIN rooms r ON r.id = b.room_id
+        JOIN hotels h ON h.id = b.hotel_id
+        WHERE
+            b.user_id = $1
+        AND ($2::text IS NULL OR b.status = $2)
+        ORDER BY b.booking_date DESC
+        "#,
+        auth.user_id,
+        filters.status,
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let booking_ids: Vec<Uuid> = bookings.iter().map(|b| b.id).collect();
+
+    let mut line_items = load_line_items(&pool, &booking_ids, &converter).await;
+    let mut guest_details = guest_details::load_many(&pool, &booking_ids).await;
+
+    let response = bookings
+        .into_iter()
+        .map(|b| {
+            let items = line_items.remove(&b.id).unwrap_or_default();
+            BookingListResponse {
+            id: b.id.to_string(),
+            reservationId: b.reservation_id.map(|v| v.to_string()),
+            roomId: b.room_id.to_string(),
+            hotelId: b.hotel_id.to_string(),
+            hotelName: b.hotel_name,
+            roomNumber: b.room_number,
+            roomType: b.room_type,
+            checkInDate: b.check_in_date.to_string(),
+            checkOutDate: b.check_out_date.to_string(),
+            guests: b.guests,
+            subtotal: converter.convert(&b.subtotal, &b.currency).to_string(),
+            taxes: items.taxes,
+            fees: items.fees,
+            discounts: items.discounts,
+            totalPrice: converter.convert(&b.total_price, &b.currency).to_string(),
+            displayCurrency: converter.display_currency(&b.currency),
+            currency: b.currency,
+            status: b.status.unwrap_or_else(|| "confirmed".to_string()),
+            holdExpiresAt: b.hold_expires_at.map(|d| d.and_utc().to_rfc3339()),
+            bookingDate: b.booking_date
+            .map(|d| d.and_utc().to_rfc3339())
+            .unwrap_or_else(|| Utc::now().to_rfc3339()),
+            cancelledAt: b.cancelled_at.map(|d| d.and_utc().to_rfc3339()),
+            cancelledBy: b.cancelled_by,
+            cancellationReason: b.cancellation_reason,
+            checkedInAt: b.checked_in_at.map(|d| d.and_utc().to_rfc3339()),
+            checkedOutAt: b.checked_out_at.map(|d| d.and_utc().to_rfc3339()),
+            guestDetails: guest_details.remove(&b.id),
+            }
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn modify_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(booking_id): Path<String>,
+    Json(payload): Json<ModifyBookingRequest>,
+) -> (StatusCode, Json<ApiResponse<ModifyBookingResponse>>) {
+
+    if auth.role != "customer" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let booking_id = match Uuid::parse_str(&booking_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let booking = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            user_id,
+            room_id,
+            hotel_id,
+            check_in_date,
+            check_out_date,
+            guests,
+            total_price,
+            status
+        FROM bookings
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        booking_id
+    )
     .fetch_optional(&mut *tx)
     .await
     .unwrap();
 
-    if overlap.is_some() {
+    let booking = match booking {
+        Some(b) => b,
+        None => {
+            tx.rollback().await.unwrap();
+            return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND");
+        }
+    };
+
+    if booking.user_id != auth.user_id {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    if let Err(code) = BookingStatus::from_column(booking.status.as_deref()).modifiable() {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, code);
+    }
+
+    let policy = cancellation::load_booking_policy(&mut tx, booking_id).await;
+
+    if !policy.allows_changes(booking.check_in_date, Utc::now().date_naive()) {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, "MODIFICATION_DEADLINE_PASSED");
+    }
+
+    let request = CreateBookingRequest {
+        roomId: payload.roomId.unwrap_or_else(|| booking.room_id.to_string()),
+        checkInDate: payload
+            .checkInDate
+            .unwrap_or_else(|| booking.check_in_date.to_string()),
+        checkOutDate: payload
+            .checkOutDate
+            .unwrap_or_else(|| booking.check_out_date.to_string()),
+        guests: payload.guests.unwrap_or(booking.guests),
+        paymentMethod: None,
+        guestDetails: match payload.guestDetails {
+            Some(v) => Some(v),
+            None => guest_details::load_request(&mut tx, booking_id).await,
+        },
+        promoCode: promotions::load_redeemed(&mut tx, booking_id)
+            .await
+            .map(|(promo, _)| promo.code),
+        loyaltyPoints: loyalty::redeemed_on(&mut tx, booking_id).await,
+    };
+
+    let prepared = match prepare_booking(&mut tx, &auth, &request, Some(booking_id)).await {
+        Ok(v) => v,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            return error(status, code);
+        }
+    };
+
+    if prepared.hotel_id != booking.hotel_id {
         tx.rollback().await.unwrap();
-        return room_not_available();
+        return error(StatusCode::BAD_REQUEST, "ROOM_NOT_IN_HOTEL");
     }
 
-    
-    let total_price = &room.price_per_night * BigDecimal::from(nights);
+    if let Some(violation) = prepared.violations.first() {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, violation.code());
+    }
 
-    
-    let booking_id = Uuid::new_v4();
+    if !prepared.available {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, "ROOM_NOT_AVAILABLE");
+    }
 
-    sqlx::query!(
+    let price = prepared.price;
+    let price_difference = &price.total - &booking.total_price;
+
+    let updated = sqlx::query!(
         r#"
-        INSERT INTO bookings (
-            id,
-            user_id,
-            room_id,
-            hotel_id,
-            check_in_date,
-            check_out_date,
-            guests,
-            total_price,
-            status
-        )
-        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,'confirmed')
+        UPDATE bookings
+        SET
+            room_id = $1,
+            check_in_date = $2,
+            check_out_date = $3,
+            guests = $4,
+            subtotal = $5,
+            total_price = $6
+        WHERE id = $7
         "#,
-        booking_id,
-        auth.user_id,
-        room_id,
-        room.hotel_id,
-        check_in,
-        check_out,
-        payload.guests,
-        total_price
+        prepared.room_id,
+        prepared.check_in,
+        prepared.check_out,
+        request.guests,
+        price.subtotal,
+        price.total,
+        booking_id
     )
     .execute(&mut *tx)
-    .await
-    .unwrap();
-
-    tx.commit().await.unwrap();
-
-    
-    let response = BookingResponse {
-        id: booking_id.to_string(),
-        userId: auth.user_id.to_string(),
-        roomId: room_id.to_string(),
-        hotelId: room.hotel_id.to_string(),
-        checkInDate: payload.checkInDate,
-        checkOutDate: payload.checkOutDate,
-        guests: payload.guests,
-        totalPrice: total_price.to_string(),
-        status: "confirmed".to_string(),
-        bookingDate: Utc::now().to_rfc3339(),
-    };
-
-    (
-        StatusCode::CREATED,
-        Json(ApiResponse::success(response)),
-    )
-}
+    .await;
 
-fn invalid_request() -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
-    (StatusCode::BAD_REQUEST, Json(ApiResponse::error("INVALID_REQUEST")))
-}
+    match updated {
+        Ok(_) => {}
+        Err(e) if db::violates_constraint(&e, db::BOOKING_OVERLAP_CONSTRAINT) => {
+            tx.rollback().await.unwrap();
+            return error(StatusCode::BAD_REQUEST, "ROOM_NOT_AVAILABLE");
+        }
+        Err(e) => {
+            eprintln!("booking update failed: {e}");
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR");
+        }
+    }
 
-fn invalid_dates() -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
-    (StatusCode::BAD_REQUEST, Json(ApiResponse::error("INVALID_DATES")))
-}
+    sqlx::query!("DELETE FROM booking_nights WHERE booking_id = $1", booking_id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
 
-fn invalid_capacity() -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
-    (StatusCode::BAD_REQUEST, Json(ApiResponse::error("INVALID_CAPACITY")))
-}
+    sqlx::query!("DELETE FROM booking_line_items WHERE booking_id = $1", booking_id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
 
-fn room_not_available() -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
-    (StatusCode::BAD_REQUEST, Json(ApiResponse::error("ROOM_NOT_AVAILABLE")))
-}
+    insert_price_details(&mut tx, booking_id, &price).await;
 
-fn room_not_found() -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
-    (StatusCode::NOT_FOUND, Json(ApiResponse::error("ROOM_NOT_FOUND")))
-}
+    invoices::void_current(&mut tx, booking_id).await;
 
-fn forbidden() -> (StatusCode, Json<ApiResponse<BookingResponse>>) {
-    (StatusCode::FORBIDDEN, Json(ApiResponse::error("FORBIDDEN")))
-}
+    if let Some(details) = &prepared.guest_details {
+        guest_details::save(&mut tx, booking_id, details).await;
+    }
 
+    if let Some(promo) = &prepared.promo {
+        promotions::update_redemption(&mut tx, booking_id, &promo.discount_for(&price.subtotal)).await;
+    }
 
-pub async fn list_bookings(
-    auth: AuthUser,
-    State(pool): State<PgPool>,
-    Query(filters): Query<BookingListQuery>,
-) -> (StatusCode, Json<ApiResponse<Vec<BookingListResponse>>>) {
+    let points_returned = request.loyaltyPoints.unwrap_or(0) - prepared.loyalty_points.unwrap_or(0);
 
-    if auth.role != "customer" {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::error("FORBIDDEN")),
-        );
+    if points_returned > 0 {
+        loyalty::return_points(&mut tx, auth.user_id, booking_id, points_returned).await;
     }
 
-    let bookings = sqlx::query!(
+    let change = sqlx::query!(
         r#"
-        SELECT
-            b.id,
-            b.room_id,
-            b.hotel_id,
-            h.name AS hotel_name,
-            r.room_number,
-            r.room_type,
-            b.check_in_date,
-            b.check_out_date,
-            b.guests,
-            b.total_price,
-            b.status,
-            b.booking_date
-        FROM bookings b
-        JOIN rooms r ON r.id = b.room_id
-        JOIN hotels h ON h.id = b.hotel_id
-        WHERE
-            b.user_id = $1
-        AND ($2::text IS NULL OR b.status = $2)
-        ORDER BY b.booking_date DESC
+        INSERT INTO booking_changes (
+            booking_id,
+            changed_by,
+            previous_room_id,
+            previous_check_in_date,
+            previous_check_out_date,
+            previous_guests,
+            previous_total_price,
+            new_total_price,
+            price_difference
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
+        RETURNING created_at
         "#,
+        booking_id,
         auth.user_id,
-        filters.status,
+        booking.room_id,
+        booking.check_in_date,
+        booking.check_out_date,
+        booking.guests,
+        booking.total_price,
+        price.total,
+        price_difference
     )
-    .fetch_all(&pool)
+    .fetch_one(&mut *tx)
     .await
     .unwrap();
 
-    let response = bookings
-        .into_iter()
-        .map(|b| BookingListResponse {
-            id: b.id.to_string(),
-            roomId: b.room_id.to_string(),
-            hotelId: b.hotel_id.to_string(),
-            hotelName: b.hotel_name,
-            roomNumber: b.room_number,
-            roomType: b.room_type,
-            checkInDate: b.check_in_date.to_string(),
-            checkOutDate: b.check_out_date.to_string(),
-            guests: b.guests,
-            totalPrice: b.total_price.to_string(),
-            status: b.status.unwrap_or_else(|| "confirmed".to_string()),
-            bookingDate: b.booking_date
+    tx.commit().await.unwrap();
+
+    let no_conversion = Converter::none();
+
+    let response = ModifyBookingResponse {
+        id: booking_id.to_string(),
+        roomId: prepared.room_id.to_string(),
+        hotelId: prepared.hotel_id.to_string(),
+        checkInDate: prepared.check_in.to_string(),
+        checkOutDate: prepared.check_out.to_string(),
+        guests: request.guests,
+        subtotal: price.subtotal.to_string(),
+        taxes: line_items_response(&price.taxes, &no_conversion, &prepared.currency),
+        fees: line_items_response(&price.fees, &no_conversion, &prepared.currency),
+        discounts: line_items_response(&price.discounts, &no_conversion, &prepared.currency),
+        totalPrice: price.total.to_string(),
+        previousTotalPrice: booking.total_price.to_string(),
+        priceDifference: price_difference.to_string(),
+        currency: prepared.currency.clone(),
+        status: "confirmed".to_string(),
+        modifiedAt: change
+            .created_at
             .map(|d| d.and_utc().to_rfc3339())
             .unwrap_or_else(|| Utc::now().to_rfc3339()),
-        })
-        .collect();
+        nights: nightly_response(&price.nights, &no_conversion, &prepared.currency),
+        guestDetails: prepared.guest_details.as_ref().map(GuestDetails::response),
+        pointsReturned: (points_returned > 0).then_some(points_returned),
+    };
 
     (
         StatusCode::OK,
@@ -263,6 +1315,7 @@ pub async fn list_bookings(
 pub async fn cancel_booking(
     auth: AuthUser,
     State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
     Path(booking_id): Path<String>,
 ) -> (StatusCode, Json<ApiResponse<CancelBookingResponse>>) {
 
@@ -286,6 +1339,7 @@ pub async fn cancel_booking(
             user_id,
             status,
             check_in_date,
+            currency,
             cancelled_at
         FROM bookings
         WHERE id = $1
@@ -312,43 +1366,167 @@ pub async fn cancel_booking(
     }
 
 
-    if booking.status == Some("cancelled".to_string()) {
+    let status = BookingStatus::from_column(booking.status.as_deref());
+
+    if let Err(code) = status.transition(BookingStatus::Cancelled) {
         tx.rollback().await.unwrap();
-        return already_cancelled();
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::error(code)));
     }
 
-    let now = Utc::now().date_naive();
-    
-    let days_until_checkin = booking.check_in_date - now;
+    let plan = match plan_cancellation(&mut tx, booking_id, booking.check_in_date).await {
+        Some(v) => v,
+        None => {
+            tx.rollback().await.unwrap();
+            return cancellation_deadline_passed();
+        }
+    };
 
-    if days_until_checkin < Duration::days(1) {
-        tx.rollback().await.unwrap();
-        return cancellation_deadline_passed();
+    let cancelled_at = cancel_with_refund(&mut tx, &plan, &CancelledBy::Customer(auth.user_id)).await;
+
+    tx.commit().await.unwrap();
+
+    if let Err((status, code)) = payments::settle_cancellation(&pool, gateway.as_ref(), &[booking_id]).await {
+        return (status, Json(ApiResponse::error(code)));
     }
 
-    let cancelled_at = Utc::now().naive_utc();
+    let response = CancelBookingResponse {
+        id: booking_id.to_string(),
+        status: "cancelled".to_string(),
+        cancelledAt: cancelled_at.and_utc().to_rfc3339(),
+        cancellationPolicy: plan.policy.name,
+        refundAmount: plan.refund.amount.to_string(),
+        penaltyAmount: plan.refund.penalty.to_string(),
+        currency: booking.currency,
+    };
 
-    sqlx::query!(
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+/// Longest reason a hotel can give for cancelling; it is shown to the
+/// customer and sent in their email, not kept as a free-form log.
+const MAX_CANCELLATION_REASON_CHARS: usize = 1000;
+
+/// Cancels a booking at the owner's hotel, e.g. when it has to close. A
+/// reason of up to `MAX_CANCELLATION_REASON_CHARS` is required, the customer gets everything they paid back whatever
+/// the policy says, and they are notified.
+pub async fn cancel_hotel_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
+    Path((hotel_id, booking_id)): Path<(String, String)>,
+    Json(payload): Json<CancelHotelBookingRequest>,
+) -> (StatusCode, Json<ApiResponse<CancelBookingResponse>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return forbidden_();
+    }
+
+    let reason = match payload.reason {
+        Some(v) if !v.trim().is_empty() && v.trim().chars().count() <= MAX_CANCELLATION_REASON_CHARS => {
+            v.trim().to_string()
+        }
+        _ => return invalid_request_(),
+    };
+
+    let (hotel_id, booking_id) = match (Uuid::parse_str(&hotel_id), Uuid::parse_str(&booking_id)) {
+        (Ok(h), Ok(b)) => (h, b),
+        _ => return booking_not_found(),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let booking = sqlx::query!(
         r#"
-        UPDATE bookings
-        SET
-            status = 'cancelled',
-            cancelled_at = $1
-        WHERE id = $2
+        SELECT
+            b.user_id,
+            b.status,
+            b.check_in_date,
+            b.check_out_date,
+            b.currency,
+            h.name AS hotel_name,
+            h.owner_id
+        FROM bookings b
+        JOIN hotels h ON h.id = b.hotel_id
+        WHERE b.id = $1 AND b.hotel_id = $2
+        FOR UPDATE OF b
         "#,
-        cancelled_at,
-        booking_id
+        booking_id,
+        hotel_id
     )
-    .execute(&mut *tx)
+    .fetch_optional(&mut *tx)
     .await
     .unwrap();
 
+    let booking = match booking {
+        Some(b) => b,
+        None => {
+            tx.rollback().await.unwrap();
+            return booking_not_found();
+        }
+    };
+
+    if auth.role != "admin" && booking.owner_id != auth.user_id {
+        tx.rollback().await.unwrap();
+        return forbidden_();
+    }
+
+    let status = BookingStatus::from_column(booking.status.as_deref());
+
+    if let Err(code) = status.transition(BookingStatus::Cancelled) {
+        tx.rollback().await.unwrap();
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::error(code)));
+    }
+
+    if booking.check_out_date <= Utc::now().date_naive() {
+        tx.rollback().await.unwrap();
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::error("STAY_COMPLETED")));
+    }
+
+    let plan = plan_full_refund(&mut tx, booking_id).await;
+
+    let by = CancelledBy::Hotel {
+        user_id: auth.user_id,
+        reason: &reason,
+    };
+
+    let cancelled_at = cancel_with_refund(&mut tx, &plan, &by).await;
+
+    let message = format!(
+        "Your booking at {} from {} to {} was cancelled by the hotel: {}. A full refund of {} {} has been issued.",
+        booking.hotel_name,
+        booking.check_in_date,
+        booking.check_out_date,
+        reason,
+        plan.refund.amount,
+        booking.currency
+    );
+
+    notifications::notify(
+        &mut tx,
+        booking.user_id,
+        "booking_cancelled_by_hotel",
+        Some(booking_id),
+        &message,
+    )
+    .await;
+
     tx.commit().await.unwrap();
 
+    if let Err((status, code)) = payments::settle_cancellation(&pool, gateway.as_ref(), &[booking_id]).await {
+        return (status, Json(ApiResponse::error(code)));
+    }
+
     let response = CancelBookingResponse {
         id: booking_id.to_string(),
         status: "cancelled".to_string(),
         cancelledAt: cancelled_at.and_utc().to_rfc3339(),
+        cancellationPolicy: plan.policy.name,
+        refundAmount: plan.refund.amount.to_string(),
+        penaltyAmount: plan.refund.penalty.to_string(),
+        currency: booking.currency,
     };
 
     (
@@ -357,6 +1535,153 @@ pub async fn cancel_booking(
     )
 }
 
+/// Who cancelled a booking, stored on it and shown to the customer.
+pub(crate) enum CancelledBy<'a> {
+    Customer(Uuid),
+    Hotel { user_id: Uuid, reason: &'a str },
+}
+
+impl CancelledBy<'_> {
+    fn user_id(&self) -> Uuid {
+        match self {
+            Self::Customer(user_id) | Self::Hotel { user_id, .. } => *user_id,
+        }
+    }
+
+    fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Customer(_) => None,
+            Self::Hotel { reason, .. } => Some(reason),
+        }
+    }
+
+    /// Recorded on the refund.
+    fn refund_reason(&self) -> &'static str {
+        match self {
+            Self::Customer(_) => "customer_cancellation",
+            Self::Hotel { .. } => "hotel_cancellation",
+        }
+    }
+}
+
+/// What cancelling a booking right now would refund under its policy.
+pub(crate) struct CancellationPlan {
+    pub booking_id: Uuid,
+    pub policy: CancellationPolicy,
+    pub payment: Option<CapturedPayment>,
+    pub refund: Refund,
+}
+
+/// Works out the refund for cancelling a booking today, or `None` if its
+/// policy no longer allows cancelling. Nothing is written, so callers can
+/// plan several cancellations before committing to any of them.
+pub(crate) async fn plan_cancellation(
+    tx: &mut Transaction<'_, Postgres>,
+    booking_id: Uuid,
+    check_in_date: NaiveDate,
+) -> Option<CancellationPlan> {
+    let policy = cancellation::load_booking_policy(tx, booking_id).await;
+    let payment = payments::captured_payment(tx, booking_id).await;
+
+    let refund = policy.refund_for(&refundable(&payment), check_in_date, Utc::now().date_naive())?;
+
+    Some(CancellationPlan {
+        booking_id,
+        policy,
+        payment,
+        refund,
+    })
+}
+
+/// Gives back everything paid, for cancellations that are not the
+/// customer's doing.
+async fn plan_full_refund(tx: &mut Transaction<'_, Postgres>, booking_id: Uuid) -> CancellationPlan {
+    let policy = cancellation::load_booking_policy(tx, booking_id).await;
+    let payment = payments::captured_payment(tx, booking_id).await;
+
+    let refund = Refund {
+        amount: refundable(&payment),
+        penalty: BigDecimal::from(0),
+    };
+
+    CancellationPlan {
+        booking_id,
+        policy,
+        payment,
+        refund,
+    }
+}
+
+fn refundable(payment: &Option<CapturedPayment>) -> BigDecimal {
+    payment
+        .as_ref()
+        .map(|p| p.refundable.clone())
+        .unwrap_or_else(|| BigDecimal::from(0))
+}
+
+/// Cancels the booking and records the refund the plan gives back. A
+/// payment still pending is marked cancelled; the caller voids it and sends
+/// the refund with `payments::settle_cancellation` after committing. Returns when the
+/// booking was cancelled.
+pub(crate) async fn cancel_with_refund(
+    tx: &mut Transaction<'_, Postgres>,
+    plan: &CancellationPlan,
+    by: &CancelledBy<'_>,
+) -> NaiveDateTime {
+    let cancelled_at = Utc::now().naive_utc();
+
+    // Joining the row to itself exposes the status it had before the update.
+    let freed = sqlx::query!(
+        r#"
+        UPDATE bookings b
+        SET
+            status = 'cancelled',
+            cancelled_at = $1,
+            cancelled_by = $2,
+            cancellation_reason = $3
+        FROM bookings previous
+        WHERE b.id = $4 AND previous.id = b.id
+        RETURNING b.room_id, b.check_in_date, b.check_out_date, previous.status AS previous_status
+        "#,
+        cancelled_at,
+        by.user_id(),
+        by.reason(),
+        plan.booking_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        "UPDATE payments SET status = 'cancelled', updated_at = now() WHERE booking_id = $1 AND status = 'pending'",
+        plan.booking_id
+    )
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+
+    if let Some(payment) = &plan.payment {
+        payments::record_refund(tx, plan.booking_id, payment, &plan.refund, by.refund_reason()).await;
+    }
+
+    loyalty::reverse_booking(tx, plan.booking_id).await.unwrap();
+    promotions::release(tx, plan.booking_id).await.unwrap();
+
+    // Holds and unpaid bookings were never announced, so neither is their
+    // cancellation.
+    if BookingStatus::from_column(freed.previous_status.as_deref()) == BookingStatus::Confirmed {
+        outbox::enqueue(tx, plan.booking_id, Template::BookingCancelled).await.unwrap();
+        webhooks::booking_event(tx, plan.booking_id, Event::BookingCancelled).await;
+    }
+
+    // The cancellation stands even if the room cannot be offered on.
+    if let Err(e) = waitlist::offer_freed_room(tx, freed.room_id, freed.check_in_date, freed.check_out_date).await {
+        eprintln!("waitlist offer for room {}: {e}", freed.room_id);
+    }
+
+    cancelled_at
+}
+
 fn invalid_request_() -> (StatusCode, Json<ApiResponse<CancelBookingResponse>>) {
     (StatusCode::BAD_REQUEST, Json(ApiResponse::error("INVALID_REQUEST")))
 }
@@ -365,10 +1690,6 @@ fn booking_not_found() -> (StatusCode, Json<ApiResponse<CancelBookingResponse>>)
     (StatusCode::NOT_FOUND, Json(ApiResponse::error("BOOKING_NOT_FOUND")))
 }
 
-fn already_cancelled() -> (StatusCode, Json<ApiResponse<CancelBookingResponse>>) {
-    (StatusCode::BAD_REQUEST, Json(ApiResponse::error("ALREADY_CANCELLED")))
-}
-
 fn cancellation_deadline_passed() -> (StatusCode, Json<ApiResponse<CancelBookingResponse>>) {
     (StatusCode::BAD_REQUEST, Json(ApiResponse::error("CANCELLATION_DEADLINE_PASSED")))
 }
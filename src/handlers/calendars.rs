@@ -0,0 +1,444 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use reqwest::Url;
+use sqlx::PgPool;
+use std::env;
+use uuid::Uuid;
+
+use crate::{
+    handlers::{auth_middleware::AuthUser, blocks::find_owned_room},
+    ical::{
+        self,
+        sync::{self, SyncError, SyncOutcome},
+        CalendarEvent,
+    },
+    models::{
+        calendars::{
+            CalendarFeedResponse, CalendarImportResponse, CalendarSyncResponse, CreateCalendarImportRequest,
+            DeleteCalendarImportResponse,
+        },
+        response::ApiResponse,
+    },
+    outbound,
+};
+
+/// The room's secret iCal feed URL, created on first request.
+pub async fn get_calendar_feed(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<CalendarFeedResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let feed = sqlx::query!(
+        r#"
+        INSERT INTO room_calendar_feeds (room_id, token)
+        VALUES ($1, $2)
+        ON CONFLICT (room_id) DO UPDATE SET token = room_calendar_feeds.token
+        RETURNING token, created_at
+        "#,
+        room_id,
+        new_token()
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(feed_response(room_id, &feed.token, feed.created_at))),
+    )
+}
+
+/// Replaces the feed URL, for when the old one was shared too widely.
+pub async fn rotate_calendar_feed(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<CalendarFeedResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let feed = sqlx::query!(
+        r#"
+        INSERT INTO room_calendar_feeds (room_id, token)
+        VALUES ($1, $2)
+        ON CONFLICT (room_id) DO UPDATE
+        SET token = EXCLUDED.token, created_at = now() AT TIME ZONE 'utc'
+        RETURNING token, created_at
+        "#,
+        room_id,
+        new_token()
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(feed_response(room_id, &feed.token, feed.created_at))),
+    )
+}
+
+fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn feed_response(room_id: Uuid, token: &str, created_at: chrono::NaiveDateTime) -> CalendarFeedResponse {
+    let base_url = env::var("PUBLIC_BASE_URL").unwrap_or_default();
+
+    CalendarFeedResponse {
+        roomId: room_id.to_string(),
+        url: format!("{}/api/calendars/{}.ics", base_url.trim_end_matches('/'), token),
+        createdAt: created_at.and_utc().to_rfc3339(),
+    }
+}
+
+/// The public iCal feed: upcoming confirmed stays and the owner's own
+/// blocks. Blocks imported from other calendars are left out so platforms
+/// do not echo each other's events back. No guest details are included.
+pub async fn export_calendar(
+    State(pool): State<PgPool>,
+    Path(token): Path<String>,
+) -> Response {
+
+    let token = token.strip_suffix(".ics").unwrap_or(&token);
+
+    let room = sqlx::query!(
+        r#"
+        SELECT r.id, r.room_number, h.name AS hotel_name
+        FROM room_calendar_feeds f
+        JOIN rooms r ON r.id = f.room_id
+        JOIN hotels h ON h.id = r.hotel_id
+        WHERE f.token = $1
+        "#,
+        token
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let room = match room {
+        Some(r) => r,
+        None => return error::<()>(StatusCode::NOT_FOUND, "CALENDAR_NOT_FOUND").into_response(),
+    };
+
+    let today = Utc::now().date_naive();
+
+    let bookings = sqlx::query!(
+        r#"
+        SELECT id, check_in_date, check_out_date
+        FROM bookings
+        WHERE room_id = $1
+        AND status IN ('confirmed', 'checked_in')
+        AND check_out_date > $2
+        ORDER BY check_in_date
+        "#,
+        room.id,
+        today
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let blocks = sqlx::query!(
+        r#"
+        SELECT id, start_date, end_date
+        FROM room_blocks
+        WHERE room_id = $1
+        AND import_id IS NULL
+        AND end_date >= $2
+        ORDER BY start_date
+        "#,
+        room.id,
+        today
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let events: Vec<CalendarEvent> = bookings
+        .into_iter()
+        .map(|b| CalendarEvent {
+            uid: format!("booking-{}@hotel-booking-backend", b.id),
+            start: b.check_in_date,
+            end: b.check_out_date - Duration::days(1),
+            summary: "Reserved".to_string(),
+        })
+        .chain(blocks.into_iter().map(|b| CalendarEvent {
+            uid: format!("block-{}@hotel-booking-backend", b.id),
+            start: b.start_date,
+            end: b.end_date,
+            summary: "Not available".to_string(),
+        }))
+        .collect();
+
+    let name = format!("{} - Room {}", room.hotel_name, room.room_number);
+    let body = ical::render(&name, &events, Utc::now().naive_utc());
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"room-{}.ics\"", room.id)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Subscribes the room to another platform's iCal feed and runs the first
+/// sync straight away. The import is kept even if that sync fails; the
+/// error is recorded on it.
+pub async fn create_calendar_import(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+    Json(payload): Json<CreateCalendarImportRequest>,
+) -> (StatusCode, Json<ApiResponse<CalendarImportResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let name = match payload.name.as_deref().map(str::trim) {
+        Some(v) if !v.is_empty() && v.len() <= 100 => v.to_string(),
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let url = match payload.url.as_deref().map(str::trim).map(Url::parse) {
+        Some(Ok(u)) => u,
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_CALENDAR_URL"),
+    };
+
+    // Calendars are fetched from inside our network, so they must be public.
+    if outbound::resolve(&url).await.is_err() {
+        return error(StatusCode::BAD_REQUEST, "INVALID_CALENDAR_URL");
+    }
+
+    let url = url.to_string();
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO room_calendar_imports (hotel_id, room_id, name, url, created_by)
+        SELECT hotel_id, id, $2, $3, $4 FROM rooms WHERE id = $1
+        ON CONFLICT (room_id, url) DO NOTHING
+        RETURNING id
+        "#,
+        room_id,
+        name,
+        url,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let import_id = match inserted {
+        Some(v) => v,
+        None => return error(StatusCode::BAD_REQUEST, "CALENDAR_IMPORT_EXISTS"),
+    };
+
+    // A calendar that cannot be read yet keeps its error on the import for
+    // the owner to see; the sync job tries again later.
+    if let Err(e) = sync::sync_import(&pool, import_id).await {
+        eprintln!("calendar sync {import_id}: {e}");
+    }
+
+    let import = load_import(&pool, import_id).await;
+
+    (StatusCode::CREATED, Json(ApiResponse::success(import)))
+}
+
+pub async fn list_calendar_imports(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<Vec<CalendarImportResponse>>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let imports = sqlx::query!(
+        r#"
+        SELECT id, room_id, name, url, last_synced_at, last_error, created_at
+        FROM room_calendar_imports
+        WHERE room_id = $1
+        ORDER BY created_at
+        "#,
+        room_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = imports
+        .into_iter()
+        .map(|i| CalendarImportResponse {
+            id: i.id.to_string(),
+            roomId: i.room_id.to_string(),
+            name: i.name,
+            url: i.url,
+            lastSyncedAt: i.last_synced_at.map(|d| d.and_utc().to_rfc3339()),
+            lastError: i.last_error,
+            createdAt: i.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// Stops syncing a calendar and removes the blocks it created.
+pub async fn delete_calendar_import(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id, import_id)): Path<(String, String, String)>,
+) -> (StatusCode, Json<ApiResponse<DeleteCalendarImportResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let import_id = match Uuid::parse_str(&import_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "CALENDAR_IMPORT_NOT_FOUND"),
+    };
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let deleted = sqlx::query!(
+        "DELETE FROM room_calendar_imports WHERE id = $1 AND room_id = $2",
+        import_id,
+        room_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        return error(StatusCode::NOT_FOUND, "CALENDAR_IMPORT_NOT_FOUND");
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteCalendarImportResponse {
+            id: import_id.to_string(),
+        })),
+    )
+}
+
+/// Syncs one imported calendar now instead of waiting for the next run of
+/// the background job.
+pub async fn sync_calendar_import(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id, import_id)): Path<(String, String, String)>,
+) -> (StatusCode, Json<ApiResponse<CalendarSyncResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let import_id = match Uuid::parse_str(&import_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "CALENDAR_IMPORT_NOT_FOUND"),
+    };
+
+    let room_id = match find_owned_room(&pool, &auth, &hotel_id, &room_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let exists = sqlx::query_scalar!(
+        "SELECT id FROM room_calendar_imports WHERE id = $1 AND room_id = $2",
+        import_id,
+        room_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    if exists.is_none() {
+        return error(StatusCode::NOT_FOUND, "CALENDAR_IMPORT_NOT_FOUND");
+    }
+
+    let outcome: SyncOutcome = match sync::sync_import(&pool, import_id).await {
+        Ok(v) => v,
+        Err(SyncError::Db(e)) => {
+            eprintln!("calendar sync {import_id}: {e}");
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR");
+        }
+        Err(_) => return error(StatusCode::BAD_GATEWAY, "CALENDAR_SYNC_FAILED"),
+    };
+
+    let response = CalendarSyncResponse {
+        importId: import_id.to_string(),
+        events: outcome.events,
+        removed: outcome.removed,
+        conflictingBookings: outcome.conflicts.iter().map(Uuid::to_string).collect(),
+    };
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+async fn load_import(pool: &PgPool, import_id: Uuid) -> CalendarImportResponse {
+    let i = sqlx::query!(
+        r#"
+        SELECT id, room_id, name, url, last_synced_at, last_error, created_at
+        FROM room_calendar_imports
+        WHERE id = $1
+        "#,
+        import_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    CalendarImportResponse {
+        id: i.id.to_string(),
+        roomId: i.room_id.to_string(),
+        name: i.name,
+        url: i.url,
+        lastSyncedAt: i.last_synced_at.map(|d| d.and_utc().to_rfc3339()),
+        lastError: i.last_error,
+        createdAt: i.created_at.and_utc().to_rfc3339(),
+    }
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,227 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use sqlx::PgPool;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{
+        cancellation_policies::{CreateCancellationPolicyRequest, CancellationPolicyResponse,
+        DeleteCancellationPolicyResponse},
+        response::ApiResponse,
+    },
+};
+
+pub async fn create_cancellation_policy(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+    Json(payload): Json<CreateCancellationPolicyRequest>,
+) -> (StatusCode, Json<ApiResponse<CancellationPolicyResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match find_owned_hotel(&pool, &auth, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let name = match payload.name {
+        Some(v) if !v.trim().is_empty() => v,
+        _ => return invalid_request(),
+    };
+
+    let hundred = BigDecimal::from(100);
+
+    let (free_days, penalty) = if payload.nonRefundable.unwrap_or(false) {
+        if payload.freeCancellationDays.is_some() || payload.penaltyPercent.is_some() {
+            return invalid_request();
+        }
+        (None, hundred)
+    } else {
+        let free_days = match payload.freeCancellationDays {
+            Some(v) if v >= 0 => v,
+            _ => return invalid_request(),
+        };
+
+        let penalty = match payload.penaltyPercent.as_deref().map(BigDecimal::from_str) {
+            Some(Ok(v)) if v >= BigDecimal::from(0) && v <= hundred => v,
+            Some(_) => return invalid_request(),
+            None => hundred,
+        };
+
+        (Some(free_days), penalty)
+    };
+
+    let policy_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO cancellation_policies (id, hotel_id, name, free_cancellation_days, penalty_percent)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        policy_id,
+        hotel_id,
+        name,
+        free_days,
+        penalty
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(policy_response(policy_id, hotel_id, name, free_days, penalty))),
+    )
+}
+
+pub async fn list_cancellation_policies(
+    _auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<Vec<CancellationPolicyResponse>>>) {
+
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    let hotel = sqlx::query!("SELECT id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+
+    if hotel.is_none() {
+        return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND");
+    }
+
+    let policies = sqlx::query!(
+        r#"
+        SELECT id, name, free_cancellation_days, penalty_percent
+        FROM cancellation_policies
+        WHERE hotel_id = $1
+        ORDER BY created_at, id
+        "#,
+        hotel_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = policies
+        .into_iter()
+        .map(|p| policy_response(p.id, hotel_id, p.name, p.free_cancellation_days, p.penalty_percent))
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+/// Rooms and rates using the policy fall back to the standard terms;
+/// existing bookings keep the terms they were made under.
+pub async fn delete_cancellation_policy(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, policy_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<DeleteCancellationPolicyResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match find_owned_hotel(&pool, &auth, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let policy_id = match Uuid::parse_str(&policy_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "POLICY_NOT_FOUND"),
+    };
+
+    let deleted = sqlx::query!(
+        "DELETE FROM cancellation_policies WHERE id = $1 AND hotel_id = $2",
+        policy_id,
+        hotel_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        return error(StatusCode::NOT_FOUND, "POLICY_NOT_FOUND");
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteCancellationPolicyResponse {
+            id: policy_id.to_string(),
+        })),
+    )
+}
+
+/// Resolves a policy id given for a room or rate of `hotel_id`.
+pub(crate) async fn find_hotel_policy(pool: &PgPool, hotel_id: Uuid, policy_id: &str) -> Option<Uuid> {
+    let policy_id = Uuid::parse_str(policy_id).ok()?;
+
+    sqlx::query!(
+        "SELECT id FROM cancellation_policies WHERE id = $1 AND hotel_id = $2",
+        policy_id,
+        hotel_id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+    .map(|p| p.id)
+}
+
+fn policy_response(
+    id: Uuid,
+    hotel_id: Uuid,
+    name: String,
+    free_days: Option<i32>,
+    penalty: BigDecimal,
+) -> CancellationPolicyResponse {
+    CancellationPolicyResponse {
+        id: id.to_string(),
+        hotelId: hotel_id.to_string(),
+        name,
+        freeCancellationDays: free_days,
+        nonRefundable: free_days.is_none() && penalty == BigDecimal::from(100),
+        penaltyPercent: penalty.to_string(),
+    }
+}
+
+async fn find_owned_hotel(
+    pool: &PgPool,
+    auth: &AuthUser,
+    hotel_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let hotel_id = Uuid::parse_str(hotel_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"))?;
+
+    let hotel = sqlx::query!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+    match hotel {
+        Some(h) if h.owner_id == auth.user_id => Ok(hotel_id),
+        Some(_) => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => Err((StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND")),
+    }
+}
+
+fn invalid_request() -> (StatusCode, Json<ApiResponse<CancellationPolicyResponse>>) {
+    error(StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
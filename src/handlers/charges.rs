@@ -0,0 +1,235 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use sqlx::PgPool;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{
+        charges::{CreateChargeRequest, ChargeResponse, DeleteChargeResponse},
+        response::ApiResponse,
+    },
+    pricing::{Calculation, ChargeBasis, ChargeCategory},
+};
+
+pub async fn create_charge(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+    Json(payload): Json<CreateChargeRequest>,
+) -> (StatusCode, Json<ApiResponse<ChargeResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match find_owned_hotel(&pool, &auth, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let name = match payload.name {
+        Some(v) if !v.trim().is_empty() => v,
+        _ => return invalid_request(),
+    };
+
+    let category = match payload.category.as_deref().and_then(ChargeCategory::parse) {
+        Some(v) => v,
+        None => return invalid_request(),
+    };
+
+    let calculation = match payload.calculation.as_deref().and_then(Calculation::parse) {
+        Some(v) => v,
+        None => return invalid_request(),
+    };
+
+    let amount = match payload.amount.as_deref().map(BigDecimal::from_str) {
+        Some(Ok(v)) if v >= BigDecimal::from(0) => v,
+        _ => return invalid_request(),
+    };
+
+    // Percentages are always taken of the whole room subtotal.
+    let basis = match (calculation, payload.basis.as_deref()) {
+        (Calculation::Percentage, None | Some("per_stay")) => ChargeBasis::Stay,
+        (Calculation::Percentage, Some(_)) => return invalid_request(),
+        (Calculation::Fixed, Some(b)) => match ChargeBasis::parse(b) {
+            Some(v) => v,
+            None => return invalid_request(),
+        },
+        (Calculation::Fixed, None) => return invalid_request(),
+    };
+
+    if calculation == Calculation::Percentage && amount > BigDecimal::from(100) {
+        return invalid_request();
+    }
+
+    let inclusive = payload.inclusive.unwrap_or(false);
+
+    let charge_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO hotel_charges (
+            id,
+            hotel_id,
+            name,
+            category,
+            calculation,
+            amount,
+            basis,
+            inclusive
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        "#,
+        charge_id,
+        hotel_id,
+        name,
+        category.as_str(),
+        calculation.as_str(),
+        amount,
+        basis.as_str(),
+        inclusive
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = ChargeResponse {
+        id: charge_id.to_string(),
+        hotelId: hotel_id.to_string(),
+        name,
+        category: category.as_str().to_string(),
+        calculation: calculation.as_str().to_string(),
+        amount: amount.to_string(),
+        basis: basis.as_str().to_string(),
+        inclusive,
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn list_charges(
+    _auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<Vec<ChargeResponse>>>) {
+
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    let hotel = sqlx::query!("SELECT id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+
+    if hotel.is_none() {
+        return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND");
+    }
+
+    let charges = sqlx::query!(
+        r#"
+        SELECT id, name, category, calculation, amount, basis, inclusive
+        FROM hotel_charges
+        WHERE hotel_id = $1
+        ORDER BY created_at, id
+        "#,
+        hotel_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = charges
+        .into_iter()
+        .map(|c| ChargeResponse {
+            id: c.id.to_string(),
+            hotelId: hotel_id.to_string(),
+            name: c.name,
+            category: c.category,
+            calculation: c.calculation,
+            amount: c.amount.to_string(),
+            basis: c.basis,
+            inclusive: c.inclusive,
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn delete_charge(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, charge_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<DeleteChargeResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match find_owned_hotel(&pool, &auth, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let charge_id = match Uuid::parse_str(&charge_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "CHARGE_NOT_FOUND"),
+    };
+
+    let deleted = sqlx::query!(
+        "DELETE FROM hotel_charges WHERE id = $1 AND hotel_id = $2",
+        charge_id,
+        hotel_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        return error(StatusCode::NOT_FOUND, "CHARGE_NOT_FOUND");
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteChargeResponse {
+            id: charge_id.to_string(),
+        })),
+    )
+}
+
+async fn find_owned_hotel(
+    pool: &PgPool,
+    auth: &AuthUser,
+    hotel_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let hotel_id = Uuid::parse_str(hotel_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"))?;
+
+    let hotel = sqlx::query!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+    match hotel {
+        Some(h) if h.owner_id == auth.user_id => Ok(hotel_id),
+        Some(_) => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => Err((StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND")),
+    }
+}
+
+fn invalid_request() -> (StatusCode, Json<ApiResponse<ChargeResponse>>) {
+    error(StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
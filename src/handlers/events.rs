@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use sqlx::PgPool;
+use std::{convert::Infallible, env, time::Duration};
+use tokio::time::{interval_at, Instant};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, IntervalStream},
+    StreamExt,
+};
+use uuid::Uuid;
+
+use crate::{
+    events::{HotelEvent, HotelEvents},
+    handlers::auth_middleware::AuthUser,
+    models::response::ApiResponse,
+};
+
+/// Server-sent events for one hotel, for front desk screens: each message
+/// is named after its type (`booking.created`, `booking.cancelled`,
+/// `availability.changed`) and carries its JSON payload. A `ready` event
+/// marks the start of the stream and `resync` means events may have been
+/// missed, so the client should refetch what it shows. Access is checked
+/// again every `EVENT_STREAM_ACCESS_CHECK_SECONDS` and the stream ends once
+/// the hotel is gone or no longer the caller's.
+pub async fn hotel_event_stream(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(events): State<HotelEvents>,
+    Path(hotel_id): Path<String>,
+) -> Response {
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    if let Err((status, code)) = check_access(&pool, &auth, hotel_id).await.unwrap() {
+        return error(status, code);
+    }
+
+    // Subscribed before `ready` is sent, so nothing after it is missed.
+    let updates = BroadcastStream::new(events.subscribe()).filter_map(move |message| match message {
+        Ok(event) if event.hotel_id.is_none_or(|id| id == hotel_id) => Some(to_sse(&event)),
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(_)) => Some(to_sse(&HotelEvent::resync())),
+    });
+
+    let ready = Event::default()
+        .event("ready")
+        .data(format!(r#"{{"type":"ready","hotelId":"{hotel_id}"}}"#));
+
+    // Each check yields `None` once access is lost, which ends the stream.
+    let period = access_check_interval();
+    let revoked = IntervalStream::new(interval_at(Instant::now() + period, period))
+        .then(move |_| {
+            let pool = pool.clone();
+            let auth = auth.clone();
+            async move { matches!(check_access(&pool, &auth, hotel_id).await, Ok(Ok(()))) }
+        })
+        .filter_map(|allowed| (!allowed).then_some(None));
+
+    let stream = tokio_stream::once(Ok(ready))
+        .chain(updates)
+        .map(Some)
+        .merge(revoked)
+        .map_while(|event| event);
+
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+async fn check_access(
+    pool: &PgPool,
+    auth: &AuthUser,
+    hotel_id: Uuid,
+) -> Result<Result<(), (StatusCode, &'static str)>, sqlx::Error> {
+    let owner = sqlx::query_scalar!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(match owner {
+        None => Err((StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND")),
+        Some(owner_id) if auth.role != "admin" && owner_id != auth.user_id => {
+            Err((StatusCode::FORBIDDEN, "FORBIDDEN"))
+        }
+        Some(_) => Ok(()),
+    })
+}
+
+fn access_check_interval() -> Duration {
+    let seconds = env::var("EVENT_STREAM_ACCESS_CHECK_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+
+    Duration::from_secs(seconds)
+}
+
+fn to_sse(event: &HotelEvent) -> Result<Event, Infallible> {
+    Ok(Event::default().event(&event.kind).data(&event.payload))
+}
+
+fn error(status: StatusCode, code: &str) -> Response {
+    (status, Json(ApiResponse::<()>::error(code))).into_response()
+}
@@ -0,0 +1,146 @@
+use axum::{extract::State, http::StatusCode, Json};
+use sqlx::PgPool;
+use sqlx::types::BigDecimal;
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{
+        exchange_rates::{UploadExchangeRatesRequest, ExchangeRatesResponse},
+        response::ApiResponse,
+    },
+    pricing::parse_currency,
+};
+
+/// Digits after the point `exchange_rates.rate` keeps; it is NUMERIC(20,10),
+/// so rates must also stay below 10^10.
+const RATE_SCALE: i64 = 10;
+const RATE_LIMIT: u64 = 10_000_000_000;
+
+/// Whether `rate` can be stored without overflowing the column or rounding
+/// to zero.
+fn storable_rate(rate: &BigDecimal) -> bool {
+    let stored = rate.round(RATE_SCALE);
+
+    stored > BigDecimal::from(0) && stored < BigDecimal::from(RATE_LIMIT)
+}
+
+/// Replaces the whole rate table. Rates are units of each currency per one
+/// unit of `baseCurrency`.
+pub async fn upload_exchange_rates(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(payload): Json<UploadExchangeRatesRequest>,
+) -> (StatusCode, Json<ApiResponse<ExchangeRatesResponse>>) {
+
+    if auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let base = match payload.baseCurrency.as_deref().and_then(parse_currency) {
+        Some(v) => v,
+        None => return invalid_request(),
+    };
+
+    let mut rates = BTreeMap::new();
+
+    for (currency, rate) in payload.rates.unwrap_or_default() {
+        let currency = match parse_currency(&currency) {
+            Some(v) => v,
+            None => return invalid_request(),
+        };
+
+        let rate = match BigDecimal::from_str(&rate) {
+            Ok(v) if storable_rate(&v) => v,
+            _ => return invalid_request(),
+        };
+
+        if rates.insert(currency, rate).is_some() {
+            return invalid_request();
+        }
+    }
+
+    match rates.get(&base) {
+        Some(rate) if *rate != BigDecimal::from(1) => return invalid_request(),
+        Some(_) => {}
+        None => {
+            rates.insert(base.clone(), BigDecimal::from(1));
+        }
+    }
+
+    let mut tx = pool.begin().await.unwrap();
+
+    sqlx::query!("DELETE FROM exchange_rates")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    for (currency, rate) in &rates {
+        sqlx::query!(
+            r#"
+            INSERT INTO exchange_rates (currency, base_currency, rate)
+            VALUES ($1, $2, $3)
+            "#,
+            currency,
+            base,
+            rate
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    }
+
+    tx.commit().await.unwrap();
+
+    let response = ExchangeRatesResponse {
+        baseCurrency: Some(base),
+        rates: rates
+            .into_iter()
+            .map(|(currency, rate)| (currency, rate.to_string()))
+            .collect(),
+        updatedAt: Some(chrono::Utc::now().to_rfc3339()),
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn get_exchange_rates(
+    _auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> (StatusCode, Json<ApiResponse<ExchangeRatesResponse>>) {
+
+    let rows = sqlx::query!(
+        "SELECT currency, base_currency, rate, updated_at FROM exchange_rates ORDER BY currency"
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = ExchangeRatesResponse {
+        baseCurrency: rows.first().map(|r| r.base_currency.clone()),
+        updatedAt: rows
+            .first()
+            .and_then(|r| r.updated_at)
+            .map(|d| d.and_utc().to_rfc3339()),
+        rates: rows
+            .into_iter()
+            .map(|r| (r.currency, r.rate.to_string()))
+            .collect(),
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+fn invalid_request() -> (StatusCode, Json<ApiResponse<ExchangeRatesResponse>>) {
+    error(StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,324 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::{auth_middleware::AuthUser, guest_details, loyalty},
+    lifecycle::BookingStatus,
+    mail::{outbox, Template},
+    models::{
+        bookings::{BookingEmailResponse, BookingStatusResponse, HotelBookingListQuery, HotelBookingResponse},
+        response::ApiResponse,
+    },
+};
+
+/// Bookings of one hotel with who is staying, for its owner.
+pub async fn list_hotel_bookings(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+    Query(filters): Query<HotelBookingListQuery>,
+) -> (StatusCode, Json<ApiResponse<Vec<HotelBookingResponse>>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    let check_in = match filters.checkInDate.as_deref() {
+        Some(v) => match NaiveDate::parse_from_str(v, "%Y-%m-%d") {
+            Ok(d) => Some(d),
+            Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_DATES"),
+        },
+        None => None,
+    };
+
+    let owner = sqlx::query_scalar!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+
+    match owner {
+        None => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+        Some(owner_id) if auth.role != "admin" && owner_id != auth.user_id => {
+            return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+        }
+        Some(_) => {}
+    }
+
+    let bookings = sqlx::query!(
+        r#"
+        SELECT
+            b.id,
+            b.room_id,
+            r.room_number,
+            u.name AS customer_name,
+            u.email AS customer_email,
+            b.check_in_date,
+            b.check_out_date,
+            b.guests,
+            b.total_price,
+            b.currency,
+            b.status,
+            b.booking_date,
+            b.checked_in_at,
+            b.checked_out_at
+        FROM bookings b
+        JOIN rooms r ON r.id = b.room_id
+        JOIN users u ON u.id = b.user_id
+        WHERE b.hotel_id = $1
+        AND ($2::text IS NULL OR b.status = $2)
+        AND ($3::date IS NULL OR b.check_in_date = $3)
+        ORDER BY b.check_in_date, r.room_number
+        "#,
+        hotel_id,
+        filters.status,
+        check_in
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let booking_ids: Vec<Uuid> = bookings.iter().map(|b| b.id).collect();
+    let mut details = guest_details::load_many(&pool, &booking_ids).await;
+
+    let response = bookings
+        .into_iter()
+        .map(|b| HotelBookingResponse {
+            id: b.id.to_string(),
+            roomId: b.room_id.to_string(),
+            roomNumber: b.room_number,
+            customerName: b.customer_name,
+            customerEmail: b.customer_email,
+            checkInDate: b.check_in_date.to_string(),
+            checkOutDate: b.check_out_date.to_string(),
+            guests: b.guests,
+            totalPrice: b.total_price.to_string(),
+            currency: b.currency,
+            status: BookingStatus::from_column(b.status.as_deref()).as_str().to_string(),
+            bookingDate: b
+                .booking_date
+                .map(|d| d.and_utc().to_rfc3339())
+                .unwrap_or_else(|| Utc::now().to_rfc3339()),
+            checkedInAt: b.checked_in_at.map(|d| d.and_utc().to_rfc3339()),
+            checkedOutAt: b.checked_out_at.map(|d| d.and_utc().to_rfc3339()),
+            guestDetails: details.remove(&b.id),
+        })
+        .collect();
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// Emails sent or queued about one of the hotel's bookings, oldest first,
+/// so the front desk can tell whether the guest has heard from them.
+pub async fn list_booking_emails(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, booking_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<Vec<BookingEmailResponse>>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let (hotel_id, booking_id) = match (Uuid::parse_str(&hotel_id), Uuid::parse_str(&booking_id)) {
+        (Ok(h), Ok(b)) => (h, b),
+        _ => return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND"),
+    };
+
+    let owner = sqlx::query_scalar!(
+        r#"
+        SELECT h.owner_id
+        FROM bookings b
+        JOIN hotels h ON h.id = b.hotel_id
+        WHERE b.id = $1 AND b.hotel_id = $2
+        "#,
+        booking_id,
+        hotel_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    match owner {
+        None => return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND"),
+        Some(owner_id) if auth.role != "admin" && owner_id != auth.user_id => {
+            return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+        }
+        Some(_) => {}
+    }
+
+    let emails = sqlx::query!(
+        r#"
+        SELECT id, template, recipient, subject, status, attempts, last_attempt_at,
+               next_attempt_at, last_error, created_at, sent_at
+        FROM email_outbox
+        WHERE booking_id = $1
+        ORDER BY created_at, template
+        "#,
+        booking_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = emails
+        .into_iter()
+        .map(|e| BookingEmailResponse {
+            id: e.id.to_string(),
+            template: e.template,
+            recipient: e.recipient,
+            subject: e.subject,
+            nextAttemptAt: (e.status == "pending").then(|| e.next_attempt_at.and_utc().to_rfc3339()),
+            status: e.status,
+            attempts: e.attempts,
+            lastAttemptAt: e.last_attempt_at.map(|t| t.and_utc().to_rfc3339()),
+            lastError: e.last_error,
+            createdAt: e.created_at.and_utc().to_rfc3339(),
+            sentAt: e.sent_at.map(|t| t.and_utc().to_rfc3339()),
+        })
+        .collect();
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// Marks the guest as arrived. Allowed from the check-in date until the day
+/// before check-out.
+pub async fn check_in_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, booking_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<BookingStatusResponse>>) {
+    change_status(auth, pool, hotel_id, booking_id, BookingStatus::CheckedIn).await
+}
+
+/// Marks the guest as departed, which makes the stay reviewable.
+pub async fn check_out_booking(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, booking_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<BookingStatusResponse>>) {
+    change_status(auth, pool, hotel_id, booking_id, BookingStatus::CheckedOut).await
+}
+
+/// Records that the guest never arrived. Allowed from the check-in date on.
+pub async fn mark_no_show(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, booking_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<BookingStatusResponse>>) {
+    change_status(auth, pool, hotel_id, booking_id, BookingStatus::NoShow).await
+}
+
+async fn change_status(
+    auth: AuthUser,
+    pool: PgPool,
+    hotel_id: String,
+    booking_id: String,
+    next: BookingStatus,
+) -> (StatusCode, Json<ApiResponse<BookingStatusResponse>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let (hotel_id, booking_id) = match (Uuid::parse_str(&hotel_id), Uuid::parse_str(&booking_id)) {
+        (Ok(h), Ok(b)) => (h, b),
+        _ => return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND"),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let booking = sqlx::query!(
+        r#"
+        SELECT b.status, b.check_in_date, b.check_out_date, h.owner_id
+        FROM bookings b
+        JOIN hotels h ON h.id = b.hotel_id
+        WHERE b.id = $1 AND b.hotel_id = $2
+        FOR UPDATE OF b
+        "#,
+        booking_id,
+        hotel_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .unwrap();
+
+    let booking = match booking {
+        Some(b) => b,
+        None => {
+            tx.rollback().await.unwrap();
+            return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND");
+        }
+    };
+
+    if auth.role != "admin" && booking.owner_id != auth.user_id {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let current = BookingStatus::from_column(booking.status.as_deref());
+
+    if let Err(code) = current.transition(next) {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, code);
+    }
+
+    let today = Utc::now().date_naive();
+
+    if matches!(next, BookingStatus::CheckedIn | BookingStatus::NoShow)
+        && today < booking.check_in_date
+    {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, "TOO_EARLY");
+    }
+
+    if next == BookingStatus::CheckedIn && today >= booking.check_out_date {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, "STAY_COMPLETED");
+    }
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE bookings
+        SET status = $2,
+            checked_in_at = CASE WHEN $2 = 'checked_in' THEN now() AT TIME ZONE 'utc' ELSE checked_in_at END,
+            checked_out_at = CASE WHEN $2 = 'checked_out' THEN now() AT TIME ZONE 'utc' ELSE checked_out_at END,
+            no_show_at = CASE WHEN $2 = 'no_show' THEN now() AT TIME ZONE 'utc' ELSE no_show_at END
+        WHERE id = $1
+        RETURNING checked_in_at, checked_out_at, no_show_at
+        "#,
+        booking_id,
+        next.as_str()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+
+    if next == BookingStatus::CheckedOut {
+        loyalty::award_stay(&mut tx, booking_id).await;
+        outbox::enqueue(&mut tx, booking_id, Template::ReviewInvitation).await.unwrap();
+    }
+
+    tx.commit().await.unwrap();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(BookingStatusResponse {
+            id: booking_id.to_string(),
+            status: next.as_str().to_string(),
+            checkedInAt: updated.checked_in_at.map(|t| t.and_utc().to_rfc3339()),
+            checkedOutAt: updated.checked_out_at.map(|t| t.and_utc().to_rfc3339()),
+            noShowAt: updated.no_show_at.map(|t| t.and_utc().to_rfc3339()),
+        })),
+    )
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
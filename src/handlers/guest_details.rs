@@ -0,0 +1,232 @@
+use chrono::NaiveTime;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::bookings::{GuestDetailsRequest, GuestDetailsResponse};
+
+/// Oldest age that still counts as a child.
+pub const MAX_CHILD_AGE: i32 = 17;
+
+/// Checked guest details, ready to store with a booking.
+pub(crate) struct GuestDetails {
+    pub lead_guest_name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub adults: i32,
+    pub children_ages: Vec<i32>,
+    pub estimated_arrival: Option<NaiveTime>,
+    pub special_requests: Option<String>,
+}
+
+/// Column limits in characters, checked up front so a booking never gets as
+/// far as paying before its details fail to save.
+const MAX_NAME_CHARS: usize = 255;
+const MAX_EMAIL_CHARS: usize = 255;
+const MAX_PHONE_CHARS: usize = 50;
+
+/// Occupancy limits of the room being booked.
+pub(crate) struct RoomOccupancy {
+    pub max_adults: Option<i32>,
+    pub max_children: Option<i32>,
+}
+
+impl GuestDetails {
+    /// Validates `request` for a booking of `guests` people in a room with
+    /// the given limits. The total against `max_occupancy` is checked by the
+    /// caller.
+    pub fn parse(
+        request: &GuestDetailsRequest,
+        guests: i32,
+        room: &RoomOccupancy,
+    ) -> Result<Self, &'static str> {
+        let lead_guest_name = request.leadGuestName.trim();
+
+        if lead_guest_name.is_empty() || lead_guest_name.chars().count() > MAX_NAME_CHARS {
+            return Err("INVALID_GUEST_DETAILS");
+        }
+
+        if request.childrenAges.iter().any(|age| !(0..=MAX_CHILD_AGE).contains(age)) {
+            return Err("INVALID_GUEST_DETAILS");
+        }
+
+        let children = request.childrenAges.len() as i32;
+        let adults = request.adults.unwrap_or(guests - children);
+
+        if adults < 1 || adults + children != guests {
+            return Err("INVALID_GUEST_DETAILS");
+        }
+
+        if room.max_adults.is_some_and(|max| adults > max)
+            || room.max_children.is_some_and(|max| children > max)
+        {
+            return Err("INVALID_CAPACITY");
+        }
+
+        let email = non_empty(&request.email);
+
+        if email
+            .as_deref()
+            .is_some_and(|e| !e.contains('@') || e.chars().count() > MAX_EMAIL_CHARS)
+        {
+            return Err("INVALID_GUEST_DETAILS");
+        }
+
+        let phone = non_empty(&request.phone);
+
+        if phone.as_deref().is_some_and(|p| p.chars().count() > MAX_PHONE_CHARS) {
+            return Err("INVALID_GUEST_DETAILS");
+        }
+
+        let estimated_arrival = match request.estimatedArrival.as_deref() {
+            Some(v) => match NaiveTime::parse_from_str(v, "%H:%M") {
+                Ok(t) => Some(t),
+                Err(_) => return Err("INVALID_GUEST_DETAILS"),
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            lead_guest_name: lead_guest_name.to_string(),
+            email,
+            phone,
+            adults,
+            children_ages: request.childrenAges.clone(),
+            estimated_arrival,
+            special_requests: non_empty(&request.specialRequests),
+        })
+    }
+
+    pub fn response(&self) -> GuestDetailsResponse {
+        GuestDetailsResponse {
+            leadGuestName: self.lead_guest_name.clone(),
+            email: self.email.clone(),
+            phone: self.phone.clone(),
+            adults: self.adults,
+            childrenAges: self.children_ages.clone(),
+            estimatedArrival: self.estimated_arrival.map(format_arrival),
+            specialRequests: self.special_requests.clone(),
+        }
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn format_arrival(time: NaiveTime) -> String {
+    time.format("%H:%M").to_string()
+}
+
+/// Stores or replaces the guest details of a booking.
+pub(crate) async fn save(conn: &mut PgConnection, booking_id: Uuid, details: &GuestDetails) {
+    sqlx::query!(
+        r#"
+        INSERT INTO booking_guest_details (
+            booking_id,
+            lead_guest_name,
+            email,
+            phone,
+            adults,
+            children_ages,
+            estimated_arrival,
+            special_requests
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        ON CONFLICT (booking_id) DO UPDATE
+        SET lead_guest_name = EXCLUDED.lead_guest_name,
+            email = EXCLUDED.email,
+            phone = EXCLUDED.phone,
+            adults = EXCLUDED.adults,
+            children_ages = EXCLUDED.children_ages,
+            estimated_arrival = EXCLUDED.estimated_arrival,
+            special_requests = EXCLUDED.special_requests
+        "#,
+        booking_id,
+        details.lead_guest_name,
+        details.email,
+        details.phone,
+        details.adults,
+        &details.children_ages,
+        details.estimated_arrival,
+        details.special_requests
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+/// The stored details of a booking in request form, so a modification that
+/// leaves them out can check them against the changed stay.
+pub(crate) async fn load_request(
+    conn: &mut PgConnection,
+    booking_id: Uuid,
+) -> Option<GuestDetailsRequest> {
+    let row = sqlx::query!(
+        r#"
+        SELECT lead_guest_name, email, phone, children_ages, estimated_arrival, special_requests
+        FROM booking_guest_details
+        WHERE booking_id = $1
+        "#,
+        booking_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()?;
+
+    // Adults are left to follow the new guest count.
+    Some(GuestDetailsRequest {
+        leadGuestName: row.lead_guest_name,
+        email: row.email,
+        phone: row.phone,
+        adults: None,
+        childrenAges: row.children_ages,
+        estimatedArrival: row.estimated_arrival.map(format_arrival),
+        specialRequests: row.special_requests,
+    })
+}
+
+pub(crate) async fn load_many(
+    pool: &PgPool,
+    booking_ids: &[Uuid],
+) -> HashMap<Uuid, GuestDetailsResponse> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            booking_id,
+            lead_guest_name,
+            email,
+            phone,
+            adults,
+            children_ages,
+            estimated_arrival,
+            special_requests
+        FROM booking_guest_details
+        WHERE booking_id = ANY($1)
+        "#,
+        booking_ids
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    rows.into_iter()
+        .map(|r| {
+            let details = GuestDetails {
+                lead_guest_name: r.lead_guest_name,
+                email: r.email,
+                phone: r.phone,
+                adults: r.adults,
+                children_ages: r.children_ages,
+                estimated_arrival: r.estimated_arrival,
+                special_requests: r.special_requests,
+            };
+
+            (r.booking_id, details.response())
+        })
+        .collect()
+}
@@ -1,17 +1,26 @@
 use axum::{extract::{State, Query, Path}, http::StatusCode, Json};
 use bigdecimal::ToPrimitive;
+use chrono::NaiveDate;
 use sqlx::PgPool;
 use uuid::Uuid;
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 use sqlx::types::BigDecimal;
 
 use crate::{
-    handlers::auth_middleware::AuthUser,
+    handlers::{
+        auth_middleware::AuthUser,
+        photos::{photo_response, PhotoRow},
+    },
+    lifecycle,
     models::{
         hotels::{CreateHotelRequest, HotelResponse, HotelSearchQuery, HotelListResponse,
                 HotelDetailResponse, HotelRoomResponse},
+        exchange_rates::CurrencyQuery,
+        photos::PhotoResponse,
         response::ApiResponse,
     },
+    pricing::{parse_currency, Converter},
+    storage::ObjectStorage,
 };
 
 pub async fn create_hotel(
@@ -45,14 +54,20 @@ pub async fn create_hotel(
 
     let amenities = payload.amenities.unwrap_or_default();
 
+    let currency = match payload.currency.as_deref().map(parse_currency) {
+        Some(Some(v)) => v,
+        Some(None) => return invalid_request(),
+        None => "USD".to_string(),
+    };
+
     let hotel_id = Uuid::new_v4();
 
     sqlx::query!(
         r#"
         INSERT INTO hotels (
-            id, owner_id, name, description, city, country, amenities
+            id, owner_id, name, description, city, country, amenities, currency
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         "#,
         hotel_id,
         auth.user_id,
@@ -60,7 +75,8 @@ pub async fn create_hotel(
         payload.description,
         city,
         country,
-        &amenities
+        &amenities,
+        currency
     )
     .execute(&pool)
     .await
@@ -74,6 +90,7 @@ pub async fn create_hotel(
         city,
         country,
         amenities,
+        currency,
         rating: 0.0,
         totalReviews: 0,
     };
@@ -94,6 +111,7 @@ fn invalid_request() -> (StatusCode, Json<ApiResponse<HotelResponse>>) {
 pub async fn list_hotels(
     _auth: AuthUser,
     State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
     Query(filters): Query<HotelSearchQuery>,
 ) -> (StatusCode, Json<ApiResponse<Vec<HotelListResponse>>>) {
 
@@ -107,6 +125,44 @@ pub async fn list_hotels(
     .as_deref()
     .and_then(|v| BigDecimal::from_str(v).ok());
 
+    let check_in = filters
+    .checkInDate
+    .as_deref()
+    .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"));
+
+    let check_out = filters
+    .checkOutDate
+    .as_deref()
+    .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"));
+
+    // Dates are optional, but when given only rooms free for the whole stay count.
+    let (check_in, check_out) = match (check_in, check_out) {
+        (None, None) => (None, None),
+        (Some(Ok(ci)), Some(Ok(co))) if ci < co => (Some(ci), Some(co)),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error("INVALID_DATES")),
+            )
+        }
+    };
+
+    let mut conn = pool.acquire().await.unwrap();
+
+    let converter = match Converter::load(&mut conn, filters.currency.as_deref()).await {
+        Ok(v) => v,
+        Err(code) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(code)),
+            )
+        }
+    };
+
+    drop(conn);
+
+    // Price filters are in the requested display currency, so each room's
+    // price is converted with the same rates before comparing.
     let hotels = sqlx::query!(
         r#"
         SELECT
@@ -118,15 +174,33 @@ pub async fn list_hotels(
             h.amenities,
             h.rating,
             h.total_reviews,
+            h.currency,
             MIN(r.price_per_night) AS min_price
         FROM hotels h
         JOIN rooms r ON r.hotel_id = h.id
+        LEFT JOIN exchange_rates hx ON hx.currency = h.currency
+        LEFT JOIN exchange_rates tx ON tx.currency = UPPER($6)
         WHERE
             ($1::text IS NULL OR LOWER(h.city) = LOWER($1))
         AND ($2::text IS NULL OR LOWER(h.country) = LOWER($2))
-        AND ($3::numeric IS NULL OR r.price_per_night >= $3)
-        AND ($4::numeric IS NULL OR r.price_per_night <= $4)
+        AND ($3::numeric IS NULL OR r.price_per_night * COALESCE(tx.rate / hx.rate, 1) >= $3)
+        AND ($4::numeric IS NULL OR r.price_per_night * COALESCE(tx.rate / hx.rate, 1) <= $4)
         AND ($5::float8 IS NULL OR h.rating >= $5)
+        AND ($7::date IS NULL OR NOT EXISTS (
+            SELECT 1 FROM bookings b
+            WHERE b.room_id = r.id
+            AND b.status = ANY($9)
+            -- Only holds and unpaid bookings carry an expiry.
+            AND (b.hold_expires_at IS NULL OR b.hold_expires_at > now() AT TIME ZONE 'utc')
+            AND b.check_in_date < $8
+            AND b.check_out_date > $7
+        ))
+        AND ($7::date IS NULL OR NOT EXISTS (
+            SELECT 1 FROM room_blocks rb
+            WHERE rb.room_id = r.id
+            AND rb.start_date < $8
+            AND rb.end_date >= $7
+        ))
         GROUP BY h.id
         "#,
         filters.city,
@@ -134,11 +208,43 @@ pub async fn list_hotels(
         min_price,
         max_price,
         filters.minRating,
+        filters.currency,
+        check_in,
+        check_out,
+        &lifecycle::OCCUPYING[..] as &[&str],
     )
     .fetch_all(&pool)
     .await
     .unwrap();
 
+    let hotel_ids: Vec<Uuid> = hotels.iter().map(|h| h.id).collect();
+
+    let mut covers: HashMap<Uuid, PhotoRow> = sqlx::query_as!(
+        PhotoRow,
+        r#"
+        SELECT
+            id,
+            hotel_id,
+            room_id,
+            storage_key,
+            thumbnail_key,
+            content_type,
+            width,
+            height,
+            position,
+            is_cover
+        FROM photos
+        WHERE hotel_id = ANY($1) AND room_id IS NULL AND is_cover
+        "#,
+        &hotel_ids
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|p| (p.hotel_id, p))
+    .collect();
+
     let response = hotels
     .into_iter()
     .map(|h| HotelListResponse {
@@ -150,10 +256,15 @@ pub async fn list_hotels(
         amenities: h.amenities.unwrap_or_default(),
         rating: h.rating.and_then(|r| r.to_f64()).unwrap_or(0.0),
         totalReviews: h.total_reviews.unwrap_or(0),
+        displayCurrency: converter.display_currency(&h.currency),
         minPricePerNight: h
             .min_price
-            .map(|v| v.to_string())
+            .map(|v| converter.convert(&v, &h.currency).to_string())
             .unwrap_or_else(|| "0".to_string()),
+        currency: h.currency,
+        coverPhoto: covers
+            .remove(&h.id)
+            .map(|p| photo_response(p, storage.as_ref())),
     })
     .collect();
 
@@ -166,9 +277,25 @@ pub async fn list_hotels(
 pub async fn get_hotel_by_id(
     _auth: AuthUser,
     State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
     Path(hotel_id): Path<Uuid>,
+    Query(query): Query<CurrencyQuery>,
 ) -> (StatusCode, Json<ApiResponse<HotelDetailResponse>>) {
     
+    let mut conn = pool.acquire().await.unwrap();
+
+    let converter = match Converter::load(&mut conn, query.currency.as_deref()).await {
+        Ok(v) => v,
+        Err(code) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(code)),
+            )
+        }
+    };
+
+    drop(conn);
+
     let hotel = sqlx::query!(
         r#"
         SELECT
@@ -180,7 +307,8 @@ pub async fn get_hotel_by_id(
             country,
             amenities,
             rating,
-            total_reviews
+            total_reviews,
+            currency
         FROM hotels
         WHERE id = $1
         "#,
@@ -208,7 +336,9 @@ pub async fn get_hotel_by_id(
             room_number,
             room_type,
             price_per_night,
-            max_occupancy
+            max_occupancy,
+            max_adults,
+            max_children
         FROM rooms
         WHERE hotel_id = $1
         ORDER BY room_number
@@ -219,14 +349,56 @@ pub async fn get_hotel_by_id(
     .await
     .unwrap();
 
+    let photos = sqlx::query_as!(
+        PhotoRow,
+        r#"
+        SELECT
+            id,
+            hotel_id,
+            room_id,
+            storage_key,
+            thumbnail_key,
+            content_type,
+            width,
+            height,
+            position,
+            is_cover
+        FROM photos
+        WHERE hotel_id = $1
+        ORDER BY position
+        "#,
+        hotel_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let mut hotel_photos = Vec::new();
+    let mut room_photos: HashMap<Uuid, Vec<PhotoResponse>> = HashMap::new();
+
+    for p in photos {
+        match p.room_id {
+            Some(room_id) => room_photos
+                .entry(room_id)
+                .or_default()
+                .push(photo_response(p, storage.as_ref())),
+            None => hotel_photos.push(photo_response(p, storage.as_ref())),
+        }
+    }
+
     let rooms = rooms
         .into_iter()
         .map(|r| HotelRoomResponse {
             id: r.id.to_string(),
             roomNumber: r.room_number,
             roomType: r.room_type,
-            pricePerNight: r.price_per_night.to_string(),
+            pricePerNight: converter
+                .convert(&r.price_per_night, &hotel.currency)
+                .to_string(),
             maxOccupancy: r.max_occupancy,
+            maxAdults: r.max_adults,
+            maxChildren: r.max_children,
+            photos: room_photos.remove(&r.id).unwrap_or_default(),
         })
         .collect();
 
@@ -241,7 +413,10 @@ pub async fn get_hotel_by_id(
         amenities: hotel.amenities.unwrap_or_default(),
         rating: hotel.rating.and_then(|r| r.to_f64()).unwrap_or(0.0),
         totalReviews: hotel.total_reviews.unwrap_or(0),
+        displayCurrency: converter.display_currency(&hotel.currency),
+        currency: hotel.currency,
         rooms,
+        photos: hotel_photos,
     };
 
     (
@@ -0,0 +1,176 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRequestParts, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::env;
+
+use crate::{handlers::auth_middleware::AuthUser, models::response::ApiResponse};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set on responses that were replayed from a stored result.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Same as axum's default request body limit.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// A key whose first request never finished (the server went down
+/// mid-request) can be reused after this long.
+const ABANDONED_AFTER_SECONDS: f64 = 300.0;
+
+/// How long stored responses are kept, from IDEMPOTENCY_KEY_TTL_HOURS.
+pub fn key_ttl_hours() -> i64 {
+    env::var("IDEMPOTENCY_KEY_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(24)
+}
+
+/// Makes a create or payment endpoint safe to retry. A request carrying an
+/// `Idempotency-Key` header runs once per user and key; repeats with the same
+/// method, path and body get the stored response back, and repeats with a
+/// different request get IDEMPOTENCY_KEY_REUSED. Requests without the header
+/// pass straight through.
+///
+/// Server errors are not stored, so the client can retry them with the same
+/// key.
+pub async fn idempotent(
+    State(pool): State<PgPool>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(v) => match v.to_str() {
+            Ok(k) if !k.trim().is_empty() && k.len() <= 255 => k.to_string(),
+            _ => return error(StatusCode::BAD_REQUEST, "INVALID_IDEMPOTENCY_KEY"),
+        },
+        None => return next.run(request).await,
+    };
+
+    let (mut parts, body) = request.into_parts();
+
+    let auth = match AuthUser::from_request_parts(&mut parts, &()).await {
+        Ok(v) => v,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE"),
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(parts.method.as_str());
+    hasher.update(b" ");
+    hasher.update(parts.uri.path());
+    hasher.update(b"\n");
+    hasher.update(&body);
+    let fingerprint = hex::encode(hasher.finalize());
+
+    // Claim the key. An expired or abandoned claim is taken over.
+    let claimed = sqlx::query_scalar!(
+        r#"
+        INSERT INTO idempotency_keys (user_id, key, fingerprint)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, key) DO UPDATE
+        SET fingerprint = EXCLUDED.fingerprint,
+            response_status = NULL,
+            response_body = NULL,
+            created_at = now() AT TIME ZONE 'utc'
+        WHERE idempotency_keys.created_at < now() AT TIME ZONE 'utc' - make_interval(hours => $4)
+           OR (
+               idempotency_keys.response_status IS NULL
+               AND idempotency_keys.created_at < now() AT TIME ZONE 'utc' - make_interval(secs => $5)
+           )
+        RETURNING id
+        "#,
+        auth.user_id,
+        key,
+        fingerprint,
+        key_ttl_hours() as i32,
+        ABANDONED_AFTER_SECONDS
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let Some(id) = claimed else {
+        let stored = sqlx::query!(
+            r#"
+            SELECT fingerprint, response_status, response_body
+            FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2
+            "#,
+            auth.user_id,
+            key
+        )
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+
+        return match stored {
+            Some(s) if s.fingerprint != fingerprint => {
+                error(StatusCode::CONFLICT, "IDEMPOTENCY_KEY_REUSED")
+            }
+            Some(s) => match (s.response_status, s.response_body) {
+                (Some(status), Some(body)) => replay(status, body),
+                _ => error(StatusCode::CONFLICT, "IDEMPOTENCY_KEY_IN_PROGRESS"),
+            },
+            None => error(StatusCode::CONFLICT, "IDEMPOTENCY_KEY_IN_PROGRESS"),
+        };
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    if response.status().is_server_error() {
+        sqlx::query!("DELETE FROM idempotency_keys WHERE id = $1", id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"),
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE idempotency_keys
+        SET response_status = $2, response_body = $3
+        WHERE id = $1
+        "#,
+        id,
+        parts.status.as_u16() as i32,
+        String::from_utf8_lossy(&body).into_owned()
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(status: i32, body: String) -> Response {
+    let status = StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK);
+
+    let mut response = (status, body).into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+fn error(status: StatusCode, code: &str) -> Response {
+    (status, Json(ApiResponse::<()>::error(code))).into_response()
+}
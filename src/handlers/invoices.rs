@@ -0,0 +1,286 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    invoices::{self, html, Document, Party},
+    models::{
+        invoices::{
+            BillingDetailsRequest, BillingDetailsResponse, InvoiceDocumentResponse, InvoiceLineResponse,
+            InvoicePartyResponse, InvoiceQuery, InvoiceResponse,
+        },
+        response::ApiResponse,
+    },
+};
+
+/// The booking's invoice with any credit notes against it, issuing the
+/// invoice on first request. Available to the customer, the hotel's owner
+/// and admins, as JSON or with `?format=html` as a printable document.
+pub async fn get_invoice(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(booking_id): Path<String>,
+    Query(query): Query<InvoiceQuery>,
+) -> Response {
+
+    let as_html = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("html") => true,
+        Some(_) => return error::<()>(StatusCode::BAD_REQUEST, "INVALID_REQUEST").into_response(),
+    };
+
+    let booking_id = match Uuid::parse_str(&booking_id) {
+        Ok(v) => v,
+        Err(_) => return error::<()>(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND").into_response(),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    // Locked so two first requests cannot both issue an invoice.
+    let booking = sqlx::query!(
+        r#"
+        SELECT b.user_id, b.status, h.owner_id
+        FROM bookings b
+        JOIN hotels h ON h.id = b.hotel_id
+        WHERE b.id = $1
+        FOR UPDATE OF b
+        "#,
+        booking_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .unwrap();
+
+    let booking = match booking {
+        Some(b) => b,
+        None => {
+            tx.rollback().await.unwrap();
+            return error::<()>(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND").into_response();
+        }
+    };
+
+    if auth.role != "admin" && booking.user_id != auth.user_id && booking.owner_id != auth.user_id {
+        tx.rollback().await.unwrap();
+        return error::<()>(StatusCode::FORBIDDEN, "FORBIDDEN").into_response();
+    }
+
+    let invoice_id = match invoices::current_invoice(&mut tx, booking_id).await {
+        Some(id) => id,
+        None if invoices::invoiceable(booking.status.as_deref().unwrap_or("")) => {
+            invoices::issue_invoice(&mut tx, booking_id).await
+        }
+        None => {
+            tx.rollback().await.unwrap();
+            return error::<()>(StatusCode::BAD_REQUEST, "INVOICE_NOT_AVAILABLE").into_response();
+        }
+    };
+
+    let (invoice, credit_notes) = invoices::load_with_credit_notes(&mut tx, invoice_id).await;
+
+    tx.commit().await.unwrap();
+
+    if as_html {
+        let disposition = format!("inline; filename=\"{}.html\"", invoice.number);
+
+        return (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/html; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, disposition),
+            ],
+            html::render(&invoice, &credit_notes),
+        )
+            .into_response();
+    }
+
+    let response = InvoiceResponse {
+        invoice: document_response(invoice),
+        creditNotes: credit_notes.into_iter().map(document_response).collect(),
+    };
+
+    (StatusCode::OK, Json(ApiResponse::success(response))).into_response()
+}
+
+fn document_response(d: Document) -> InvoiceDocumentResponse {
+    InvoiceDocumentResponse {
+        id: d.id.to_string(),
+        bookingId: d.booking_id.to_string(),
+        kind: d.kind.as_str().to_string(),
+        number: d.number,
+        correctsInvoice: d.corrects,
+        status: d.status,
+        reason: d.reason,
+        issuedAt: d.issued_at.and_utc().to_rfc3339(),
+        seller: party_response(d.seller),
+        customer: party_response(d.customer),
+        leadGuestName: d.lead_guest_name,
+        roomNumber: d.room_number,
+        checkInDate: d.check_in.to_string(),
+        checkOutDate: d.check_out.to_string(),
+        guests: d.guests,
+        lines: d
+            .lines
+            .into_iter()
+            .map(|l| InvoiceLineResponse {
+                kind: l.kind,
+                description: l.description,
+                amount: l.amount.to_string(),
+                inclusive: l.inclusive,
+            })
+            .collect(),
+        total: d.total.to_string(),
+        currency: d.currency,
+    }
+}
+
+fn party_response(p: Party) -> InvoicePartyResponse {
+    InvoicePartyResponse {
+        name: p.name,
+        address: p.address,
+        taxId: p.tax_id,
+        email: p.email,
+    }
+}
+
+/// Sets who the hotel's invoices are issued by. Invoices already issued
+/// keep the details they were issued with.
+pub async fn update_billing_details(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+    Json(payload): Json<BillingDetailsRequest>,
+) -> (StatusCode, Json<ApiResponse<BillingDetailsResponse>>) {
+
+    let hotel_id = match authorize_hotel(&auth, &pool, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let legal_name = match non_empty(&payload.legalName) {
+        Some(v) => v,
+        None => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let email = non_empty(&payload.email);
+
+    if email.as_deref().is_some_and(|e| !e.contains('@')) {
+        return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST");
+    }
+
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO hotel_billing_details (hotel_id, legal_name, address, tax_id, email)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (hotel_id) DO UPDATE
+        SET legal_name = EXCLUDED.legal_name,
+            address = EXCLUDED.address,
+            tax_id = EXCLUDED.tax_id,
+            email = EXCLUDED.email,
+            updated_at = now() AT TIME ZONE 'utc'
+        RETURNING legal_name, address, tax_id, email, updated_at
+        "#,
+        hotel_id,
+        legal_name,
+        non_empty(&payload.address),
+        non_empty(&payload.taxId),
+        email
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let response = BillingDetailsResponse {
+        hotelId: hotel_id.to_string(),
+        legalName: saved.legal_name,
+        address: saved.address,
+        taxId: saved.tax_id,
+        email: saved.email,
+        updatedAt: saved.updated_at.and_utc().to_rfc3339(),
+    };
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+pub async fn get_billing_details(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<BillingDetailsResponse>>) {
+
+    let hotel_id = match authorize_hotel(&auth, &pool, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let details = sqlx::query!(
+        r#"
+        SELECT legal_name, address, tax_id, email, updated_at
+        FROM hotel_billing_details
+        WHERE hotel_id = $1
+        "#,
+        hotel_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let details = match details {
+        Some(d) => d,
+        None => return error(StatusCode::NOT_FOUND, "BILLING_DETAILS_NOT_FOUND"),
+    };
+
+    let response = BillingDetailsResponse {
+        hotelId: hotel_id.to_string(),
+        legalName: details.legal_name,
+        address: details.address,
+        taxId: details.tax_id,
+        email: details.email,
+        updatedAt: details.updated_at.and_utc().to_rfc3339(),
+    };
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// The hotel's id if the user owns it or is an admin.
+async fn authorize_hotel(
+    auth: &AuthUser,
+    pool: &PgPool,
+    hotel_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    if auth.role != "owner" && auth.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "FORBIDDEN"));
+    }
+
+    let hotel_id = Uuid::parse_str(hotel_id).map_err(|_| (StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"))?;
+
+    let owner = sqlx::query_scalar!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+    match owner {
+        None => Err((StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND")),
+        Some(owner_id) if auth.role != "admin" && owner_id != auth.user_id => {
+            Err((StatusCode::FORBIDDEN, "FORBIDDEN"))
+        }
+        Some(_) => Ok(hotel_id),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,325 @@
+use axum::{extract::State, http::StatusCode, Json};
+use bigdecimal::ToPrimitive;
+use sqlx::{types::BigDecimal, PgConnection, PgPool};
+use std::env;
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{
+        loyalty::{LoyaltyResponse, LoyaltyTransactionResponse},
+        response::ApiResponse,
+    },
+    pricing::{parse_currency, Converter},
+};
+
+/// Points a customer gets back for one unit of the loyalty currency when
+/// redeeming them.
+pub const POINTS_PER_UNIT_REDEEMED: i64 = 100;
+
+/// Status levels, reached by the number of completed stays. Higher tiers
+/// earn more points per unit spent.
+pub struct Tier {
+    pub name: &'static str,
+    pub min_stays: i64,
+    /// Points per 100 units of the loyalty currency spent.
+    pub earn_percent: i64,
+}
+
+static TIERS: [Tier; 4] = [
+    Tier { name: "member", min_stays: 0, earn_percent: 100 },
+    Tier { name: "silver", min_stays: 5, earn_percent: 125 },
+    Tier { name: "gold", min_stays: 10, earn_percent: 150 },
+    Tier { name: "platinum", min_stays: 25, earn_percent: 200 },
+];
+
+impl Tier {
+    pub fn for_stays(stays: i64) -> &'static Tier {
+        TIERS
+            .iter()
+            .rev()
+            .find(|t| stays >= t.min_stays)
+            .unwrap_or(&TIERS[0])
+    }
+
+    pub fn next(&self) -> Option<&'static Tier> {
+        TIERS.iter().find(|t| t.min_stays > self.min_stays)
+    }
+}
+
+/// Currency points are earned and valued in, from `LOYALTY_CURRENCY`
+/// (default USD). Stays charged in other currencies are converted with the
+/// uploaded exchange rates; without a rate they earn nothing and points
+/// cannot be spent on them.
+pub fn loyalty_currency() -> String {
+    env::var("LOYALTY_CURRENCY")
+        .ok()
+        .and_then(|v| parse_currency(&v))
+        .unwrap_or_else(|| "USD".to_string())
+}
+
+/// Converts amounts in `from` to `to`, or None when no uploaded rate links
+/// the two.
+async fn converter_between(conn: &mut PgConnection, from: &str, to: &str) -> Option<Converter> {
+    if from == to {
+        return Some(Converter::none());
+    }
+
+    let converter = Converter::load(conn, Some(to)).await.ok()?;
+
+    converter.converts(from).then_some(converter)
+}
+
+/// What `points` are worth off a booking charged in `currency`.
+pub(crate) async fn points_value(
+    conn: &mut PgConnection,
+    points: i32,
+    currency: &str,
+) -> Result<BigDecimal, &'static str> {
+    let value = BigDecimal::from(points) / BigDecimal::from(POINTS_PER_UNIT_REDEEMED);
+    let converter = converter_between(conn, &loyalty_currency(), currency)
+        .await
+        .ok_or("POINTS_CURRENCY_UNSUPPORTED")?;
+
+    Ok(converter.convert(&value, &loyalty_currency()).round(2))
+}
+
+/// The most points worth no more than `amount` off a booking charged in
+/// `currency`.
+pub(crate) async fn points_within(
+    conn: &mut PgConnection,
+    amount: &BigDecimal,
+    currency: &str,
+) -> Result<i32, &'static str> {
+    let converter = converter_between(conn, &loyalty_currency(), currency)
+        .await
+        .ok_or("POINTS_CURRENCY_UNSUPPORTED")?;
+    let unit = converter.convert(&BigDecimal::from(1), &loyalty_currency());
+
+    if unit <= BigDecimal::from(0) {
+        return Ok(0);
+    }
+
+    let mut points = (amount * BigDecimal::from(POINTS_PER_UNIT_REDEEMED) / &unit)
+        .with_scale(0)
+        .to_i32()
+        .unwrap_or(0);
+
+    // Values are rounded to cents, which can tip the estimate over.
+    while points > 0 {
+        let value = BigDecimal::from(points) / BigDecimal::from(POINTS_PER_UNIT_REDEEMED);
+
+        if converter.convert(&value, &loyalty_currency()).round(2) <= *amount {
+            break;
+        }
+
+        points -= 1;
+    }
+
+    Ok(points.max(0))
+}
+
+pub(crate) async fn balance(conn: &mut PgConnection, user_id: Uuid) -> i64 {
+    sqlx::query_scalar!(
+        r#"SELECT COALESCE(sum(points), 0) AS "balance!" FROM loyalty_transactions WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .unwrap()
+}
+
+async fn completed_stays(conn: &mut PgConnection, user_id: Uuid) -> i64 {
+    sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM bookings WHERE user_id = $1 AND status = 'checked_out'"#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .unwrap()
+}
+
+/// Points spent on a booking, less any returned since, which a modification
+/// carries over.
+pub(crate) async fn redeemed_on(conn: &mut PgConnection, booking_id: Uuid) -> Option<i32> {
+    let points = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(-sum(points), 0)::int AS "points!"
+        FROM loyalty_transactions
+        WHERE booking_id = $1 AND kind IN ('redeemed', 'returned')
+        "#,
+        booking_id
+    )
+    .fetch_one(conn)
+    .await
+    .unwrap();
+
+    (points > 0).then_some(points)
+}
+
+/// Spends points on a booking. The user row is locked so two bookings
+/// cannot spend the same points; the caller rolls back on error.
+pub(crate) async fn redeem(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    booking_id: Uuid,
+    points: i32,
+) -> Result<(), &'static str> {
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", user_id)
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    if balance(conn, user_id).await < points as i64 {
+        return Err("INSUFFICIENT_POINTS");
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO loyalty_transactions (user_id, booking_id, kind, points)
+        VALUES ($1, $2, 'redeemed', $3)
+        "#,
+        user_id,
+        booking_id,
+        -points
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+
+    Ok(())
+}
+
+/// Gives back points spent on a booking that no longer needs them all.
+pub(crate) async fn return_points(conn: &mut PgConnection, user_id: Uuid, booking_id: Uuid, points: i32) {
+    sqlx::query!(
+        r#"
+        INSERT INTO loyalty_transactions (user_id, booking_id, kind, points)
+        VALUES ($1, $2, 'returned', $3)
+        "#,
+        user_id,
+        booking_id,
+        points
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+/// Credits the guest for a checked-out stay, in proportion to what it cost
+/// and at the rate of the tier the stay brings them to. A stay that cannot
+/// be converted to the loyalty currency earns nothing.
+pub(crate) async fn award_stay(conn: &mut PgConnection, booking_id: Uuid) {
+    let booking = sqlx::query!(
+        "SELECT user_id, total_price, currency FROM bookings WHERE id = $1",
+        booking_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    let tier = Tier::for_stays(completed_stays(conn, booking.user_id).await);
+    let converter = match converter_between(conn, &booking.currency, &loyalty_currency()).await {
+        Some(v) => v,
+        None => {
+            eprintln!(
+                "no exchange rate from {} to {}; booking {booking_id} earns no points",
+                booking.currency,
+                loyalty_currency()
+            );
+            return;
+        }
+    };
+    let spent = converter.convert(&booking.total_price, &booking.currency);
+
+    let points = (spent * BigDecimal::from(tier.earn_percent) / BigDecimal::from(100))
+        .with_scale(0)
+        .to_i32()
+        .unwrap_or(0);
+
+    if points <= 0 {
+        return;
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO loyalty_transactions (user_id, booking_id, kind, points)
+        VALUES ($1, $2, 'earned', $3)
+        "#,
+        booking.user_id,
+        booking_id,
+        points
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+/// Undoes every points movement of a cancelled booking: spent points are
+/// given back and earned ones taken back.
+pub(crate) async fn reverse_booking(conn: &mut PgConnection, booking_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO loyalty_transactions (user_id, booking_id, kind, points)
+        SELECT user_id, booking_id, 'reversed', -sum(points)
+        FROM loyalty_transactions
+        WHERE booking_id = $1
+        GROUP BY user_id, booking_id
+        HAVING sum(points) <> 0
+        "#,
+        booking_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Balance, tier and points history of the signed-in user.
+pub async fn get_loyalty(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> (StatusCode, Json<ApiResponse<LoyaltyResponse>>) {
+
+    let mut conn = pool.acquire().await.unwrap();
+
+    let balance = balance(&mut conn, auth.user_id).await;
+    let stays = completed_stays(&mut conn, auth.user_id).await;
+    let tier = Tier::for_stays(stays);
+    let next = tier.next();
+
+    let transactions = sqlx::query!(
+        r#"
+        SELECT id, booking_id, kind, points, created_at
+        FROM loyalty_transactions
+        WHERE user_id = $1
+        ORDER BY created_at DESC, kind
+        "#,
+        auth.user_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .unwrap();
+
+    let response = LoyaltyResponse {
+        balance,
+        currency: loyalty_currency(),
+        pointsPerUnit: POINTS_PER_UNIT_REDEEMED,
+        tier: tier.name.to_string(),
+        completedStays: stays,
+        nextTier: next.map(|t| t.name.to_string()),
+        staysToNextTier: next.map(|t| t.min_stays - stays),
+        transactions: transactions
+            .into_iter()
+            .map(|t| LoyaltyTransactionResponse {
+                id: t.id.to_string(),
+                bookingId: t.booking_id.map(|v| v.to_string()),
+                kind: t.kind,
+                points: t.points,
+                createdAt: t.created_at.and_utc().to_rfc3339(),
+            })
+            .collect(),
+    };
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
@@ -3,4 +3,24 @@ pub mod auth_middleware;
 pub mod hotels;
 pub mod rooms;
 pub mod bookings;
-pub mod reviews;
\ No newline at end of file
+pub mod reviews;
+pub mod photos;
+pub mod rates;
+pub mod charges;
+pub mod exchange_rates;
+pub mod restrictions;
+pub mod blocks;
+pub mod reservations;
+pub mod payments;
+pub mod cancellation_policies;
+pub mod notifications;
+pub mod front_desk;
+pub mod idempotency;
+pub mod guest_details;
+pub mod waitlist;
+pub mod promo_codes;
+pub mod loyalty;
+pub mod invoices;
+pub mod calendars;
+pub mod webhooks;
+pub mod events;
\ No newline at end of file
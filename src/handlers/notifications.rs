@@ -0,0 +1,120 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{notifications::NotificationResponse, response::ApiResponse},
+};
+
+/// Leaves a message in the user's inbox, in the caller's transaction.
+pub(crate) async fn notify(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    kind: &str,
+    booking_id: Option<Uuid>,
+    message: &str,
+) {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_id, kind, booking_id, message)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        user_id,
+        kind,
+        booking_id,
+        message
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+pub async fn list_notifications(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> (StatusCode, Json<ApiResponse<Vec<NotificationResponse>>>) {
+
+    let notifications = sqlx::query!(
+        r#"
+        SELECT id, kind, booking_id, message, created_at, read_at
+        FROM notifications
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        auth.user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = notifications
+        .into_iter()
+        .map(|n| NotificationResponse {
+            id: n.id.to_string(),
+            r#type: n.kind,
+            bookingId: n.booking_id.map(|v| v.to_string()),
+            message: n.message,
+            createdAt: n
+                .created_at
+                .map(|d| d.and_utc().to_rfc3339())
+                .unwrap_or_default(),
+            readAt: n.read_at.map(|d| d.and_utc().to_rfc3339()),
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn mark_notification_read(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(notification_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<NotificationResponse>>) {
+
+    let notification_id = match Uuid::parse_str(&notification_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "NOTIFICATION_NOT_FOUND"),
+    };
+
+    let notification = sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET read_at = COALESCE(read_at, now())
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, kind, booking_id, message, created_at, read_at
+        "#,
+        notification_id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    let n = match notification {
+        Some(n) => n,
+        None => return error(StatusCode::NOT_FOUND, "NOTIFICATION_NOT_FOUND"),
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(NotificationResponse {
+            id: n.id.to_string(),
+            r#type: n.kind,
+            bookingId: n.booking_id.map(|v| v.to_string()),
+            message: n.message,
+            createdAt: n
+                .created_at
+                .map(|d| d.and_utc().to_rfc3339())
+                .unwrap_or_default(),
+            readAt: n.read_at.map(|d| d.and_utc().to_rfc3339()),
+        })),
+    )
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,516 @@
+use axum::{body::Bytes, extract::State, http::{HeaderMap, StatusCode}, Json};
+use chrono::{Duration, Utc};
+use sqlx::{types::BigDecimal, PgConnection, PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    handlers::loyalty,
+    invoices,
+    lifecycle::BookingStatus,
+    mail::{outbox, Template},
+    models::{
+        payments::{PaymentResponse, PaymentWebhookResponse},
+        response::ApiResponse,
+    },
+    payments::{IntentStatus, PaymentError, PaymentGateway, PaymentIntent, PaymentOutcome},
+    pricing::{cancellation::Refund, promotions},
+    webhooks::{self, Event},
+};
+
+/// A captured payment and how much of it has not been refunded yet.
+pub(crate) struct CapturedPayment {
+    pub id: Uuid,
+    pub refundable: BigDecimal,
+}
+
+/// A payment intent created for a booking before its transaction begins,
+/// so a slow gateway never holds the booking's locks.
+pub(crate) struct StartedPayment {
+    pub booking_id: Uuid,
+    pub provider: &'static str,
+    pub amount: BigDecimal,
+    pub currency: String,
+    pub intent: PaymentIntent,
+}
+
+/// Asks the gateway for an intent. A declined payment is an error, as is any
+/// failure to reach the gateway.
+pub(crate) async fn create_intent(
+    gateway: &dyn PaymentGateway,
+    booking_id: Uuid,
+    amount: &BigDecimal,
+    currency: &str,
+    method: Option<&str>,
+) -> Result<StartedPayment, (StatusCode, &'static str)> {
+    let intent = match gateway.create_intent(booking_id, amount, currency, method).await {
+        Ok(v) => v,
+        Err(PaymentError::UnknownMethod(_)) => {
+            return Err((StatusCode::BAD_REQUEST, "INVALID_PAYMENT_METHOD"));
+        }
+        Err(e) => {
+            eprintln!("payment intent for booking {booking_id} failed: {e}");
+            return Err((StatusCode::BAD_GATEWAY, "PAYMENT_GATEWAY_ERROR"));
+        }
+    };
+
+    if intent.status == IntentStatus::Declined {
+        return Err((StatusCode::PAYMENT_REQUIRED, "PAYMENT_DECLINED"));
+    }
+
+    Ok(StartedPayment {
+        booking_id,
+        provider: gateway.name(),
+        amount: amount.clone(),
+        currency: currency.to_string(),
+        intent,
+    })
+}
+
+/// Records the intent against its booking, which the caller has re-checked
+/// under lock is still awaiting payment. An immediately captured payment
+/// confirms the booking on the spot.
+pub(crate) async fn record_payment(
+    tx: &mut Transaction<'_, Postgres>,
+    started: &StartedPayment,
+) -> PaymentResponse {
+    let booking_id = started.booking_id;
+    let intent = &started.intent;
+
+    let status = match intent.status {
+        IntentStatus::Succeeded => "succeeded",
+        _ => "pending",
+    };
+
+    let payment = sqlx::query!(
+        r#"
+        INSERT INTO payments (booking_id, provider, provider_ref, amount, currency, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+        booking_id,
+        started.provider,
+        intent.reference,
+        started.amount,
+        started.currency,
+        status
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap();
+
+    if intent.status == IntentStatus::Succeeded {
+        let confirmed = sqlx::query!(
+            r#"
+            UPDATE bookings
+            SET status = 'confirmed', hold_expires_at = NULL
+            WHERE id = $1 AND status = ANY($2)
+            "#,
+            booking_id,
+            &BookingStatus::sources(BookingStatus::Confirmed) as &[&str]
+        )
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+
+        if confirmed.rows_affected() > 0 {
+            outbox::enqueue(tx, booking_id, Template::BookingConfirmed).await.unwrap();
+            webhooks::booking_event(tx, booking_id, Event::BookingCreated).await;
+        }
+    }
+
+    PaymentResponse {
+        id: payment.id.to_string(),
+        provider: started.provider.to_string(),
+        status: status.to_string(),
+        amount: started.amount.to_string(),
+        currency: started.currency.clone(),
+        clientSecret: intent.client_secret.clone(),
+        reference: intent.reference.clone(),
+    }
+}
+
+/// Gives back an intent whose booking could not be written after all. Only
+/// a captured payment has anything to refund; a pending one is never
+/// recorded, so its webhook finds no payment.
+pub(crate) async fn abandon(gateway: &dyn PaymentGateway, started: &StartedPayment) {
+    if started.intent.status != IntentStatus::Succeeded {
+        return;
+    }
+
+    if let Err(e) = gateway.refund(&started.intent.reference, &started.amount).await {
+        eprintln!("refund of abandoned payment {} failed: {e}", started.intent.reference);
+    }
+}
+
+/// The booking's captured payment, if it has been paid for.
+pub(crate) async fn captured_payment(conn: &mut PgConnection, booking_id: Uuid) -> Option<CapturedPayment> {
+    sqlx::query!(
+        r#"
+        SELECT
+            p.id,
+            p.amount - COALESCE(
+                (SELECT sum(r.amount) FROM refunds r WHERE r.payment_id = p.id),
+                0
+            ) AS "refundable!"
+        FROM payments p
+        WHERE p.booking_id = $1 AND p.status = 'succeeded'
+        ORDER BY p.created_at DESC
+        LIMIT 1
+        "#,
+        booking_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()
+    .map(|p| CapturedPayment {
+        id: p.id,
+        refundable: p.refundable,
+    })
+}
+
+/// Records the refund together with the penalty kept and credits it against
+/// the booking's invoice. Anything to give back is left pending for
+/// `settle_refunds` to send once the caller has committed.
+pub(crate) async fn record_refund(
+    tx: &mut Transaction<'_, Postgres>,
+    booking_id: Uuid,
+    payment: &CapturedPayment,
+    refund: &Refund,
+    reason: &str,
+) {
+    let status = if refund.amount > BigDecimal::from(0) { "pending" } else { "succeeded" };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO refunds (booking_id, payment_id, amount, penalty_amount, currency, reason, status)
+        SELECT $1, p.id, $3, $4, p.currency, $5, $6
+        FROM payments p
+        WHERE p.id = $2
+        "#,
+        booking_id,
+        payment.id,
+        refund.amount,
+        refund.penalty,
+        reason,
+        status
+    )
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+
+    invoices::credit_refund(tx, booking_id, &refund.amount, reason).await;
+}
+
+/// How long a worker has to send a claimed refund before another may take
+/// it over; well beyond any gateway timeout.
+fn refund_lease() -> Duration {
+    Duration::minutes(5)
+}
+
+/// Sends pending refunds to the provider: those of `booking_ids`, or every
+/// one when `None`. Refunds are claimed with a lease so the same one is
+/// never sent twice at once, and no lock is held while the gateway is
+/// called. Returns how many the provider turned down; they stay pending and
+/// are retried by the settlement job.
+pub(crate) async fn settle_refunds(
+    pool: &PgPool,
+    gateway: &dyn PaymentGateway,
+    booking_ids: Option<&[Uuid]>,
+) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query!(
+        r#"
+        UPDATE refunds r
+        SET locked_until = $2
+        FROM payments p
+        WHERE p.id = r.payment_id
+        AND r.id IN (
+            SELECT id
+            FROM refunds
+            WHERE status = 'pending'
+            AND ($1::uuid[] IS NULL OR booking_id = ANY($1))
+            AND (locked_until IS NULL OR locked_until <= now() AT TIME ZONE 'utc')
+            ORDER BY created_at
+            LIMIT 50
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING r.id, r.amount, p.provider_ref
+        "#,
+        booking_ids,
+        Utc::now().naive_utc() + refund_lease()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut failed = 0;
+
+    for refund in due {
+        match gateway.refund(&refund.provider_ref, &refund.amount).await {
+            Ok(provider_ref) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE refunds
+                    SET status = 'succeeded', provider_ref = $2, last_error = NULL, locked_until = NULL
+                    WHERE id = $1
+                    "#,
+                    refund.id,
+                    provider_ref
+                )
+                .execute(pool)
+                .await?;
+            }
+            Err(e) => {
+                eprintln!("refund {} failed: {e}", refund.id);
+
+                sqlx::query!(
+                    "UPDATE refunds SET last_error = $2, locked_until = NULL WHERE id = $1",
+                    refund.id,
+                    e.to_string()
+                )
+                .execute(pool)
+                .await?;
+
+                failed += 1;
+            }
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Tells the provider to void intents whose booking expired or was
+/// cancelled before they were captured: those of `booking_ids`, or every
+/// one when `None`. Returns how many could not be voided; they are retried
+/// by the settlement job, and a capture that still gets through is refunded
+/// by `payment_webhook`.
+pub(crate) async fn void_abandoned(
+    pool: &PgPool,
+    gateway: &dyn PaymentGateway,
+    booking_ids: Option<&[Uuid]>,
+) -> Result<usize, sqlx::Error> {
+    let abandoned = sqlx::query!(
+        r#"
+        SELECT id, provider_ref
+        FROM payments
+        WHERE status IN ('expired', 'cancelled')
+        AND voided_at IS NULL
+        AND provider = $1
+        AND ($2::uuid[] IS NULL OR booking_id = ANY($2))
+        ORDER BY updated_at
+        LIMIT 50
+        "#,
+        gateway.name(),
+        booking_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut failed = 0;
+
+    for payment in abandoned {
+        if let Err(e) = gateway.cancel(&payment.provider_ref).await {
+            eprintln!("voiding payment {} failed: {e}", payment.id);
+            failed += 1;
+            continue;
+        }
+
+        sqlx::query!(
+            "UPDATE payments SET voided_at = now() AT TIME ZONE 'utc' WHERE id = $1",
+            payment.id
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(failed)
+}
+
+/// Settles what cancelling `booking_ids` left for the provider, right after
+/// the cancellation committed. The cancellation stands either way; a refund
+/// the provider turned down is retried later, but the caller is told.
+pub(crate) async fn settle_cancellation(
+    pool: &PgPool,
+    gateway: &dyn PaymentGateway,
+    booking_ids: &[Uuid],
+) -> Result<(), (StatusCode, &'static str)> {
+    if let Err(e) = void_abandoned(pool, gateway, Some(booking_ids)).await {
+        eprintln!("voiding payments failed: {e}");
+    }
+
+    match settle_refunds(pool, gateway, Some(booking_ids)).await {
+        Ok(0) => Ok(()),
+        Ok(_) => Err((StatusCode::BAD_GATEWAY, "PAYMENT_GATEWAY_ERROR")),
+        Err(e) => {
+            eprintln!("settling refunds failed: {e}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"))
+        }
+    }
+}
+
+/// Gives back a whole capture that arrived after its booking could no longer
+/// be paid for. It was never part of the booking's invoice, so nothing is
+/// credited there.
+async fn refund_late_capture(tx: &mut Transaction<'_, Postgres>, payment_id: Uuid) {
+    sqlx::query!(
+        r#"
+        INSERT INTO refunds (booking_id, payment_id, amount, penalty_amount, currency, reason, status)
+        SELECT booking_id, id, amount, 0, currency, 'late_capture', 'pending'
+        FROM payments
+        WHERE id = $1
+        "#,
+        payment_id
+    )
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+}
+
+/// Capture results from the provider. Repeated deliveries of an event that
+/// was already applied are acknowledged without changing anything. A capture
+/// for a booking that expired or was cancelled meanwhile is refunded.
+pub async fn payment_webhook(
+    State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<ApiResponse<PaymentWebhookResponse>>) {
+
+    let signature = headers
+        .get("x-payment-signature")
+        .and_then(|v| v.to_str().ok());
+
+    let event = match gateway.parse_webhook(&body, signature) {
+        Ok(v) => v,
+        Err(PaymentError::InvalidSignature) => {
+            return error(StatusCode::UNAUTHORIZED, "INVALID_SIGNATURE");
+        }
+        Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let payment = sqlx::query!(
+        r#"
+        SELECT p.id, p.booking_id, p.status
+        FROM payments p
+        WHERE p.provider = $1 AND p.provider_ref = $2
+        FOR UPDATE
+        "#,
+        gateway.name(),
+        event.reference
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .unwrap();
+
+    let payment = match payment {
+        Some(p) => p,
+        None => {
+            tx.rollback().await.unwrap();
+            return error(StatusCode::NOT_FOUND, "PAYMENT_NOT_FOUND");
+        }
+    };
+
+    let mut late_capture = false;
+
+    if payment.status == "pending" {
+        let (payment_status, booking_status) = match event.outcome {
+            PaymentOutcome::Succeeded => ("succeeded", BookingStatus::Confirmed),
+            PaymentOutcome::Failed => ("failed", BookingStatus::Expired),
+        };
+
+        sqlx::query!(
+            "UPDATE payments SET status = $1, updated_at = now() WHERE id = $2",
+            payment_status,
+            payment.id
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        // A booking that expired or was cancelled while the payment was in
+        // flight stays that way.
+        let updated = sqlx::query!(
+            r#"
+            UPDATE bookings
+            SET status = $1, hold_expires_at = NULL
+            WHERE id = $2 AND status = ANY($3)
+            "#,
+            booking_status.as_str(),
+            payment.booking_id,
+            &BookingStatus::sources(booking_status) as &[&str]
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        if updated.rows_affected() > 0 && booking_status == BookingStatus::Confirmed {
+            outbox::enqueue(&mut tx, payment.booking_id, Template::BookingConfirmed).await.unwrap();
+            webhooks::booking_event(&mut tx, payment.booking_id, Event::BookingCreated).await;
+        }
+
+        if updated.rows_affected() > 0 && booking_status == BookingStatus::Expired {
+            loyalty::reverse_booking(&mut tx, payment.booking_id).await.unwrap();
+            promotions::release(&mut tx, payment.booking_id).await.unwrap();
+        }
+
+        late_capture = updated.rows_affected() == 0 && booking_status == BookingStatus::Confirmed;
+    } else if matches!(payment.status.as_str(), "expired" | "cancelled")
+        && event.outcome == PaymentOutcome::Succeeded
+    {
+        // The provider captured an intent that was meant to be void.
+        sqlx::query!(
+            "UPDATE payments SET status = 'succeeded', updated_at = now() WHERE id = $1",
+            payment.id
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        late_capture = true;
+    }
+
+    if late_capture {
+        refund_late_capture(&mut tx, payment.id).await;
+    }
+
+    let current = sqlx::query!(
+        r#"
+        SELECT
+            p.status,
+            b.status AS booking_status,
+            (SELECT sum(r.amount) FROM refunds r WHERE r.payment_id = p.id) AS refunded
+        FROM payments p
+        JOIN bookings b ON b.id = p.booking_id
+        WHERE p.id = $1
+        "#,
+        payment.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+
+    tx.commit().await.unwrap();
+
+    // The provider only needs to hear the event was taken; a refund it turns
+    // down now is retried by the settlement job.
+    if late_capture {
+        if let Err(e) = settle_refunds(&pool, gateway.as_ref(), Some(&[payment.booking_id])).await {
+            eprintln!("refunding late capture {} failed: {e}", payment.id);
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(PaymentWebhookResponse {
+            paymentId: payment.id.to_string(),
+            status: current.status,
+            bookingStatus: current.booking_status.unwrap_or_default(),
+            refundAmount: current.refunded.map(|r| r.to_string()),
+        })),
+    )
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,704 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    Json,
+};
+use image::{codecs::jpeg::JpegEncoder, ImageFormat};
+use sqlx::PgPool;
+use std::{collections::HashSet, env, sync::Arc};
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{
+        photos::{PhotoResponse, ReorderPhotosRequest, DeletePhotoResponse},
+        response::ApiResponse,
+    },
+    storage::ObjectStorage,
+};
+
+const THUMBNAIL_SIZE: u32 = 320;
+
+pub fn max_photo_bytes() -> usize {
+    env::var("MAX_PHOTO_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5 * 1024 * 1024)
+}
+
+pub(crate) struct PhotoRow {
+    pub id: Uuid,
+    pub hotel_id: Uuid,
+    pub room_id: Option<Uuid>,
+    pub storage_key: String,
+    pub thumbnail_key: String,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+    pub position: i32,
+    pub is_cover: bool,
+}
+
+pub(crate) fn photo_response(p: PhotoRow, storage: &dyn ObjectStorage) -> PhotoResponse {
+    PhotoResponse {
+        id: p.id.to_string(),
+        hotelId: p.hotel_id.to_string(),
+        roomId: p.room_id.map(|r| r.to_string()),
+        url: storage.public_url(&p.storage_key),
+        thumbnailUrl: storage.public_url(&p.thumbnail_key),
+        contentType: p.content_type,
+        width: p.width,
+        height: p.height,
+        position: p.position,
+        isCover: p.is_cover,
+    }
+}
+
+pub async fn upload_hotel_photo(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
+    Path(hotel_id): Path<String>,
+    multipart: Multipart,
+) -> (StatusCode, Json<ApiResponse<PhotoResponse>>) {
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    store_photo(auth, pool, storage, hotel_id, None, multipart).await
+}
+
+pub async fn upload_room_photo(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+    multipart: Multipart,
+) -> (StatusCode, Json<ApiResponse<PhotoResponse>>) {
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    let room_id = match Uuid::parse_str(&room_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"),
+    };
+
+    store_photo(auth, pool, storage, hotel_id, Some(room_id), multipart).await
+}
+
+async fn store_photo(
+    auth: AuthUser,
+    pool: PgPool,
+    storage: Arc<dyn ObjectStorage>,
+    hotel_id: Uuid,
+    room_id: Option<Uuid>,
+    multipart: Multipart,
+) -> (StatusCode, Json<ApiResponse<PhotoResponse>>) {
+
+    if let Err((status, code)) = authorize_scope(&pool, &auth, hotel_id, room_id).await {
+        return error(status, code);
+    }
+
+    let upload = match read_upload(multipart).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let is_cover_requested = upload.is_cover;
+
+    // A decoder that panics on a malformed file is the file's fault too.
+    let processed = match tokio::task::spawn_blocking(move || process_image(&upload.bytes)).await {
+        Ok(Some(v)) => v,
+        Ok(None) | Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_IMAGE"),
+    };
+
+    let photo_id = Uuid::new_v4();
+
+    let prefix = match room_id {
+        Some(room_id) => format!("hotels/{}/rooms/{}", hotel_id, room_id),
+        None => format!("hotels/{}", hotel_id),
+    };
+
+    let storage_key = format!("{}/{}.{}", prefix, photo_id, processed.extension);
+    let thumbnail_key = format!("{}/{}_thumb.jpg", prefix, photo_id);
+
+    if storage
+        .put(&storage_key, processed.original, processed.content_type)
+        .await
+        .is_err()
+    {
+        return error(StatusCode::BAD_GATEWAY, "STORAGE_ERROR");
+    }
+
+    if storage
+        .put(&thumbnail_key, processed.thumbnail, "image/jpeg")
+        .await
+        .is_err()
+    {
+        delete_objects(storage.as_ref(), &[&storage_key]).await;
+        return error(StatusCode::BAD_GATEWAY, "STORAGE_ERROR");
+    }
+
+    let mut row = PhotoRow {
+        id: photo_id,
+        hotel_id,
+        room_id,
+        storage_key,
+        thumbnail_key,
+        content_type: processed.content_type.to_string(),
+        width: processed.width as i32,
+        height: processed.height as i32,
+        position: 0,
+        is_cover: is_cover_requested,
+    };
+
+    // Nothing refers to the objects until the row commits, so they are
+    // removed again if it does not.
+    if let Err(e) = insert_photo(&pool, &mut row, processed.size_bytes as i32).await {
+        eprintln!("photo insert failed: {e}");
+        delete_objects(storage.as_ref(), &[&row.storage_key, &row.thumbnail_key]).await;
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR");
+    }
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(photo_response(row, storage.as_ref()))),
+    )
+}
+
+/// Stores the row of an uploaded photo at the end of its scope, filling in
+/// its position. It becomes the cover if asked to or if there is none yet.
+async fn insert_photo(pool: &PgPool, row: &mut PhotoRow, size_bytes: i32) -> sqlx::Result<()> {
+    let mut tx = pool.begin().await?;
+
+    // Serialise uploads per hotel so positions and the cover flag stay consistent.
+    sqlx::query!("SELECT id FROM hotels WHERE id = $1 FOR UPDATE", row.hotel_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let scope = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(MAX(position) + 1, 0) AS "next_position!",
+            BOOL_OR(is_cover) AS has_cover
+        FROM photos
+        WHERE hotel_id = $1 AND room_id IS NOT DISTINCT FROM $2
+        "#,
+        row.hotel_id,
+        row.room_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    row.position = scope.next_position;
+    row.is_cover = row.is_cover || !scope.has_cover.unwrap_or(false);
+
+    if row.is_cover {
+        clear_cover(&mut tx, row.hotel_id, row.room_id).await?;
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO photos (
+            id,
+            hotel_id,
+            room_id,
+            storage_key,
+            thumbnail_key,
+            content_type,
+            width,
+            height,
+            size_bytes,
+            position,
+            is_cover
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)
+        "#,
+        row.id,
+        row.hotel_id,
+        row.room_id,
+        row.storage_key,
+        row.thumbnail_key,
+        row.content_type,
+        row.width,
+        row.height,
+        size_bytes,
+        row.position,
+        row.is_cover
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+pub async fn reorder_hotel_photos(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
+    Path(hotel_id): Path<String>,
+    Json(payload): Json<ReorderPhotosRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<PhotoResponse>>>) {
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    reorder_photos(auth, pool, storage, hotel_id, None, payload).await
+}
+
+pub async fn reorder_room_photos(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+    Json(payload): Json<ReorderPhotosRequest>,
+) -> (StatusCode, Json<ApiResponse<Vec<PhotoResponse>>>) {
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    let room_id = match Uuid::parse_str(&room_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"),
+    };
+
+    reorder_photos(auth, pool, storage, hotel_id, Some(room_id), payload).await
+}
+
+async fn reorder_photos(
+    auth: AuthUser,
+    pool: PgPool,
+    storage: Arc<dyn ObjectStorage>,
+    hotel_id: Uuid,
+    room_id: Option<Uuid>,
+    payload: ReorderPhotosRequest,
+) -> (StatusCode, Json<ApiResponse<Vec<PhotoResponse>>>) {
+
+    if let Err((status, code)) = authorize_scope(&pool, &auth, hotel_id, room_id).await {
+        return error(status, code);
+    }
+
+    let photo_ids: Vec<Uuid> = match payload.photoIds {
+        Some(ids) => match ids.iter().map(|id| Uuid::parse_str(id)).collect() {
+            Ok(v) => v,
+            Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+        },
+        None => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    sqlx::query!("SELECT id FROM hotels WHERE id = $1 FOR UPDATE", hotel_id)
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+
+    let existing: HashSet<Uuid> = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM photos
+        WHERE hotel_id = $1 AND room_id IS NOT DISTINCT FROM $2
+        "#,
+        hotel_id,
+        room_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap()
+    .into_iter()
+    .collect();
+
+    // The new order must name every photo in the gallery exactly once.
+    let requested: HashSet<Uuid> = photo_ids.iter().copied().collect();
+
+    if requested.len() != photo_ids.len() || requested != existing {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST");
+    }
+
+    for (position, photo_id) in photo_ids.iter().enumerate() {
+        sqlx::query!(
+            "UPDATE photos SET position = $1 WHERE id = $2",
+            position as i32,
+            photo_id
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    }
+
+    let photos = sqlx::query_as!(
+        PhotoRow,
+        r#"
+        SELECT
+            id,
+            hotel_id,
+            room_id,
+            storage_key,
+            thumbnail_key,
+            content_type,
+            width,
+            height,
+            position,
+            is_cover
+        FROM photos
+        WHERE hotel_id = $1 AND room_id IS NOT DISTINCT FROM $2
+        ORDER BY position
+        "#,
+        hotel_id,
+        room_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+
+    tx.commit().await.unwrap();
+
+    let response = photos
+        .into_iter()
+        .map(|p| photo_response(p, storage.as_ref()))
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn set_cover_photo(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
+    Path(photo_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<PhotoResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let photo_id = match Uuid::parse_str(&photo_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "PHOTO_NOT_FOUND"),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let photo = match fetch_owned_photo(&mut tx, &auth, photo_id).await {
+        Ok(p) => p,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            return error(status, code);
+        }
+    };
+
+    clear_cover(&mut tx, photo.hotel_id, photo.room_id).await.unwrap();
+
+    sqlx::query!("UPDATE photos SET is_cover = true WHERE id = $1", photo_id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    tx.commit().await.unwrap();
+
+    let photo = PhotoRow { is_cover: true, ..photo };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(photo_response(photo, storage.as_ref()))),
+    )
+}
+
+pub async fn delete_photo(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn ObjectStorage>>,
+    Path(photo_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<DeletePhotoResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let photo_id = match Uuid::parse_str(&photo_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "PHOTO_NOT_FOUND"),
+    };
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let photo = match fetch_owned_photo(&mut tx, &auth, photo_id).await {
+        Ok(p) => p,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            return error(status, code);
+        }
+    };
+
+    sqlx::query!("DELETE FROM photos WHERE id = $1", photo_id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    // A gallery with photos always has a cover; hand it to the next photo in line.
+    if photo.is_cover {
+        sqlx::query!(
+            r#"
+            UPDATE photos
+            SET is_cover = true
+            WHERE id = (
+                SELECT id FROM photos
+                WHERE hotel_id = $1 AND room_id IS NOT DISTINCT FROM $2
+                ORDER BY position
+                LIMIT 1
+            )
+            "#,
+            photo.hotel_id,
+            photo.room_id
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    }
+
+    tx.commit().await.unwrap();
+
+    delete_objects(storage.as_ref(), &[&photo.storage_key, &photo.thumbnail_key]).await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeletePhotoResponse {
+            id: photo_id.to_string(),
+        })),
+    )
+}
+
+async fn authorize_scope(
+    pool: &PgPool,
+    auth: &AuthUser,
+    hotel_id: Uuid,
+    room_id: Option<Uuid>,
+) -> Result<(), (StatusCode, &'static str)> {
+
+    if auth.role != "owner" {
+        return Err((StatusCode::FORBIDDEN, "FORBIDDEN"));
+    }
+
+    let hotel = sqlx::query!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+    match hotel {
+        Some(h) if h.owner_id == auth.user_id => {}
+        Some(_) => return Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => return Err((StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND")),
+    }
+
+    if let Some(room_id) = room_id {
+        let room = sqlx::query!(
+            "SELECT id FROM rooms WHERE id = $1 AND hotel_id = $2",
+            room_id,
+            hotel_id
+        )
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+        if room.is_none() {
+            return Err((StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"));
+        }
+    }
+
+    Ok(())
+}
+
+async fn fetch_owned_photo(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    auth: &AuthUser,
+    photo_id: Uuid,
+) -> Result<PhotoRow, (StatusCode, &'static str)> {
+
+    let photo = sqlx::query!(
+        r#"
+        SELECT
+            p.id,
+            p.hotel_id,
+            p.room_id,
+            p.storage_key,
+            p.thumbnail_key,
+            p.content_type,
+            p.width,
+            p.height,
+            p.position,
+            p.is_cover,
+            h.owner_id
+        FROM photos p
+        JOIN hotels h ON h.id = p.hotel_id
+        WHERE p.id = $1
+        FOR UPDATE OF h
+        "#,
+        photo_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .unwrap();
+
+    let photo = match photo {
+        Some(p) => p,
+        None => return Err((StatusCode::NOT_FOUND, "PHOTO_NOT_FOUND")),
+    };
+
+    if photo.owner_id != auth.user_id {
+        return Err((StatusCode::FORBIDDEN, "FORBIDDEN"));
+    }
+
+    Ok(PhotoRow {
+        id: photo.id,
+        hotel_id: photo.hotel_id,
+        room_id: photo.room_id,
+        storage_key: photo.storage_key,
+        thumbnail_key: photo.thumbnail_key,
+        content_type: photo.content_type,
+        width: photo.width,
+        height: photo.height,
+        position: photo.position,
+        is_cover: photo.is_cover,
+    })
+}
+
+async fn clear_cover(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    hotel_id: Uuid,
+    room_id: Option<Uuid>,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE photos
+        SET is_cover = false
+        WHERE hotel_id = $1 AND room_id IS NOT DISTINCT FROM $2 AND is_cover
+        "#,
+        hotel_id,
+        room_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Removes objects no row refers to any more. Failures are only logged; an
+/// orphaned object costs storage but breaks nothing.
+async fn delete_objects(storage: &dyn ObjectStorage, keys: &[&str]) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            eprintln!("Failed to delete {}: {}", key, e);
+        }
+    }
+}
+
+struct Upload {
+    bytes: Vec<u8>,
+    is_cover: bool,
+}
+
+async fn read_upload(mut multipart: Multipart) -> Result<Upload, (StatusCode, &'static str)> {
+    let mut bytes = None;
+    let mut is_cover = false;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                return Err((StatusCode::PAYLOAD_TOO_LARGE, "PHOTO_TOO_LARGE"))
+            }
+            Err(_) => return Err((StatusCode::BAD_REQUEST, "INVALID_REQUEST")),
+        };
+
+        match field.name() {
+            Some("file") => {
+                let declared = field.content_type().unwrap_or_default();
+                if !declared.is_empty() && !is_accepted_type(declared) {
+                    return Err((StatusCode::BAD_REQUEST, "INVALID_IMAGE"));
+                }
+
+                let data = match field.bytes().await {
+                    Ok(v) => v,
+                    Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                        return Err((StatusCode::PAYLOAD_TOO_LARGE, "PHOTO_TOO_LARGE"))
+                    }
+                    Err(_) => return Err((StatusCode::BAD_REQUEST, "INVALID_REQUEST")),
+                };
+
+                if data.len() > max_photo_bytes() {
+                    return Err((StatusCode::PAYLOAD_TOO_LARGE, "PHOTO_TOO_LARGE"));
+                }
+
+                bytes = Some(data.to_vec());
+            }
+            Some("isCover") => {
+                is_cover = matches!(field.text().await.as_deref(), Ok("true"));
+            }
+            _ => {}
+        }
+    }
+
+    match bytes {
+        Some(bytes) if !bytes.is_empty() => Ok(Upload { bytes, is_cover }),
+        _ => Err((StatusCode::BAD_REQUEST, "INVALID_REQUEST")),
+    }
+}
+
+fn is_accepted_type(mime: &str) -> bool {
+    matches!(mime, "image/jpeg" | "image/png" | "image/webp")
+}
+
+struct ProcessedImage {
+    original: Vec<u8>,
+    thumbnail: Vec<u8>,
+    content_type: &'static str,
+    extension: &'static str,
+    width: u32,
+    height: u32,
+    size_bytes: usize,
+}
+
+/// Sniffs the real format from the bytes rather than trusting the upload's
+/// declared type, and renders a JPEG thumbnail.
+fn process_image(bytes: &[u8]) -> Option<ProcessedImage> {
+    let format = image::guess_format(bytes).ok()?;
+
+    let (content_type, extension) = match format {
+        ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+        ImageFormat::Png => ("image/png", "png"),
+        ImageFormat::WebP => ("image/webp", "webp"),
+        _ => return None,
+    };
+
+    let decoded = image::load_from_memory_with_format(bytes, format).ok()?;
+
+    let mut thumbnail = Vec::new();
+    decoded
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut thumbnail, 80))
+        .ok()?;
+
+    Some(ProcessedImage {
+        original: bytes.to_vec(),
+        thumbnail,
+        content_type,
+        extension,
+        width: decoded.width(),
+        height: decoded.height(),
+        size_bytes: bytes.len(),
+    })
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
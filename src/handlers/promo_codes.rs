@@ -0,0 +1,345 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{
+    db,
+    handlers::auth_middleware::AuthUser,
+    models::{
+        promo_codes::{CreatePromoCodeRequest, PromoCodeListQuery, PromoCodeResponse},
+        response::ApiResponse,
+    },
+    pricing::{parse_currency, promotions::DiscountKind},
+};
+
+const PROMO_CODE_UNIQUE_INDEX: &str = "promo_codes_code_idx";
+
+/// Hotel owners create codes for their own hotels; admins can also create
+/// platform-wide codes.
+pub async fn create_promo_code(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(payload): Json<CreatePromoCodeRequest>,
+) -> (StatusCode, Json<ApiResponse<PromoCodeResponse>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let code = match payload.code.as_deref().map(str::trim) {
+        Some(v) if !v.is_empty() && v.len() <= 50 && !v.contains(char::is_whitespace) => v.to_string(),
+        _ => return invalid_request(),
+    };
+
+    let kind = match payload.discountType.as_deref().and_then(DiscountKind::parse) {
+        Some(v) => v,
+        None => return invalid_request(),
+    };
+
+    let amount = match payload.amount.as_deref().map(BigDecimal::from_str) {
+        Some(Ok(v)) if v > BigDecimal::from(0) => v,
+        _ => return invalid_request(),
+    };
+
+    if kind == DiscountKind::Percent && amount > BigDecimal::from(100) {
+        return invalid_request();
+    }
+
+    let min_nights = match payload.minNights {
+        Some(v) if v < 1 => return invalid_request(),
+        v => v.unwrap_or(1),
+    };
+
+    let valid_from = match parse_date(payload.validFrom.as_deref()) {
+        Ok(v) => v,
+        Err(()) => return error(StatusCode::BAD_REQUEST, "INVALID_DATES"),
+    };
+
+    let valid_until = match parse_date(payload.validUntil.as_deref()) {
+        Ok(v) => v,
+        Err(()) => return error(StatusCode::BAD_REQUEST, "INVALID_DATES"),
+    };
+
+    if let (Some(from), Some(until)) = (valid_from, valid_until) {
+        if from > until {
+            return error(StatusCode::BAD_REQUEST, "INVALID_DATES");
+        }
+    }
+
+    if payload.maxUses.is_some_and(|v| v < 1) || payload.maxUsesPerUser.is_some_and(|v| v < 1) {
+        return invalid_request();
+    }
+
+    let hotel = match payload.hotelId.as_deref() {
+        Some(hotel_id) => {
+            let hotel_id = match Uuid::parse_str(hotel_id) {
+                Ok(v) => v,
+                Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+            };
+
+            let hotel = sqlx::query!("SELECT owner_id, currency FROM hotels WHERE id = $1", hotel_id)
+                .fetch_optional(&pool)
+                .await
+                .unwrap();
+
+            match hotel {
+                None => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+                Some(h) if auth.role != "admin" && h.owner_id != auth.user_id => {
+                    return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+                }
+                Some(h) => Some((hotel_id, h.currency)),
+            }
+        }
+        None if auth.role == "admin" => None,
+        None => return invalid_request(),
+    };
+
+    let currency = match (kind, payload.currency.as_deref(), &hotel) {
+        (DiscountKind::Percent, None, _) => None,
+        (DiscountKind::Percent, Some(_), _) => return invalid_request(),
+        (DiscountKind::Fixed, Some(v), _) => match parse_currency(v) {
+            Some(c) => Some(c),
+            None => return error(StatusCode::BAD_REQUEST, "INVALID_CURRENCY"),
+        },
+        (DiscountKind::Fixed, None, Some((_, hotel_currency))) => Some(hotel_currency.clone()),
+        (DiscountKind::Fixed, None, None) => return error(StatusCode::BAD_REQUEST, "INVALID_CURRENCY"),
+    };
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO promo_codes (
+            code,
+            hotel_id,
+            created_by,
+            discount_type,
+            amount,
+            currency,
+            min_nights,
+            valid_from,
+            valid_until,
+            max_uses,
+            max_uses_per_user
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)
+        RETURNING id, created_at
+        "#,
+        code,
+        hotel.as_ref().map(|(id, _)| *id),
+        auth.user_id,
+        kind.as_str(),
+        amount,
+        currency,
+        min_nights,
+        valid_from,
+        valid_until,
+        payload.maxUses,
+        payload.maxUsesPerUser
+    )
+    .fetch_one(&pool)
+    .await;
+
+    let inserted = match inserted {
+        Ok(v) => v,
+        Err(e) if db::violates_constraint(&e, PROMO_CODE_UNIQUE_INDEX) => {
+            return error(StatusCode::BAD_REQUEST, "PROMO_CODE_EXISTS");
+        }
+        Err(e) => {
+            eprintln!("promo code insert failed: {e}");
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR");
+        }
+    };
+
+    let response = PromoCodeResponse {
+        id: inserted.id.to_string(),
+        code,
+        hotelId: hotel.map(|(id, _)| id.to_string()),
+        discountType: kind.as_str().to_string(),
+        amount: amount.to_string(),
+        currency,
+        minNights: min_nights,
+        validFrom: valid_from.map(|d| d.to_string()),
+        validUntil: valid_until.map(|d| d.to_string()),
+        maxUses: payload.maxUses,
+        maxUsesPerUser: payload.maxUsesPerUser,
+        uses: 0,
+        active: true,
+        createdAt: inserted.created_at.and_utc().to_rfc3339(),
+    };
+
+    (StatusCode::CREATED, Json(ApiResponse::success(response)))
+}
+
+/// Admins see every code, owners the codes of their own hotels.
+pub async fn list_promo_codes(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Query(filters): Query<PromoCodeListQuery>,
+) -> (StatusCode, Json<ApiResponse<Vec<PromoCodeResponse>>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match filters.hotelId.as_deref().map(Uuid::parse_str) {
+        Some(Ok(v)) => Some(v),
+        Some(Err(_)) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+        None => None,
+    };
+
+    let owner_id = (auth.role != "admin").then_some(auth.user_id);
+
+    let codes = sqlx::query!(
+        r#"
+        SELECT
+            p.id,
+            p.code,
+            p.hotel_id,
+            p.discount_type,
+            p.amount,
+            p.currency,
+            p.min_nights,
+            p.valid_from,
+            p.valid_until,
+            p.max_uses,
+            p.max_uses_per_user,
+            p.uses,
+            p.active,
+            p.created_at
+        FROM promo_codes p
+        LEFT JOIN hotels h ON h.id = p.hotel_id
+        WHERE ($1::uuid IS NULL OR h.owner_id = $1)
+        AND ($2::uuid IS NULL OR p.hotel_id = $2)
+        ORDER BY p.created_at DESC
+        "#,
+        owner_id,
+        hotel_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = codes
+        .into_iter()
+        .map(|p| PromoCodeResponse {
+            id: p.id.to_string(),
+            code: p.code,
+            hotelId: p.hotel_id.map(|v| v.to_string()),
+            discountType: p.discount_type,
+            amount: p.amount.to_string(),
+            currency: p.currency,
+            minNights: p.min_nights,
+            validFrom: p.valid_from.map(|d| d.to_string()),
+            validUntil: p.valid_until.map(|d| d.to_string()),
+            maxUses: p.max_uses,
+            maxUsesPerUser: p.max_uses_per_user,
+            uses: p.uses,
+            active: p.active,
+            createdAt: p.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// Stops a code from being redeemed. Bookings already made with it keep
+/// their discount.
+pub async fn deactivate_promo_code(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(promo_code_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<PromoCodeResponse>>) {
+
+    if auth.role != "owner" && auth.role != "admin" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let promo_code_id = match Uuid::parse_str(&promo_code_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "PROMO_CODE_NOT_FOUND"),
+    };
+
+    let promo = sqlx::query!(
+        r#"
+        SELECT h.owner_id AS "owner_id?"
+        FROM promo_codes p
+        LEFT JOIN hotels h ON h.id = p.hotel_id
+        WHERE p.id = $1
+        "#,
+        promo_code_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    match promo {
+        None => return error(StatusCode::NOT_FOUND, "PROMO_CODE_NOT_FOUND"),
+        Some(p) if auth.role != "admin" && p.owner_id != Some(auth.user_id) => {
+            return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+        }
+        Some(_) => {}
+    }
+
+    let p = sqlx::query!(
+        r#"
+        UPDATE promo_codes
+        SET active = false
+        WHERE id = $1
+        RETURNING
+            id,
+            code,
+            hotel_id,
+            discount_type,
+            amount,
+            currency,
+            min_nights,
+            valid_from,
+            valid_until,
+            max_uses,
+            max_uses_per_user,
+            uses,
+            active,
+            created_at
+        "#,
+        promo_code_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let response = PromoCodeResponse {
+        id: p.id.to_string(),
+        code: p.code,
+        hotelId: p.hotel_id.map(|v| v.to_string()),
+        discountType: p.discount_type,
+        amount: p.amount.to_string(),
+        currency: p.currency,
+        minNights: p.min_nights,
+        validFrom: p.valid_from.map(|d| d.to_string()),
+        validUntil: p.valid_until.map(|d| d.to_string()),
+        maxUses: p.max_uses,
+        maxUsesPerUser: p.max_uses_per_user,
+        uses: p.uses,
+        active: p.active,
+        createdAt: p.created_at.and_utc().to_rfc3339(),
+    };
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+fn parse_date(value: Option<&str>) -> Result<Option<NaiveDate>, ()> {
+    match value {
+        Some(v) => NaiveDate::parse_from_str(v, "%Y-%m-%d").map(Some).map_err(|_| ()),
+        None => Ok(None),
+    }
+}
+
+fn invalid_request<T>() -> (StatusCode, Json<ApiResponse<T>>) {
+    error(StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,267 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{
+    handlers::{auth_middleware::AuthUser, cancellation_policies::find_hotel_policy},
+    models::{
+        rates::{CreateRateRequest, RateResponse, DeleteRateResponse},
+        response::ApiResponse,
+    },
+    pricing::RateKind,
+};
+
+pub async fn create_rate(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+    Json(payload): Json<CreateRateRequest>,
+) -> (StatusCode, Json<ApiResponse<RateResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let room_id = match find_room(&pool, &hotel_id, &room_id).await {
+        Ok((room_id, owner_id)) if owner_id == auth.user_id => room_id,
+        Ok(_) => return error(StatusCode::FORBIDDEN, "FORBIDDEN"),
+        Err(code) => return error(StatusCode::NOT_FOUND, code),
+    };
+
+    let kind = match payload.r#type.as_deref().and_then(RateKind::parse) {
+        Some(v) => v,
+        None => return invalid_request(),
+    };
+
+    let price = match payload.pricePerNight.as_deref().map(BigDecimal::from_str) {
+        Some(Ok(v)) if v > BigDecimal::from(0) => v,
+        _ => return invalid_request(),
+    };
+
+    let start_date = match parse_optional_date(payload.startDate.as_deref()) {
+        Ok(v) => v,
+        Err(_) => return invalid_request(),
+    };
+
+    let end_date = match parse_optional_date(payload.endDate.as_deref()) {
+        Ok(v) => v,
+        Err(_) => return invalid_request(),
+    };
+
+    let (start_date, end_date, days_of_week) = match kind {
+        RateKind::Date => match (start_date, end_date) {
+            (Some(d), None) => (Some(d), Some(d), None),
+            (Some(d), Some(e)) if d == e => (Some(d), Some(d), None),
+            _ => return invalid_request(),
+        },
+        RateKind::DateRange => match (start_date, end_date) {
+            (Some(s), Some(e)) if s <= e => (Some(s), Some(e), None),
+            _ => return invalid_request(),
+        },
+        RateKind::DayOfWeek => {
+            let mut days = match payload.daysOfWeek {
+                Some(days) if !days.is_empty() && days.iter().all(|d| (1..=7).contains(d)) => days,
+                _ => return invalid_request(),
+            };
+            days.sort_unstable();
+            days.dedup();
+
+            if let (Some(s), Some(e)) = (start_date, end_date) {
+                if s > e {
+                    return invalid_request();
+                }
+            }
+
+            (start_date, end_date, Some(days))
+        }
+    };
+
+    // find_room has already checked the hotel id.
+    let policy_id = match payload.cancellationPolicyId.as_deref() {
+        Some(policy_id) => {
+            let hotel_id = Uuid::parse_str(&hotel_id).unwrap();
+            match find_hotel_policy(&pool, hotel_id, policy_id).await {
+                Some(v) => Some(v),
+                None => return error(StatusCode::NOT_FOUND, "POLICY_NOT_FOUND"),
+            }
+        }
+        None => None,
+    };
+
+    let rate_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO room_rates (
+            id,
+            room_id,
+            kind,
+            start_date,
+            end_date,
+            days_of_week,
+            price_per_night,
+            cancellation_policy_id
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        "#,
+        rate_id,
+        room_id,
+        kind.as_str(),
+        start_date,
+        end_date,
+        days_of_week.as_deref(),
+        price,
+        policy_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = RateResponse {
+        id: rate_id.to_string(),
+        roomId: room_id.to_string(),
+        r#type: kind.as_str().to_string(),
+        startDate: start_date.map(|d| d.to_string()),
+        endDate: end_date.map(|d| d.to_string()),
+        daysOfWeek: days_of_week,
+        pricePerNight: price.to_string(),
+        cancellationPolicyId: policy_id.map(|v| v.to_string()),
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn list_rates(
+    _auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<Vec<RateResponse>>>) {
+
+    let room_id = match find_room(&pool, &hotel_id, &room_id).await {
+        Ok((room_id, _)) => room_id,
+        Err(code) => return error(StatusCode::NOT_FOUND, code),
+    };
+
+    let rates = sqlx::query!(
+        r#"
+        SELECT id, kind, start_date, end_date, days_of_week, price_per_night, cancellation_policy_id
+        FROM room_rates
+        WHERE room_id = $1
+        ORDER BY start_date NULLS FIRST, created_at
+        "#,
+        room_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = rates
+        .into_iter()
+        .map(|r| RateResponse {
+            id: r.id.to_string(),
+            roomId: room_id.to_string(),
+            r#type: r.kind,
+            startDate: r.start_date.map(|d| d.to_string()),
+            endDate: r.end_date.map(|d| d.to_string()),
+            daysOfWeek: r.days_of_week,
+            pricePerNight: r.price_per_night.to_string(),
+            cancellationPolicyId: r.cancellation_policy_id.map(|v| v.to_string()),
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn delete_rate(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, room_id, rate_id)): Path<(String, String, String)>,
+) -> (StatusCode, Json<ApiResponse<DeleteRateResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let room_id = match find_room(&pool, &hotel_id, &room_id).await {
+        Ok((room_id, owner_id)) if owner_id == auth.user_id => room_id,
+        Ok(_) => return error(StatusCode::FORBIDDEN, "FORBIDDEN"),
+        Err(code) => return error(StatusCode::NOT_FOUND, code),
+    };
+
+    let rate_id = match Uuid::parse_str(&rate_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "RATE_NOT_FOUND"),
+    };
+
+    let deleted = sqlx::query!(
+        "DELETE FROM room_rates WHERE id = $1 AND room_id = $2",
+        rate_id,
+        room_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        return error(StatusCode::NOT_FOUND, "RATE_NOT_FOUND");
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteRateResponse {
+            id: rate_id.to_string(),
+        })),
+    )
+}
+
+/// Looks up a room within a hotel, returning its id and the hotel owner.
+async fn find_room(
+    pool: &PgPool,
+    hotel_id: &str,
+    room_id: &str,
+) -> Result<(Uuid, Uuid), &'static str> {
+    let hotel_id = Uuid::parse_str(hotel_id).map_err(|_| "HOTEL_NOT_FOUND")?;
+    let room_id = Uuid::parse_str(room_id).map_err(|_| "ROOM_NOT_FOUND")?;
+
+    let room = sqlx::query!(
+        r#"
+        SELECT r.id, h.owner_id
+        FROM rooms r
+        JOIN hotels h ON h.id = r.hotel_id
+        WHERE r.id = $1 AND r.hotel_id = $2
+        "#,
+        room_id,
+        hotel_id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap();
+
+    match room {
+        Some(r) => Ok((r.id, r.owner_id)),
+        None => Err("ROOM_NOT_FOUND"),
+    }
+}
+
+fn parse_optional_date(value: Option<&str>) -> Result<Option<NaiveDate>, chrono::ParseError> {
+    value
+        .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+        .transpose()
+}
+
+fn invalid_request() -> (StatusCode, Json<ApiResponse<RateResponse>>) {
+    error(StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,422 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use chrono::{NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
+use sqlx::types::BigDecimal;
+use std::{collections::HashSet, sync::Arc};
+use uuid::Uuid;
+
+use crate::{
+    handlers::{
+        auth_middleware::AuthUser,
+        bookings::{book_room, cancel_with_refund, plan_cancellation, start_checkout, CancelledBy, Checkout},
+        payments::{self, StartedPayment},
+    },
+    models::{
+        bookings::CreateBookingRequest,
+        reservations::{CreateReservationRequest, CancelReservationRequest, ReservationResponse,
+        ReservationBookingResponse},
+        response::ApiResponse,
+    },
+    lifecycle::{self, BookingStatus},
+    payments::PaymentGateway,
+};
+
+/// Books several rooms of one hotel for the same stay. Either every room is
+/// booked or none is. Each room is charged separately.
+pub async fn create_reservation(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
+    Json(payload): Json<CreateReservationRequest>,
+) -> (StatusCode, Json<ApiResponse<ReservationResponse>>) {
+
+    if auth.role != "customer" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    if payload.rooms.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST");
+    }
+
+    let mut room_ids = Vec::with_capacity(payload.rooms.len());
+
+    for room in &payload.rooms {
+        match Uuid::parse_str(&room.roomId) {
+            Ok(v) => room_ids.push(v),
+            Err(_) => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+        }
+    }
+
+    if room_ids.iter().collect::<HashSet<_>>().len() != room_ids.len() {
+        return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST");
+    }
+
+    let check_in = NaiveDate::parse_from_str(&payload.checkInDate, "%Y-%m-%d").ok();
+    let check_out = NaiveDate::parse_from_str(&payload.checkOutDate, "%Y-%m-%d").ok();
+
+    let (check_in, check_out) = match (check_in, check_out) {
+        (Some(ci), Some(co)) if ci < co && ci >= Utc::now().date_naive() => (ci, co),
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_DATES"),
+    };
+
+    let hotels = sqlx::query!(
+        "SELECT DISTINCT hotel_id FROM rooms WHERE id = ANY($1)",
+        &room_ids
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let hotel_id = match hotels.as_slice() {
+        [hotel] => hotel.hotel_id,
+        [] => return error(StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"),
+        _ => return error(StatusCode::BAD_REQUEST, "ROOM_NOT_IN_HOTEL"),
+    };
+
+    // Rooms are booked in id order so two overlapping group bookings cannot
+    // deadlock each other.
+    let mut rooms: Vec<_> = payload.rooms.iter().zip(room_ids).collect();
+    rooms.sort_by_key(|(_, id)| *id);
+
+    let requests: Vec<_> = rooms
+        .into_iter()
+        .map(|(room, _)| CreateBookingRequest {
+            roomId: room.roomId.clone(),
+            checkInDate: payload.checkInDate.clone(),
+            checkOutDate: payload.checkOutDate.clone(),
+            guests: room.guests,
+            paymentMethod: None,
+            guestDetails: room.guestDetails.clone(),
+            promoCode: None,
+            loyaltyPoints: None,
+        })
+        .collect();
+
+    // Every room is charged before the reservation is written; if any charge
+    // or booking fails, the ones already taken are given back.
+    let mut started = Vec::with_capacity(requests.len());
+
+    for request in &requests {
+        let method = payload.paymentMethod.as_deref();
+
+        match start_checkout(&pool, gateway.as_ref(), &auth, request, method).await {
+            Ok(v) => started.push(v),
+            Err((status, code)) => {
+                abandon_all(gateway.as_ref(), &started).await;
+                return error(status, code);
+            }
+        }
+    }
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let reservation_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO reservations (id, user_id, hotel_id, check_in_date, check_out_date)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        reservation_id,
+        auth.user_id,
+        hotel_id,
+        check_in,
+        check_out
+    )
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+
+    for (request, started_room) in requests.iter().zip(&started) {
+        let checkout = Checkout::Pay(started_room);
+
+        if let Err((status, code)) = book_room(&mut tx, &auth, request, Some(reservation_id), checkout).await {
+            tx.rollback().await.unwrap();
+            abandon_all(gateway.as_ref(), &started).await;
+            return error(status, code);
+        }
+    }
+
+    let response = load_reservation(&mut tx, reservation_id).await;
+
+    tx.commit().await.unwrap();
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+async fn abandon_all(gateway: &dyn PaymentGateway, started: &[StartedPayment]) {
+    for payment in started {
+        payments::abandon(gateway, payment).await;
+    }
+}
+
+pub async fn get_reservation(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(reservation_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<ReservationResponse>>) {
+
+    let mut conn = pool.acquire().await.unwrap();
+
+    let reservation_id = match find_own_reservation(&mut conn, &auth, &reservation_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let response = load_reservation(&mut conn, reservation_id).await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+/// Cancels some or all rooms of a reservation, each refunded under its own
+/// cancellation policy. If any room can no longer be cancelled, none is.
+pub async fn cancel_reservation(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    State(gateway): State<Arc<dyn PaymentGateway>>,
+    Path(reservation_id): Path<String>,
+    payload: Option<Json<CancelReservationRequest>>,
+) -> (StatusCode, Json<ApiResponse<ReservationResponse>>) {
+
+    if auth.role != "customer" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let mut tx = pool.begin().await.unwrap();
+
+    let reservation_id = match find_own_reservation(&mut tx, &auth, &reservation_id).await {
+        Ok(v) => v,
+        Err((status, code)) => {
+            tx.rollback().await.unwrap();
+            return error(status, code);
+        }
+    };
+
+    let bookings = sqlx::query!(
+        r#"
+        SELECT id, status, check_in_date
+        FROM bookings
+        WHERE reservation_id = $1
+        FOR UPDATE
+        "#,
+        reservation_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+
+    let requested = payload.and_then(|Json(p)| p.bookingIds);
+
+    let selected: Vec<_> = match requested {
+        Some(ids) => {
+            let mut selected = Vec::with_capacity(ids.len());
+
+            for id in ids {
+                let booking = Uuid::parse_str(&id)
+                    .ok()
+                    .and_then(|id| bookings.iter().find(|b| b.id == id));
+
+                match booking {
+                    Some(b) => {
+                        let status = BookingStatus::from_column(b.status.as_deref());
+
+                        if let Err(code) = status.transition(BookingStatus::Cancelled) {
+                            tx.rollback().await.unwrap();
+                            return error(StatusCode::BAD_REQUEST, code);
+                        }
+
+                        selected.push(b);
+                    }
+                    None => {
+                        tx.rollback().await.unwrap();
+                        return error(StatusCode::NOT_FOUND, "BOOKING_NOT_FOUND");
+                    }
+                }
+            }
+
+            selected
+        }
+        None => bookings
+            .iter()
+            .filter(|b| {
+                BookingStatus::from_column(b.status.as_deref()).can_become(BookingStatus::Cancelled)
+            })
+            .collect(),
+    };
+
+    if selected.is_empty() {
+        tx.rollback().await.unwrap();
+        return error(StatusCode::BAD_REQUEST, "ALREADY_CANCELLED");
+    }
+
+    let mut plans = Vec::with_capacity(selected.len());
+
+    for booking in selected {
+        match plan_cancellation(&mut tx, booking.id, booking.check_in_date).await {
+            Some(plan) => plans.push(plan),
+            None => {
+                tx.rollback().await.unwrap();
+                return error(StatusCode::BAD_REQUEST, "CANCELLATION_DEADLINE_PASSED");
+            }
+        }
+    }
+
+    for plan in &plans {
+        cancel_with_refund(&mut tx, plan, &CancelledBy::Customer(auth.user_id)).await;
+    }
+
+    let response = load_reservation(&mut tx, reservation_id).await;
+
+    tx.commit().await.unwrap();
+
+    let booking_ids: Vec<Uuid> = plans.iter().map(|p| p.booking_id).collect();
+
+    if let Err((status, code)) = payments::settle_cancellation(&pool, gateway.as_ref(), &booking_ids).await {
+        return error(status, code);
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+async fn find_own_reservation(
+    conn: &mut PgConnection,
+    auth: &AuthUser,
+    reservation_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let reservation_id = Uuid::parse_str(reservation_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "RESERVATION_NOT_FOUND"))?;
+
+    let reservation = sqlx::query!(
+        "SELECT user_id FROM reservations WHERE id = $1",
+        reservation_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap();
+
+    match reservation {
+        Some(r) if r.user_id == auth.user_id => Ok(reservation_id),
+        Some(_) => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => Err((StatusCode::NOT_FOUND, "RESERVATION_NOT_FOUND")),
+    }
+}
+
+/// The reservation with its rooms. Totals only cover rooms still booked or
+/// awaiting payment.
+async fn load_reservation(conn: &mut PgConnection, reservation_id: Uuid) -> ReservationResponse {
+    let reservation = sqlx::query!(
+        r#"
+        SELECT id, hotel_id, check_in_date, check_out_date, created_at
+        FROM reservations
+        WHERE id = $1
+        "#,
+        reservation_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    let bookings = sqlx::query!(
+        r#"
+        SELECT
+            b.id,
+            b.room_id,
+            r.room_number,
+            r.room_type,
+            b.guests,
+            b.subtotal,
+            b.total_price,
+            b.currency,
+            b.status
+        FROM bookings b
+        JOIN rooms r ON r.id = b.room_id
+        WHERE b.reservation_id = $1
+        ORDER BY r.room_number
+        "#,
+        reservation_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .unwrap();
+
+    let statuses: Vec<_> = bookings
+        .iter()
+        .map(|b| BookingStatus::from_column(b.status.as_deref()))
+        .collect();
+
+    let status = lifecycle::reservation_status(&statuses);
+
+    let active: Vec<_> = bookings
+        .iter()
+        .zip(&statuses)
+        .filter(|(_, s)| !s.is_void())
+        .map(|(b, _)| b)
+        .collect();
+
+    let subtotal = active
+        .iter()
+        .fold(BigDecimal::from(0), |acc, b| acc + &b.subtotal);
+    let total = active
+        .iter()
+        .fold(BigDecimal::from(0), |acc, b| acc + &b.total_price);
+
+    let refunded = sqlx::query!(
+        r#"
+        SELECT COALESCE(sum(f.amount), 0) AS "amount!"
+        FROM refunds f
+        JOIN bookings b ON b.id = f.booking_id
+        WHERE b.reservation_id = $1
+        "#,
+        reservation_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    let currency = bookings
+        .first()
+        .map(|b| b.currency.clone())
+        .unwrap_or_default();
+
+    ReservationResponse {
+        id: reservation.id.to_string(),
+        hotelId: reservation.hotel_id.to_string(),
+        checkInDate: reservation.check_in_date.to_string(),
+        checkOutDate: reservation.check_out_date.to_string(),
+        status: status.to_string(),
+        subtotal: subtotal.to_string(),
+        totalPrice: total.to_string(),
+        refundedAmount: refunded.amount.to_string(),
+        currency,
+        bookings: bookings
+            .into_iter()
+            .map(|b| ReservationBookingResponse {
+                id: b.id.to_string(),
+                roomId: b.room_id.to_string(),
+                roomNumber: b.room_number,
+                roomType: b.room_type,
+                guests: b.guests,
+                subtotal: b.subtotal.to_string(),
+                totalPrice: b.total_price.to_string(),
+                status: b.status.unwrap_or_else(|| "confirmed".to_string()),
+            })
+            .collect(),
+        createdAt: reservation
+            .created_at
+            .map(|d| d.and_utc().to_rfc3339())
+            .unwrap_or_else(|| Utc::now().to_rfc3339()),
+    }
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
@@ -0,0 +1,264 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    availability::RestrictionKind,
+    handlers::auth_middleware::AuthUser,
+    models::{
+        restrictions::{CreateRestrictionRequest, RestrictionResponse, DeleteRestrictionResponse},
+        response::ApiResponse,
+    },
+};
+
+pub async fn create_restriction(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+    Json(payload): Json<CreateRestrictionRequest>,
+) -> (StatusCode, Json<ApiResponse<RestrictionResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match find_owned_hotel(&pool, &auth, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let room_id = match payload.roomId.as_deref() {
+        Some(room_id) => match find_room(&pool, hotel_id, room_id).await {
+            Some(v) => Some(v),
+            None => return error(StatusCode::NOT_FOUND, "ROOM_NOT_FOUND"),
+        },
+        None => None,
+    };
+
+    let kind = match payload.r#type.as_deref().and_then(RestrictionKind::parse) {
+        Some(v) => v,
+        None => return invalid_request(),
+    };
+
+    let value = match (kind.has_value(), payload.value) {
+        (true, Some(v)) if v > 0 => Some(v),
+        (false, None) => None,
+        _ => return invalid_request(),
+    };
+
+    let start_date = match parse_optional_date(payload.startDate.as_deref()) {
+        Ok(v) => v,
+        Err(_) => return invalid_request(),
+    };
+
+    let end_date = match parse_optional_date(payload.endDate.as_deref()) {
+        Ok(v) => v,
+        Err(_) => return invalid_request(),
+    };
+
+    if let (Some(s), Some(e)) = (start_date, end_date) {
+        if s > e {
+            return invalid_request();
+        }
+    }
+
+    let days_of_week = match payload.daysOfWeek {
+        Some(mut days) => {
+            if days.is_empty() || !days.iter().all(|d| (1..=7).contains(d)) {
+                return invalid_request();
+            }
+            days.sort_unstable();
+            days.dedup();
+            Some(days)
+        }
+        None => None,
+    };
+
+    let restriction_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO stay_restrictions (
+            id,
+            hotel_id,
+            room_id,
+            kind,
+            value,
+            start_date,
+            end_date,
+            days_of_week
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        "#,
+        restriction_id,
+        hotel_id,
+        room_id,
+        kind.as_str(),
+        value,
+        start_date,
+        end_date,
+        days_of_week.as_deref()
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = RestrictionResponse {
+        id: restriction_id.to_string(),
+        hotelId: hotel_id.to_string(),
+        roomId: room_id.map(|v| v.to_string()),
+        r#type: kind.as_str().to_string(),
+        value,
+        startDate: start_date.map(|d| d.to_string()),
+        endDate: end_date.map(|d| d.to_string()),
+        daysOfWeek: days_of_week,
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn list_restrictions(
+    _auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(hotel_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<Vec<RestrictionResponse>>>) {
+
+    let hotel_id = match Uuid::parse_str(&hotel_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"),
+    };
+
+    let hotel = sqlx::query!("SELECT id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+
+    if hotel.is_none() {
+        return error(StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND");
+    }
+
+    let restrictions = sqlx::query!(
+        r#"
+        SELECT id, room_id, kind, value, start_date, end_date, days_of_week
+        FROM stay_restrictions
+        WHERE hotel_id = $1
+        ORDER BY room_id NULLS FIRST, start_date NULLS FIRST, created_at
+        "#,
+        hotel_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = restrictions
+        .into_iter()
+        .map(|r| RestrictionResponse {
+            id: r.id.to_string(),
+            hotelId: hotel_id.to_string(),
+            roomId: r.room_id.map(|v| v.to_string()),
+            r#type: r.kind,
+            value: r.value,
+            startDate: r.start_date.map(|d| d.to_string()),
+            endDate: r.end_date.map(|d| d.to_string()),
+            daysOfWeek: r.days_of_week,
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(response)),
+    )
+}
+
+pub async fn delete_restriction(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((hotel_id, restriction_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<DeleteRestrictionResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let hotel_id = match find_owned_hotel(&pool, &auth, &hotel_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let restriction_id = match Uuid::parse_str(&restriction_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "RESTRICTION_NOT_FOUND"),
+    };
+
+    let deleted = sqlx::query!(
+        "DELETE FROM stay_restrictions WHERE id = $1 AND hotel_id = $2",
+        restriction_id,
+        hotel_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    if deleted.rows_affected() == 0 {
+        return error(StatusCode::NOT_FOUND, "RESTRICTION_NOT_FOUND");
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteRestrictionResponse {
+            id: restriction_id.to_string(),
+        })),
+    )
+}
+
+async fn find_owned_hotel(
+    pool: &PgPool,
+    auth: &AuthUser,
+    hotel_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let hotel_id = Uuid::parse_str(hotel_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND"))?;
+
+    let hotel = sqlx::query!("SELECT owner_id FROM hotels WHERE id = $1", hotel_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+    match hotel {
+        Some(h) if h.owner_id == auth.user_id => Ok(hotel_id),
+        Some(_) => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        None => Err((StatusCode::NOT_FOUND, "HOTEL_NOT_FOUND")),
+    }
+}
+
+async fn find_room(pool: &PgPool, hotel_id: Uuid, room_id: &str) -> Option<Uuid> {
+    let room_id = Uuid::parse_str(room_id).ok()?;
+
+    sqlx::query!(
+        "SELECT id FROM rooms WHERE id = $1 AND hotel_id = $2",
+        room_id,
+        hotel_id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+    .map(|r| r.id)
+}
+
+fn parse_optional_date(value: Option<&str>) -> Result<Option<NaiveDate>, chrono::ParseError> {
+    value
+        .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+        .transpose()
+}
+
+fn invalid_request() -> (StatusCode, Json<ApiResponse<RestrictionResponse>>) {
+    error(StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
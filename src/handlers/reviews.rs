@@ -6,10 +6,12 @@ use sqlx::types::BigDecimal;
 
 use crate::{
     handlers::auth_middleware::AuthUser,
+    lifecycle::BookingStatus,
     models::{
         reviews::{CreateReviewRequest, ReviewResponse},
         response::ApiResponse,
     },
+    webhooks,
 };
 
 pub async fn create_review(
@@ -66,9 +68,8 @@ pub async fn create_review(
     }
 
     
-    if booking.status != Some("confirmed".to_string())
-        || booking.check_out_date >= Utc::now().date_naive()
-    {
+    // Only stays the hotel has checked out can be reviewed.
+    if BookingStatus::from_column(booking.status.as_deref()) != BookingStatus::CheckedOut {
         tx.rollback().await.unwrap();
         return booking_not_eligible();
     }
@@ -149,6 +150,8 @@ pub async fn create_review(
     .await
     .unwrap();
 
+    webhooks::review_created(&mut tx, review_id).await;
+
     tx.commit().await.unwrap();
 
     let response = ReviewResponse {
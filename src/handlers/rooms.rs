@@ -9,7 +9,7 @@ use std::str::FromStr;
 use uuid::Uuid;
 
 use crate::{
-    handlers::auth_middleware::AuthUser,
+    handlers::{auth_middleware::AuthUser, cancellation_policies::find_hotel_policy},
     models::{
         rooms::{CreateRoomRequest, RoomResponse},
         response::ApiResponse,
@@ -56,6 +56,16 @@ pub async fn create_room(
         _ => return invalid_request(),
     };
 
+    let max_adults = match payload.maxAdults {
+        Some(v) if v <= 0 || v > occupancy => return invalid_request(),
+        v => v,
+    };
+
+    let max_children = match payload.maxChildren {
+        Some(v) if v < 0 || v >= occupancy => return invalid_request(),
+        v => v,
+    };
+
     
     let hotel = sqlx::query!(
         r#"
@@ -96,6 +106,14 @@ pub async fn create_room(
         return room_exists();
     }
 
+    let policy_id = match payload.cancellationPolicyId.as_deref() {
+        Some(policy_id) => match find_hotel_policy(&pool, hotel_id, policy_id).await {
+            Some(v) => Some(v),
+            None => return policy_not_found(),
+        },
+        None => None,
+    };
+
     
     let room_id = Uuid::new_v4();
 
@@ -107,16 +125,22 @@ pub async fn create_room(
             room_number,
             room_type,
             price_per_night,
-            max_occupancy
+            max_occupancy,
+            max_adults,
+            max_children,
+            cancellation_policy_id
         )
-        VALUES ($1, $2, $3, $4, $5, $6)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         "#,
         room_id,
         hotel_id,
         room_number,
         room_type,
         price,
-        occupancy
+        occupancy,
+        max_adults,
+        max_children,
+        policy_id
     )
     .execute(&pool)
     .await
@@ -130,6 +154,9 @@ pub async fn create_room(
         roomType: room_type,
         pricePerNight: price.to_string(),
         maxOccupancy: occupancy,
+        maxAdults: max_adults,
+        maxChildren: max_children,
+        cancellationPolicyId: policy_id.map(|v| v.to_string()),
     };
 
     (
@@ -160,6 +187,13 @@ fn not_found() -> (StatusCode, Json<ApiResponse<RoomResponse>>) {
     )
 }
 
+fn policy_not_found() -> (StatusCode, Json<ApiResponse<RoomResponse>>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ApiResponse::error("POLICY_NOT_FOUND")),
+    )
+}
+
 fn room_exists() -> (StatusCode, Json<ApiResponse<RoomResponse>>) {
     (
         StatusCode::BAD_REQUEST,
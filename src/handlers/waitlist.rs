@@ -0,0 +1,405 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::{Acquire, PgPool, Postgres, Transaction};
+use std::env;
+use uuid::Uuid;
+
+use crate::{
+    handlers::{
+        auth_middleware::AuthUser,
+        bookings::{book_room, Checkout},
+        notifications,
+    },
+    models::{
+        bookings::CreateBookingRequest,
+        response::ApiResponse,
+        waitlist::{CreateWaitlistRequest, WaitlistEntryResponse},
+    },
+};
+
+/// How long a customer has to confirm a room offered from the waitlist, from
+/// `WAITLIST_OFFER_TTL_SECONDS` (default one hour).
+pub fn offer_ttl() -> Duration {
+    let seconds = env::var("WAITLIST_OFFER_TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60 * 60);
+
+    Duration::seconds(seconds)
+}
+
+pub async fn join_waitlist(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(payload): Json<CreateWaitlistRequest>,
+) -> (StatusCode, Json<ApiResponse<WaitlistEntryResponse>>) {
+
+    if auth.role != "customer" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let check_in = NaiveDate::parse_from_str(&payload.checkInDate, "%Y-%m-%d").ok();
+    let check_out = NaiveDate::parse_from_str(&payload.checkOutDate, "%Y-%m-%d").ok();
+
+    let (check_in, check_out) = match (check_in, check_out) {
+        (Some(ci), Some(co)) if ci < co && ci >= Utc::now().date_naive() => (ci, co),
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_DATES"),
+    };
+
+    if payload.guests < 1 {
+        return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST");
+    }
+
+    let room_id = payload.roomId.as_deref().map(Uuid::parse_str);
+    let hotel_id = payload.hotelId.as_deref().map(Uuid::parse_str);
+    let room_type = payload
+        .roomType
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    // The rooms the entry could be offered, with their hotel and owner.
+    let rooms = match (&room_id, &hotel_id, room_type) {
+        (Some(Ok(room_id)), None, None) => sqlx::query!(
+            r#"
+            SELECT r.hotel_id, r.max_occupancy, h.owner_id
+            FROM rooms r
+            JOIN hotels h ON h.id = r.hotel_id
+            WHERE r.id = $1
+            "#,
+            *room_id
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| (r.hotel_id, r.max_occupancy, r.owner_id))
+        .collect::<Vec<_>>(),
+        (None, Some(Ok(hotel_id)), Some(room_type)) => sqlx::query!(
+            r#"
+            SELECT r.hotel_id, r.max_occupancy, h.owner_id
+            FROM rooms r
+            JOIN hotels h ON h.id = r.hotel_id
+            WHERE r.hotel_id = $1 AND lower(r.room_type) = lower($2)
+            "#,
+            *hotel_id,
+            room_type
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| (r.hotel_id, r.max_occupancy, r.owner_id))
+        .collect::<Vec<_>>(),
+        (Some(Err(_)), _, _) | (_, Some(Err(_)), _) => {
+            return error(StatusCode::NOT_FOUND, "ROOM_NOT_FOUND");
+        }
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let Some(&(hotel_id, _, owner_id)) = rooms.first() else {
+        return error(StatusCode::NOT_FOUND, "ROOM_NOT_FOUND");
+    };
+
+    if owner_id == auth.user_id {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    if rooms.iter().all(|&(_, max_occupancy, _)| payload.guests > max_occupancy) {
+        return error(StatusCode::BAD_REQUEST, "INVALID_CAPACITY");
+    }
+
+    let room_id = room_id.and_then(Result::ok);
+
+    let duplicate = sqlx::query!(
+        r#"
+        SELECT id FROM waitlist_entries
+        WHERE user_id = $1
+        AND hotel_id = $2
+        AND room_id IS NOT DISTINCT FROM $3
+        AND lower(room_type) IS NOT DISTINCT FROM lower($4)
+        AND check_in_date = $5
+        AND check_out_date = $6
+        AND status IN ('waiting', 'offered')
+        "#,
+        auth.user_id,
+        hotel_id,
+        room_id,
+        room_type,
+        check_in,
+        check_out
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    if duplicate.is_some() {
+        return error(StatusCode::BAD_REQUEST, "ALREADY_WAITLISTED");
+    }
+
+    let entry_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO waitlist_entries (
+            user_id,
+            hotel_id,
+            room_id,
+            room_type,
+            check_in_date,
+            check_out_date,
+            guests
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7)
+        RETURNING id
+        "#,
+        auth.user_id,
+        hotel_id,
+        room_id,
+        room_type,
+        check_in,
+        check_out,
+        payload.guests
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    match load_entries(&pool, auth.user_id, Some(entry_id)).await.pop() {
+        Some(entry) => (StatusCode::CREATED, Json(ApiResponse::success(entry))),
+        None => error(StatusCode::NOT_FOUND, "WAITLIST_ENTRY_NOT_FOUND"),
+    }
+}
+
+pub async fn list_waitlist(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> (StatusCode, Json<ApiResponse<Vec<WaitlistEntryResponse>>>) {
+
+    let entries = load_entries(&pool, auth.user_id, None).await;
+
+    (StatusCode::OK, Json(ApiResponse::success(entries)))
+}
+
+/// Leaves the waitlist. A room already offered stays held until its offer
+/// runs out or the hold is cancelled.
+pub async fn leave_waitlist(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(entry_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<WaitlistEntryResponse>>) {
+
+    let entry_id = match Uuid::parse_str(&entry_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "WAITLIST_ENTRY_NOT_FOUND"),
+    };
+
+    let entry = sqlx::query!(
+        "SELECT status FROM waitlist_entries WHERE id = $1 AND user_id = $2",
+        entry_id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    match entry {
+        None => return error(StatusCode::NOT_FOUND, "WAITLIST_ENTRY_NOT_FOUND"),
+        Some(e) if e.status == "cancelled" => {
+            return error(StatusCode::BAD_REQUEST, "ALREADY_CANCELLED");
+        }
+        Some(_) => {}
+    }
+
+    sqlx::query!(
+        "UPDATE waitlist_entries SET status = 'cancelled' WHERE id = $1",
+        entry_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    match load_entries(&pool, auth.user_id, Some(entry_id)).await.pop() {
+        Some(entry) => (StatusCode::OK, Json(ApiResponse::success(entry))),
+        None => error(StatusCode::NOT_FOUND, "WAITLIST_ENTRY_NOT_FOUND"),
+    }
+}
+
+async fn load_entries(
+    pool: &PgPool,
+    user_id: Uuid,
+    entry_id: Option<Uuid>,
+) -> Vec<WaitlistEntryResponse> {
+    let entries = sqlx::query!(
+        r#"
+        SELECT
+            e.id,
+            e.hotel_id,
+            e.room_id,
+            e.room_type,
+            e.check_in_date,
+            e.check_out_date,
+            e.guests,
+            e.status,
+            e.offered_booking_id,
+            b.hold_expires_at AS "offer_expires_at?",
+            e.created_at
+        FROM waitlist_entries e
+        LEFT JOIN bookings b ON b.id = e.offered_booking_id AND e.status = 'offered'
+        WHERE e.user_id = $1
+        AND ($2::uuid IS NULL OR e.id = $2)
+        ORDER BY e.created_at DESC
+        "#,
+        user_id,
+        entry_id
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    entries
+        .into_iter()
+        .map(|e| WaitlistEntryResponse {
+            id: e.id.to_string(),
+            hotelId: e.hotel_id.to_string(),
+            roomId: e.room_id.map(|v| v.to_string()),
+            roomType: e.room_type,
+            checkInDate: e.check_in_date.to_string(),
+            checkOutDate: e.check_out_date.to_string(),
+            guests: e.guests,
+            status: e.status,
+            offeredBookingId: e.offered_booking_id.map(|v| v.to_string()),
+            offerExpiresAt: e.offer_expires_at.map(|d| d.and_utc().to_rfc3339()),
+            createdAt: e.created_at.and_utc().to_rfc3339(),
+        })
+        .collect()
+}
+
+/// Offers nights of a room that were just freed to the waitlist. In the
+/// order they joined, each waiting customer whose stay now fits gets a held
+/// booking for `offer_ttl` and a notification; a hold taken for one entry
+/// keeps overlapping later entries out.
+pub(crate) async fn offer_freed_room(
+    tx: &mut Transaction<'_, Postgres>,
+    room_id: Uuid,
+    freed_from: NaiveDate,
+    freed_until: NaiveDate,
+) -> sqlx::Result<()> {
+    let candidates = sqlx::query!(
+        r#"
+        SELECT e.id, e.user_id, e.check_in_date, e.check_out_date, e.guests, r.room_number, h.name AS hotel_name
+        FROM waitlist_entries e
+        JOIN rooms r ON r.id = $1
+        JOIN hotels h ON h.id = r.hotel_id
+        WHERE e.status = 'waiting'
+        AND e.hotel_id = r.hotel_id
+        AND (e.room_id = r.id OR (e.room_id IS NULL AND lower(e.room_type) = lower(r.room_type)))
+        AND e.check_in_date < $3
+        AND e.check_out_date > $2
+        AND e.check_in_date >= (now() AT TIME ZONE 'utc')::date
+        ORDER BY e.created_at
+        FOR UPDATE OF e SKIP LOCKED
+        "#,
+        room_id,
+        freed_from,
+        freed_until
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for entry in candidates {
+        let customer = AuthUser {
+            user_id: entry.user_id,
+            role: "customer".to_string(),
+        };
+
+        let request = CreateBookingRequest {
+            roomId: room_id.to_string(),
+            checkInDate: entry.check_in_date.to_string(),
+            checkOutDate: entry.check_out_date.to_string(),
+            guests: entry.guests,
+            paymentMethod: None,
+            guestDetails: None,
+            promoCode: None,
+            loyaltyPoints: None,
+        };
+
+        // A savepoint, so an entry that no longer fits leaves the
+        // cancellation untouched.
+        let mut offer = tx.begin().await?;
+
+        let booked = match book_room(&mut offer, &customer, &request, None, Checkout::Hold).await {
+            Ok(v) => v,
+            Err(_) => {
+                offer.rollback().await?;
+                continue;
+            }
+        };
+
+        let expires_at = Utc::now().naive_utc() + offer_ttl();
+
+        sqlx::query!(
+            "UPDATE bookings SET hold_expires_at = $2 WHERE id = $1",
+            booked.id,
+            expires_at
+        )
+        .execute(&mut *offer)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE waitlist_entries
+            SET status = 'offered', offered_booking_id = $2, offered_at = now() AT TIME ZONE 'utc'
+            WHERE id = $1
+            "#,
+            entry.id,
+            booked.id
+        )
+        .execute(&mut *offer)
+        .await?;
+
+        let message = format!(
+            "Room {} at {} is available for {} to {}. It is held for you until {} UTC; confirm the booking to keep it.",
+            entry.room_number,
+            entry.hotel_name,
+            entry.check_in_date,
+            entry.check_out_date,
+            expires_at.format("%Y-%m-%d %H:%M")
+        );
+
+        notifications::notify(&mut offer, entry.user_id, "waitlist_offer", Some(booked.id), &message).await;
+
+        offer.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Closes offers whose hold has ended: confirmed ones as booked, the rest
+/// as lapsed, in which case the room goes to the next customer waiting.
+/// Returns how many offers lapsed.
+pub(crate) async fn settle_offers(tx: &mut Transaction<'_, Postgres>) -> sqlx::Result<usize> {
+    let settled = sqlx::query!(
+        r#"
+        UPDATE waitlist_entries e
+        SET status = CASE WHEN b.status = 'cancelled' OR b.status = 'expired' THEN 'lapsed' ELSE 'booked' END
+        FROM bookings b
+        WHERE b.id = e.offered_booking_id
+        AND e.status = 'offered'
+        AND b.status NOT IN ('held', 'pending_payment')
+        RETURNING e.status, b.room_id, b.check_in_date, b.check_out_date
+        "#
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let lapsed: Vec<_> = settled.into_iter().filter(|s| s.status == "lapsed").collect();
+
+    for offer in &lapsed {
+        offer_freed_room(tx, offer.room_id, offer.check_in_date, offer.check_out_date).await?;
+    }
+
+    Ok(lapsed.len())
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
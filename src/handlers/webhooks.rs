@@ -0,0 +1,322 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDateTime;
+use reqwest::Url;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::auth_middleware::AuthUser,
+    models::{
+        response::ApiResponse,
+        webhooks::{
+            CreateWebhookRequest, DeleteWebhookResponse, DeliveryListQuery, WebhookDeliveryResponse,
+            WebhookResponse,
+        },
+    },
+    outbound,
+    webhooks::Event,
+};
+
+/// Subscribes one of the owner's endpoints to events at all of their
+/// hotels. The signing secret is only shown in this response.
+pub async fn create_webhook(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> (StatusCode, Json<ApiResponse<WebhookResponse>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let url = match payload.url.as_deref().map(str::trim).map(Url::parse) {
+        Some(Ok(u)) => u,
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_WEBHOOK_URL"),
+    };
+
+    // Deliveries come from inside our network, so endpoints must be public.
+    if outbound::resolve(&url).await.is_err() {
+        return error(StatusCode::BAD_REQUEST, "INVALID_WEBHOOK_URL");
+    }
+
+    let url = url.to_string();
+
+    let requested = match payload.events {
+        Some(v) if !v.is_empty() => v,
+        _ => return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
+    };
+
+    let mut events: Vec<String> = Vec::new();
+
+    for name in &requested {
+        match Event::parse(name) {
+            Some(event) if !events.iter().any(|e| e == event.as_str()) => {
+                events.push(event.as_str().to_string())
+            }
+            Some(_) => {}
+            None => return error(StatusCode::BAD_REQUEST, "INVALID_EVENT_TYPE"),
+        }
+    }
+
+    let secret = format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+    let created = sqlx::query!(
+        r#"
+        INSERT INTO webhook_subscriptions (owner_id, url, events, secret)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, created_at
+        "#,
+        auth.user_id,
+        url,
+        &events,
+        secret
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(WebhookResponse {
+            id: created.id.to_string(),
+            url,
+            events,
+            secret: Some(secret),
+            createdAt: created.created_at.and_utc().to_rfc3339(),
+        })),
+    )
+}
+
+pub async fn list_webhooks(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+) -> (StatusCode, Json<ApiResponse<Vec<WebhookResponse>>>) {
+
+    if auth.role != "owner" {
+        return error(StatusCode::FORBIDDEN, "FORBIDDEN");
+    }
+
+    let subscriptions = sqlx::query!(
+        r#"
+        SELECT id, url, events, created_at
+        FROM webhook_subscriptions
+        WHERE owner_id = $1
+        ORDER BY created_at
+        "#,
+        auth.user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = subscriptions
+        .into_iter()
+        .map(|s| WebhookResponse {
+            id: s.id.to_string(),
+            url: s.url,
+            events: s.events,
+            secret: None,
+            createdAt: s.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// Removes the subscription along with its delivery history.
+pub async fn delete_webhook(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(webhook_id): Path<String>,
+) -> (StatusCode, Json<ApiResponse<DeleteWebhookResponse>>) {
+
+    let webhook_id = match find_own_webhook(&auth, &pool, &webhook_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    sqlx::query!("DELETE FROM webhook_subscriptions WHERE id = $1", webhook_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(DeleteWebhookResponse {
+            id: webhook_id.to_string(),
+        })),
+    )
+}
+
+/// The subscription's latest deliveries, optionally only those in one
+/// status (`pending`, `delivered` or `dead`).
+pub async fn list_webhook_deliveries(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path(webhook_id): Path<String>,
+    Query(query): Query<DeliveryListQuery>,
+) -> (StatusCode, Json<ApiResponse<Vec<WebhookDeliveryResponse>>>) {
+
+    if let Some(status) = query.status.as_deref() {
+        if !matches!(status, "pending" | "delivered" | "dead") {
+            return error(StatusCode::BAD_REQUEST, "INVALID_REQUEST");
+        }
+    }
+
+    let webhook_id = match find_own_webhook(&auth, &pool, &webhook_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let deliveries = sqlx::query_as!(
+        DeliveryRow,
+        r#"
+        SELECT id, subscription_id, event_id, event_type, payload, status, attempts,
+               next_attempt_at, last_status_code, last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE subscription_id = $1
+        AND ($2::text IS NULL OR status = $2)
+        ORDER BY created_at DESC
+        LIMIT 100
+        "#,
+        webhook_id,
+        query.status
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let response = deliveries.into_iter().map(delivery_response).collect();
+
+    (StatusCode::OK, Json(ApiResponse::success(response)))
+}
+
+/// Sends a dead-lettered delivery again with a fresh set of attempts. The
+/// payload and its event id are unchanged, so receivers can deduplicate.
+pub async fn replay_webhook_delivery(
+    auth: AuthUser,
+    State(pool): State<PgPool>,
+    Path((webhook_id, delivery_id)): Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<WebhookDeliveryResponse>>) {
+
+    let webhook_id = match find_own_webhook(&auth, &pool, &webhook_id).await {
+        Ok(v) => v,
+        Err((status, code)) => return error(status, code),
+    };
+
+    let delivery_id = match Uuid::parse_str(&delivery_id) {
+        Ok(v) => v,
+        Err(_) => return error(StatusCode::NOT_FOUND, "DELIVERY_NOT_FOUND"),
+    };
+
+    let status = sqlx::query_scalar!(
+        "SELECT status FROM webhook_deliveries WHERE id = $1 AND subscription_id = $2",
+        delivery_id,
+        webhook_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+
+    match status.as_deref() {
+        None => return error(StatusCode::NOT_FOUND, "DELIVERY_NOT_FOUND"),
+        Some("dead") => {}
+        Some(_) => return error(StatusCode::BAD_REQUEST, "DELIVERY_NOT_FAILED"),
+    }
+
+    // The status check is repeated so a concurrent replay queues it once.
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'pending', attempts = 0, next_attempt_at = now() AT TIME ZONE 'utc'
+        WHERE id = $1 AND status = 'dead'
+        "#,
+        delivery_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    (StatusCode::OK, Json(ApiResponse::success(load_delivery(&pool, delivery_id).await)))
+}
+
+async fn find_own_webhook(
+    auth: &AuthUser,
+    pool: &PgPool,
+    webhook_id: &str,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    if auth.role != "owner" {
+        return Err((StatusCode::FORBIDDEN, "FORBIDDEN"));
+    }
+
+    let webhook_id = Uuid::parse_str(webhook_id).map_err(|_| (StatusCode::NOT_FOUND, "WEBHOOK_NOT_FOUND"))?;
+
+    let owner = sqlx::query_scalar!("SELECT owner_id FROM webhook_subscriptions WHERE id = $1", webhook_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap();
+
+    match owner {
+        None => Err((StatusCode::NOT_FOUND, "WEBHOOK_NOT_FOUND")),
+        Some(owner_id) if owner_id != auth.user_id => Err((StatusCode::FORBIDDEN, "FORBIDDEN")),
+        Some(_) => Ok(webhook_id),
+    }
+}
+
+struct DeliveryRow {
+    id: Uuid,
+    subscription_id: Uuid,
+    event_id: Uuid,
+    event_type: String,
+    payload: String,
+    status: String,
+    attempts: i32,
+    next_attempt_at: NaiveDateTime,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    delivered_at: Option<NaiveDateTime>,
+}
+
+async fn load_delivery(pool: &PgPool, delivery_id: Uuid) -> WebhookDeliveryResponse {
+    let d = sqlx::query_as!(
+        DeliveryRow,
+        r#"
+        SELECT id, subscription_id, event_id, event_type, payload, status, attempts,
+               next_attempt_at, last_status_code, last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE id = $1
+        "#,
+        delivery_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    delivery_response(d)
+}
+
+fn delivery_response(d: DeliveryRow) -> WebhookDeliveryResponse {
+    WebhookDeliveryResponse {
+        id: d.id.to_string(),
+        subscriptionId: d.subscription_id.to_string(),
+        eventId: d.event_id.to_string(),
+        eventType: d.event_type,
+        nextAttemptAt: (d.status == "pending").then(|| d.next_attempt_at.and_utc().to_rfc3339()),
+        status: d.status,
+        attempts: d.attempts,
+        lastStatusCode: d.last_status_code,
+        lastError: d.last_error,
+        payload: serde_json::from_str(&d.payload).unwrap_or_default(),
+        createdAt: d.created_at.and_utc().to_rfc3339(),
+        deliveredAt: d.delivered_at.map(|t| t.and_utc().to_rfc3339()),
+    }
+}
+
+fn error<T>(status: StatusCode, code: &str) -> (StatusCode, Json<ApiResponse<T>>) {
+    (status, Json(ApiResponse::error(code)))
+}
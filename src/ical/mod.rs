@@ -0,0 +1,214 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+pub mod sync;
+
+/// One busy period of a room, as exported to or imported from a calendar.
+pub struct CalendarEvent {
+    pub uid: String,
+    /// First night taken.
+    pub start: NaiveDate,
+    /// Last night taken, inclusive like `room_blocks.end_date`.
+    pub end: NaiveDate,
+    pub summary: String,
+}
+
+/// Renders events as an iCalendar (RFC 5545) document of all-day VEVENTs.
+pub fn render(name: &str, events: &[CalendarEvent], stamp: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//hotel-booking-backend//Room calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ");
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", escape_text(&event.uid)));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", event.start.format("%Y%m%d")));
+        // DTEND is exclusive: the morning the room is free again.
+        lines.push(format!("DTEND;VALUE=DATE:{}", (event.end + Duration::days(1)).format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        lines.push("TRANSP:OPAQUE".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+
+    for line in lines {
+        fold_into(&mut out, &line);
+    }
+
+    out
+}
+
+/// Reads the busy periods out of an iCalendar document. Cancelled events and
+/// events without a UID or start date are skipped; anything else the parser
+/// does not understand is ignored.
+pub fn parse(document: &str) -> Result<Vec<CalendarEvent>, &'static str> {
+    let lines = unfold(document);
+
+    if !lines.iter().any(|l| l.eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err("not an iCalendar document");
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<EventBuilder> = None;
+
+    for line in &lines {
+        let Some((name, params, value)) = split_property(line) else {
+            continue;
+        };
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(EventBuilder::default());
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(event) = current.take().and_then(EventBuilder::build) {
+                    events.push(event);
+                }
+            }
+            ("UID", Some(e)) => e.uid = Some(unescape_text(value)),
+            ("SUMMARY", Some(e)) => e.summary = Some(unescape_text(value)),
+            ("STATUS", Some(e)) => e.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            ("DTSTART", Some(e)) => e.start = parse_date_time(params, value),
+            ("DTEND", Some(e)) => e.end = parse_date_time(params, value),
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+#[derive(Default)]
+struct EventBuilder {
+    uid: Option<String>,
+    summary: Option<String>,
+    start: Option<(NaiveDate, bool)>,
+    end: Option<(NaiveDate, bool)>,
+    cancelled: bool,
+}
+
+impl EventBuilder {
+    fn build(self) -> Option<CalendarEvent> {
+        if self.cancelled {
+            return None;
+        }
+
+        let uid = self.uid.filter(|u| !u.is_empty())?;
+        let (start, _) = self.start?;
+
+        // An end at midnight (or a plain date) is exclusive; an end later in
+        // the day still takes that night.
+        let end = match self.end {
+            Some((date, true)) => date - Duration::days(1),
+            Some((date, false)) => date,
+            None => start,
+        };
+
+        Some(CalendarEvent {
+            uid,
+            start,
+            end: end.max(start),
+            summary: self.summary.unwrap_or_default(),
+        })
+    }
+}
+
+/// The date of a DTSTART/DTEND value, and whether it falls on a day
+/// boundary. Times are taken as given; the hotel's timezone is not known.
+fn parse_date_time(params: &str, value: &str) -> Option<(NaiveDate, bool)> {
+    let value = value.trim();
+    let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+
+    if params.split(';').any(|p| p.trim().eq_ignore_ascii_case("VALUE=DATE")) {
+        return Some((date, true));
+    }
+
+    let time = value.get(9..15).unwrap_or("000000");
+
+    Some((date, time == "000000"))
+}
+
+/// Splits `NAME;PARAM=X:VALUE` into its upper-cased name, parameters and
+/// value.
+fn split_property(line: &str) -> Option<(String, &str, &str)> {
+    let colon = line.find(':')?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let (name, params) = match head.find(';') {
+        Some(i) => (&head[..i], &head[i + 1..]),
+        None => (head, ""),
+    };
+
+    Some((name.trim().to_ascii_uppercase(), params, value))
+}
+
+/// Joins folded lines back together (RFC 5545 section 3.1).
+fn unfold(document: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for raw in document.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if raw.is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+
+    lines
+}
+
+/// Appends a content line, folded at 75 octets and ended with CRLF.
+fn fold_into(out: &mut String, line: &str) {
+    let mut width = 0;
+
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+
+        out.push(c);
+        width += c.len_utf8();
+    }
+
+    out.push_str("\r\n");
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+
+    out.trim().to_string()
+}
@@ -0,0 +1,208 @@
+use chrono::Utc;
+use reqwest::Url;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{parse, CalendarEvent};
+use crate::{
+    lifecycle,
+    outbound::{self, OutboundError},
+};
+
+/// Calendars larger than this are rejected rather than read into memory.
+const MAX_CALENDAR_BYTES: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Blocked(#[from] OutboundError),
+    #[error("calendar returned {0}")]
+    Status(u16),
+    #[error("calendar is larger than {MAX_CALENDAR_BYTES} bytes")]
+    TooLarge,
+    #[error("invalid calendar: {0}")]
+    Invalid(&'static str),
+    #[error("database error: {0}")]
+    Db(#[from] sqlx::Error),
+}
+
+/// What one sync changed.
+#[derive(Default)]
+pub struct SyncOutcome {
+    /// Events now mirrored as blocks.
+    pub events: usize,
+    /// Blocks removed because their event left the calendar.
+    pub removed: u64,
+    /// Bookings here that overlap nights sold elsewhere, for the owner to
+    /// sort out.
+    pub conflicts: Vec<Uuid>,
+}
+
+/// Reads the calendar, checking its host again on every sync since what it
+/// resolves to can change after the import was created. The body is read a
+/// chunk at a time so an endless response stops at the size cap.
+async fn fetch(url: &str) -> Result<String, SyncError> {
+    let url = Url::parse(url).map_err(|_| SyncError::Blocked(OutboundError::InvalidUrl))?;
+    let client = outbound::client_for(&url, Duration::from_secs(15)).await?;
+
+    let mut response = client.get(url).send().await?;
+
+    if !response.status().is_success() {
+        return Err(SyncError::Status(response.status().as_u16()));
+    }
+
+    if response.content_length().is_some_and(|l| l as usize > MAX_CALENDAR_BYTES) {
+        return Err(SyncError::TooLarge);
+    }
+
+    let mut body = Vec::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_CALENDAR_BYTES {
+            return Err(SyncError::TooLarge);
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Fetches an imported calendar and mirrors its events as blocks on the
+/// room, replacing what the previous sync created. Failures are recorded on
+/// the import and leave its existing blocks in place.
+pub async fn sync_import(pool: &PgPool, import_id: Uuid) -> Result<SyncOutcome, SyncError> {
+    let url = sqlx::query_scalar!("SELECT url FROM room_calendar_imports WHERE id = $1", import_id)
+        .fetch_optional(pool)
+        .await?;
+
+    // Deleted since the caller looked it up.
+    let Some(url) = url else {
+        return Ok(SyncOutcome::default());
+    };
+
+    let events = match fetch(&url).await {
+        Ok(body) => parse(&body).map_err(SyncError::Invalid),
+        Err(e) => Err(e),
+    };
+
+    let events = match events {
+        Ok(v) => v,
+        Err(e) => {
+            sqlx::query!(
+                "UPDATE room_calendar_imports SET last_error = $2 WHERE id = $1",
+                import_id,
+                e.to_string()
+            )
+            .execute(pool)
+            .await?;
+
+            return Err(e);
+        }
+    };
+
+    let mut tx = pool.begin().await?;
+    let outcome = apply(&mut tx, import_id, &events).await?;
+    tx.commit().await?;
+
+    Ok(outcome)
+}
+
+async fn apply(
+    tx: &mut Transaction<'_, Postgres>,
+    import_id: Uuid,
+    events: &[CalendarEvent],
+) -> Result<SyncOutcome, SyncError> {
+    let import = sqlx::query!(
+        r#"
+        SELECT i.hotel_id, i.room_id, i.name, i.created_by
+        FROM room_calendar_imports i
+        JOIN rooms r ON r.id = i.room_id
+        WHERE i.id = $1
+        FOR UPDATE OF r
+        "#,
+        import_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(import) = import else {
+        return Ok(SyncOutcome::default());
+    };
+
+    // Stays that are already over no longer matter for availability.
+    let today = Utc::now().date_naive();
+    let events: Vec<&CalendarEvent> = events.iter().filter(|e| e.end >= today).collect();
+    let reason = format!("Imported from {}", import.name);
+
+    for event in &events {
+        sqlx::query!(
+            r#"
+            INSERT INTO room_blocks (hotel_id, room_id, start_date, end_date, reason, created_by, import_id, external_uid)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (import_id, external_uid) DO UPDATE
+            SET start_date = EXCLUDED.start_date,
+                end_date = EXCLUDED.end_date,
+                reason = EXCLUDED.reason
+            "#,
+            import.hotel_id,
+            import.room_id,
+            event.start,
+            event.end,
+            reason,
+            import.created_by,
+            import_id,
+            event.uid
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    let uids: Vec<String> = events.iter().map(|e| e.uid.clone()).collect();
+
+    let removed = sqlx::query!(
+        "DELETE FROM room_blocks WHERE import_id = $1 AND external_uid <> ALL($2)",
+        import_id,
+        &uids
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    // Block end dates are inclusive, unlike check-out dates.
+    let conflicts = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT b.id
+        FROM bookings b
+        JOIN room_blocks rb ON rb.room_id = b.room_id
+        WHERE rb.import_id = $1
+        AND b.status = ANY($2)
+        AND b.check_in_date <= rb.end_date
+        AND b.check_out_date > rb.start_date
+        "#,
+        import_id,
+        &lifecycle::OCCUPYING[..] as &[&str]
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE room_calendar_imports
+        SET last_synced_at = now() AT TIME ZONE 'utc', last_error = NULL
+        WHERE id = $1
+        "#,
+        import_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(SyncOutcome {
+        events: events.len(),
+        removed,
+        conflicts,
+    })
+}
@@ -0,0 +1,124 @@
+use std::fmt::Write;
+
+use super::{Document, DocumentKind, Party};
+
+const STYLE: &str = "\
+body { font-family: Helvetica, Arial, sans-serif; color: #222; margin: 2em auto; max-width: 48em; }
+section { page-break-after: always; margin-bottom: 3em; }
+section:last-child { page-break-after: auto; }
+header { display: flex; justify-content: space-between; }
+table { width: 100%; border-collapse: collapse; margin-top: 1.5em; }
+th, td { text-align: left; padding: 0.4em; border-bottom: 1px solid #ddd; }
+td.amount, th.amount { text-align: right; }
+tfoot td { font-weight: bold; border-bottom: none; }
+.muted { color: #777; }
+";
+
+/// A printable page with the invoice followed by its credit notes, one per
+/// printed page.
+pub fn render(invoice: &Document, credit_notes: &[Document]) -> String {
+    let mut out = String::new();
+
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{} {}</title>", invoice.kind.title(), escape(&invoice.number));
+    let _ = writeln!(out, "<style>\n{STYLE}</style>\n</head>\n<body>");
+
+    for document in std::iter::once(invoice).chain(credit_notes) {
+        render_document(&mut out, document);
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn render_document(out: &mut String, d: &Document) {
+    out.push_str("<section>\n<header>\n<div>\n");
+    let _ = writeln!(out, "<h1>{}</h1>", d.kind.title());
+    let _ = writeln!(out, "<p>No. {}<br>Issued {}</p>", escape(&d.number), d.issued_at.date());
+
+    if let Some(corrects) = &d.corrects {
+        let _ = writeln!(out, "<p>Corrects invoice {}</p>", escape(corrects));
+    }
+
+    if d.kind == DocumentKind::Invoice && d.status == "void" {
+        out.push_str("<p><strong>Void</strong>: replaced by a later invoice.</p>\n");
+    }
+
+    out.push_str("</div>\n<div>\n");
+    render_party(out, &d.seller);
+    out.push_str("</div>\n</header>\n");
+
+    out.push_str("<h2>Billed to</h2>\n");
+    render_party(out, &d.customer);
+
+    let _ = writeln!(
+        out,
+        "<p>Room {}, {} to {}, {} guest{}",
+        escape(&d.room_number),
+        d.check_in,
+        d.check_out,
+        d.guests,
+        if d.guests == 1 { "" } else { "s" }
+    );
+
+    if let Some(lead) = &d.lead_guest_name {
+        let _ = write!(out, "<br>Lead guest: {}", escape(lead));
+    }
+
+    out.push_str("</p>\n<table>\n<thead><tr><th>Description</th><th class=\"amount\">Amount</th></tr></thead>\n<tbody>\n");
+
+    for line in &d.lines {
+        let note = if line.inclusive { " <span class=\"muted\">(included)</span>" } else { "" };
+        let _ = writeln!(
+            out,
+            "<tr><td>{}{}</td><td class=\"amount\">{}</td></tr>",
+            escape(&line.description),
+            note,
+            line.amount.with_scale(2)
+        );
+    }
+
+    let label = match d.kind {
+        DocumentKind::Invoice => "Total",
+        DocumentKind::CreditNote => "Total credited",
+    };
+
+    let _ = writeln!(
+        out,
+        "</tbody>\n<tfoot><tr><td>{}</td><td class=\"amount\">{} {}</td></tr></tfoot>\n</table>\n</section>",
+        label,
+        d.total.with_scale(2),
+        escape(&d.currency)
+    );
+}
+
+fn render_party(out: &mut String, party: &Party) {
+    let _ = write!(out, "<p><strong>{}</strong>", escape(&party.name));
+
+    for line in [&party.address, &party.email].into_iter().flatten() {
+        let _ = write!(out, "<br>{}", escape(line));
+    }
+
+    if let Some(tax_id) = &party.tax_id {
+        let _ = write!(out, "<br>Tax ID: {}", escape(tax_id));
+    }
+
+    out.push_str("</p>\n");
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,482 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{types::BigDecimal, PgConnection};
+use uuid::Uuid;
+
+pub mod html;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentKind {
+    Invoice,
+    CreditNote,
+}
+
+impl DocumentKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "invoice" => Some(Self::Invoice),
+            "credit_note" => Some(Self::CreditNote),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Invoice => "invoice",
+            Self::CreditNote => "credit_note",
+        }
+    }
+
+    /// Printed in front of the sequence number, e.g. INV-000042.
+    fn prefix(&self) -> &'static str {
+        match self {
+            Self::Invoice => "INV",
+            Self::CreditNote => "CN",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Invoice => "Invoice",
+            Self::CreditNote => "Credit note",
+        }
+    }
+}
+
+/// An issued invoice or credit note, exactly as it was issued.
+pub struct Document {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    pub kind: DocumentKind,
+    pub number: String,
+    /// For a credit note, the number of the invoice it corrects.
+    pub corrects: Option<String>,
+    pub status: String,
+    pub reason: Option<String>,
+    pub seller: Party,
+    pub customer: Party,
+    pub lead_guest_name: Option<String>,
+    pub room_number: String,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    pub guests: i32,
+    pub lines: Vec<DocumentLine>,
+    pub currency: String,
+    /// Amount charged, or credited for a credit note.
+    pub total: BigDecimal,
+    pub issued_at: NaiveDateTime,
+}
+
+pub struct Party {
+    pub name: String,
+    pub address: Option<String>,
+    pub tax_id: Option<String>,
+    pub email: Option<String>,
+}
+
+pub struct DocumentLine {
+    /// `night`, `discount`, `tax`, `fee` or `refund`.
+    pub kind: String,
+    pub description: String,
+    pub amount: BigDecimal,
+    pub inclusive: bool,
+}
+
+/// Whether a booking is paid for, and so has an invoice.
+pub fn invoiceable(status: &str) -> bool {
+    matches!(status, "confirmed" | "checked_in" | "checked_out")
+}
+
+/// The booking's invoice in force: the latest one not replaced after a
+/// change.
+pub async fn current_invoice(conn: &mut PgConnection, booking_id: Uuid) -> Option<Uuid> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id FROM invoices
+        WHERE booking_id = $1 AND kind = 'invoice' AND status = 'issued'
+        ORDER BY issued_at DESC
+        LIMIT 1
+        "#,
+        booking_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()
+}
+
+/// Hands out the next number in a hotel's series. The sequence row stays
+/// locked until the caller commits, so numbers are never skipped or reused.
+async fn next_number(conn: &mut PgConnection, hotel_id: Uuid, kind: DocumentKind) -> i32 {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO invoice_sequences (hotel_id, kind, last_number)
+        VALUES ($1, $2, 1)
+        ON CONFLICT (hotel_id, kind) DO UPDATE
+        SET last_number = invoice_sequences.last_number + 1
+        RETURNING last_number
+        "#,
+        hotel_id,
+        kind.as_str()
+    )
+    .fetch_one(conn)
+    .await
+    .unwrap()
+}
+
+fn document_number(kind: DocumentKind, number: i32) -> String {
+    format!("{}-{:06}", kind.prefix(), number)
+}
+
+/// Issues an invoice for the booking as it stands: the hotel's billing
+/// details, the customer, and every night, discount, tax and fee charged.
+pub async fn issue_invoice(conn: &mut PgConnection, booking_id: Uuid) -> Uuid {
+    let booking = sqlx::query!(
+        r#"
+        SELECT
+            b.hotel_id,
+            b.check_in_date,
+            b.check_out_date,
+            b.guests,
+            b.currency,
+            b.total_price,
+            r.room_number,
+            COALESCE(bd.legal_name, h.name) AS "seller_name!",
+            COALESCE(bd.address, h.city || ', ' || h.country) AS "seller_address!",
+            bd.tax_id AS "seller_tax_id?",
+            bd.email AS "seller_email?",
+            u.name AS customer_name,
+            u.email AS customer_email,
+            g.lead_guest_name AS "lead_guest_name?"
+        FROM bookings b
+        JOIN rooms r ON r.id = b.room_id
+        JOIN hotels h ON h.id = b.hotel_id
+        JOIN users u ON u.id = b.user_id
+        LEFT JOIN hotel_billing_details bd ON bd.hotel_id = b.hotel_id
+        LEFT JOIN booking_guest_details g ON g.booking_id = b.id
+        WHERE b.id = $1
+        "#,
+        booking_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    let number = next_number(conn, booking.hotel_id, DocumentKind::Invoice).await;
+
+    let invoice_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO invoices (
+            hotel_id,
+            booking_id,
+            kind,
+            number,
+            document_number,
+            seller_name,
+            seller_address,
+            seller_tax_id,
+            seller_email,
+            customer_name,
+            customer_email,
+            lead_guest_name,
+            room_number,
+            check_in_date,
+            check_out_date,
+            guests,
+            currency,
+            total
+        )
+        VALUES ($1,$2,'invoice',$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17)
+        RETURNING id
+        "#,
+        booking.hotel_id,
+        booking_id,
+        number,
+        document_number(DocumentKind::Invoice, number),
+        booking.seller_name,
+        booking.seller_address,
+        booking.seller_tax_id,
+        booking.seller_email,
+        booking.customer_name,
+        booking.customer_email,
+        booking.lead_guest_name,
+        booking.room_number,
+        booking.check_in_date,
+        booking.check_out_date,
+        booking.guests,
+        booking.currency,
+        booking.total_price
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    // Nights first, then discounts, taxes and fees in the order they were
+    // applied. Discounts are printed as negative amounts.
+    sqlx::query!(
+        r#"
+        INSERT INTO invoice_lines (invoice_id, position, kind, description, amount, inclusive)
+        SELECT $1, row_number() OVER (ORDER BY sort, position), kind, description, amount, inclusive
+        FROM (
+            SELECT
+                0 AS sort,
+                night_date - DATE '1970-01-01' AS position,
+                'night' AS kind,
+                'Night of ' || to_char(night_date, 'YYYY-MM-DD') AS description,
+                price AS amount,
+                false AS inclusive
+            FROM booking_nights
+            WHERE booking_id = $2
+            UNION ALL
+            SELECT
+                CASE category WHEN 'discount' THEN 1 WHEN 'tax' THEN 2 ELSE 3 END,
+                position,
+                category,
+                name,
+                CASE category WHEN 'discount' THEN -amount ELSE amount END,
+                inclusive
+            FROM booking_line_items
+            WHERE booking_id = $2
+        ) lines
+        "#,
+        invoice_id,
+        booking_id
+    )
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+
+    invoice_id
+}
+
+/// Issues a credit note against an invoice for `amount` of it.
+async fn issue_credit_note(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+    amount: &BigDecimal,
+    reason: &str,
+    description: &str,
+) -> Uuid {
+    let hotel_id = sqlx::query_scalar!("SELECT hotel_id FROM invoices WHERE id = $1", invoice_id)
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    let number = next_number(conn, hotel_id, DocumentKind::CreditNote).await;
+
+    // Parties and stay are copied from the invoice being corrected.
+    let credit_note_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO invoices (
+            hotel_id,
+            booking_id,
+            kind,
+            number,
+            document_number,
+            invoice_id,
+            reason,
+            seller_name,
+            seller_address,
+            seller_tax_id,
+            seller_email,
+            customer_name,
+            customer_email,
+            lead_guest_name,
+            room_number,
+            check_in_date,
+            check_out_date,
+            guests,
+            currency,
+            total
+        )
+        SELECT
+            hotel_id,
+            booking_id,
+            'credit_note',
+            $2,
+            $3,
+            id,
+            $4,
+            seller_name,
+            seller_address,
+            seller_tax_id,
+            seller_email,
+            customer_name,
+            customer_email,
+            lead_guest_name,
+            room_number,
+            check_in_date,
+            check_out_date,
+            guests,
+            currency,
+            $5
+        FROM invoices
+        WHERE id = $1
+        RETURNING id
+        "#,
+        invoice_id,
+        number,
+        document_number(DocumentKind::CreditNote, number),
+        reason,
+        amount
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO invoice_lines (invoice_id, position, kind, description, amount)
+        VALUES ($1, 1, 'refund', $2, $3)
+        "#,
+        credit_note_id,
+        description,
+        amount
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+
+    credit_note_id
+}
+
+/// Credits a refund on a paid booking. A booking refunded before it was
+/// ever invoiced is invoiced first, since the payment was taken either way
+/// and the credit note needs something to correct.
+pub async fn credit_refund(conn: &mut PgConnection, booking_id: Uuid, amount: &BigDecimal, reason: &str) {
+    let invoice_id = match current_invoice(conn, booking_id).await {
+        Some(id) => id,
+        None => issue_invoice(conn, booking_id).await,
+    };
+
+    if *amount <= BigDecimal::from(0) {
+        return;
+    }
+
+    let description = match reason {
+        "customer_cancellation" => "Refund on cancellation by the guest",
+        "hotel_cancellation" => "Refund on cancellation by the hotel",
+        _ => "Refund",
+    };
+
+    issue_credit_note(conn, invoice_id, amount, reason, description).await;
+}
+
+/// Cancels the booking's invoice in full after its stay or price changed.
+/// The next request for the invoice issues a new one for the booking as it
+/// now stands.
+pub async fn void_current(conn: &mut PgConnection, booking_id: Uuid) {
+    let Some(invoice_id) = current_invoice(conn, booking_id).await else {
+        return;
+    };
+
+    let total = sqlx::query_scalar!(
+        "UPDATE invoices SET status = 'void' WHERE id = $1 RETURNING total",
+        invoice_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    issue_credit_note(conn, invoice_id, &total, "booking_modified", "Booking modified; replaced by a new invoice").await;
+}
+
+/// An invoice and every credit note issued against it, oldest first.
+pub async fn load_with_credit_notes(conn: &mut PgConnection, invoice_id: Uuid) -> (Document, Vec<Document>) {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            i.id,
+            i.booking_id,
+            i.kind,
+            i.document_number,
+            c.document_number AS "corrects?",
+            i.status,
+            i.reason,
+            i.seller_name,
+            i.seller_address,
+            i.seller_tax_id,
+            i.seller_email,
+            i.customer_name,
+            i.customer_email,
+            i.lead_guest_name,
+            i.room_number,
+            i.check_in_date,
+            i.check_out_date,
+            i.guests,
+            i.currency,
+            i.total,
+            i.issued_at
+        FROM invoices i
+        LEFT JOIN invoices c ON c.id = i.invoice_id
+        WHERE i.id = $1 OR i.invoice_id = $1
+        ORDER BY i.kind = 'credit_note', i.number
+        "#,
+        invoice_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .unwrap();
+
+    let ids: Vec<Uuid> = rows.iter().map(|r| r.id).collect();
+
+    let lines = sqlx::query!(
+        r#"
+        SELECT invoice_id, kind, description, amount, inclusive
+        FROM invoice_lines
+        WHERE invoice_id = ANY($1)
+        ORDER BY invoice_id, position
+        "#,
+        &ids
+    )
+    .fetch_all(conn)
+    .await
+    .unwrap();
+
+    let mut documents: Vec<Document> = rows
+        .into_iter()
+        .map(|r| Document {
+            id: r.id,
+            booking_id: r.booking_id,
+            kind: DocumentKind::parse(&r.kind).unwrap_or(DocumentKind::Invoice),
+            number: r.document_number,
+            corrects: r.corrects,
+            status: r.status,
+            reason: r.reason,
+            seller: Party {
+                name: r.seller_name,
+                address: r.seller_address,
+                tax_id: r.seller_tax_id,
+                email: r.seller_email,
+            },
+            customer: Party {
+                name: r.customer_name,
+                address: None,
+                tax_id: None,
+                email: Some(r.customer_email),
+            },
+            lead_guest_name: r.lead_guest_name,
+            room_number: r.room_number,
+            check_in: r.check_in_date,
+            check_out: r.check_out_date,
+            guests: r.guests,
+            lines: Vec::new(),
+            currency: r.currency,
+            total: r.total,
+            issued_at: r.issued_at,
+        })
+        .collect();
+
+    for line in lines {
+        if let Some(document) = documents.iter_mut().find(|d| d.id == line.invoice_id) {
+            document.lines.push(DocumentLine {
+                kind: line.kind,
+                description: line.description,
+                amount: line.amount,
+                inclusive: line.inclusive,
+            });
+        }
+    }
+
+    let invoice = documents.remove(0);
+
+    (invoice, documents)
+}
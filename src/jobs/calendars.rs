@@ -0,0 +1,37 @@
+use sqlx::PgPool;
+use std::{env, time::Duration};
+
+use crate::ical::sync::sync_import;
+
+/// Periodically re-reads every imported calendar so nights sold on other
+/// platforms are blocked here too. One failing calendar does not hold up
+/// the rest; its error is kept on the import.
+pub async fn sync_calendars(pool: PgPool) {
+    let seconds = env::var("CALENDAR_SYNC_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15 * 60);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+
+        let imports = match sqlx::query_scalar!("SELECT id FROM room_calendar_imports ORDER BY last_synced_at NULLS FIRST")
+            .fetch_all(&pool)
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("calendar sync: {e}");
+                continue;
+            }
+        };
+
+        for import_id in imports {
+            if let Err(e) = sync_import(&pool, import_id).await {
+                eprintln!("calendar sync {import_id}: {e}");
+            }
+        }
+    }
+}
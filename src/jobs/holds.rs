@@ -0,0 +1,59 @@
+use sqlx::PgPool;
+use std::{env, time::Duration};
+
+use crate::{availability::holds::release_expired, handlers::waitlist::settle_offers};
+
+/// Periodically releases holds that were never confirmed and bookings that
+/// were never paid for. Booking paths also
+/// release a room's lapsed holds on demand, so this only keeps listings and
+/// search results tidy. Waitlist offers that lapsed are then passed on to
+/// the next customer waiting.
+pub async fn sweep_expired_holds(pool: PgPool) {
+    let seconds = env::var("HOLD_SWEEP_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+
+        let mut tx = match pool.begin().await {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("hold sweeper: {e}");
+                continue;
+            }
+        };
+
+        let released = match release_expired(&mut tx, None).await {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("hold sweeper: {e}");
+                continue;
+            }
+        };
+
+        let lapsed = match settle_offers(&mut tx).await {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("hold sweeper: {e}");
+                continue;
+            }
+        };
+
+        if let Err(e) = tx.commit().await {
+            eprintln!("hold sweeper: {e}");
+            continue;
+        }
+
+        if released > 0 {
+            println!("Released {released} expired holds and unpaid bookings");
+        }
+
+        if lapsed > 0 {
+            println!("Passed {lapsed} lapsed waitlist offers on");
+        }
+    }
+}
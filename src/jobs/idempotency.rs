@@ -0,0 +1,32 @@
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::handlers::idempotency::key_ttl_hours;
+
+/// Deletes stored idempotent responses once their keys have expired. Expired
+/// keys are already ignored on lookup; this only keeps the table small.
+pub async fn purge_expired_keys(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(3600));
+
+    loop {
+        interval.tick().await;
+
+        let purged = sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE created_at < now() AT TIME ZONE 'utc' - make_interval(hours => $1)
+            "#,
+            key_ttl_hours() as i32
+        )
+        .execute(&pool)
+        .await;
+
+        match purged {
+            Ok(r) if r.rows_affected() > 0 => {
+                println!("Purged {} expired idempotency keys", r.rows_affected())
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("idempotency key purge: {e}"),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{Days, Utc};
+use sqlx::PgPool;
+use std::{env, sync::Arc, time::Duration};
+
+use crate::mail::{outbox, Mailer, Template};
+
+/// Drains the email outbox. Failed sends are retried with backoff by
+/// `outbox::deliver_due`; this only decides how often to look.
+pub async fn deliver_emails(pool: PgPool, mailer: Arc<dyn Mailer>) {
+    let seconds = env::var("MAIL_POLL_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+
+        match outbox::deliver_due(&pool, mailer.as_ref(), 50).await {
+            Ok(sent) if sent > 0 => println!("Sent {sent} emails"),
+            Ok(_) => {}
+            Err(e) => eprintln!("email outbox: {e}"),
+        }
+    }
+}
+
+/// Queues a reminder for confirmed stays starting within
+/// `PRE_ARRIVAL_REMINDER_DAYS`. Bookings already reminded are skipped by the
+/// outbox, so this can run as often as it likes.
+pub async fn queue_reminders(pool: PgPool) {
+    let days = env::var("PRE_ARRIVAL_REMINDER_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(3600));
+
+    loop {
+        interval.tick().await;
+
+        let today = Utc::now().date_naive();
+
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT b.id
+            FROM bookings b
+            WHERE b.status = 'confirmed'
+            AND b.check_in_date BETWEEN $1 AND $2
+            AND NOT EXISTS (
+                SELECT 1 FROM email_outbox o
+                WHERE o.booking_id = b.id AND o.template = $3
+            )
+            "#,
+            today,
+            today + Days::new(days),
+            Template::PreArrivalReminder.as_str()
+        )
+        .fetch_all(&pool)
+        .await;
+
+        let due = match due {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("pre-arrival reminders: {e}");
+                continue;
+            }
+        };
+
+        let mut queued = 0;
+
+        for booking_id in &due {
+            let mut conn = match pool.acquire().await {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("pre-arrival reminders: {e}");
+                    break;
+                }
+            };
+
+            // One booking that cannot be queued does not hold up the rest;
+            // it is picked up again on the next run.
+            match outbox::enqueue(&mut conn, *booking_id, Template::PreArrivalReminder).await {
+                Ok(()) => queued += 1,
+                Err(e) => eprintln!("pre-arrival reminder {booking_id}: {e}"),
+            }
+        }
+
+        if queued > 0 {
+            println!("Queued {queued} pre-arrival reminders");
+        }
+    }
+}
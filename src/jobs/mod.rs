@@ -0,0 +1,22 @@
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{mail::Mailer, payments::PaymentGateway};
+
+pub mod calendars;
+pub mod holds;
+pub mod idempotency;
+pub mod mail;
+pub mod payments;
+pub mod webhooks;
+
+/// Starts every background task the server runs alongside the API.
+pub fn spawn_all(pool: PgPool, mailer: Arc<dyn Mailer>, gateway: Arc<dyn PaymentGateway>) {
+    tokio::spawn(holds::sweep_expired_holds(pool.clone()));
+    tokio::spawn(idempotency::purge_expired_keys(pool.clone()));
+    tokio::spawn(calendars::sync_calendars(pool.clone()));
+    tokio::spawn(mail::queue_reminders(pool.clone()));
+    tokio::spawn(mail::deliver_emails(pool.clone(), mailer));
+    tokio::spawn(payments::settle_payments(pool.clone(), gateway));
+    tokio::spawn(webhooks::deliver_webhooks(pool));
+}
@@ -0,0 +1,36 @@
+use sqlx::PgPool;
+use std::{env, sync::Arc, time::Duration};
+
+use crate::{
+    handlers::payments::{settle_refunds, void_abandoned},
+    payments::PaymentGateway,
+};
+
+/// Voids the intents of bookings that expired or were cancelled before
+/// being paid, and retries refunds the provider turned down or that were
+/// never sent, e.g. because the server stopped right after a cancellation
+/// committed.
+pub async fn settle_payments(pool: PgPool, gateway: Arc<dyn PaymentGateway>) {
+    let seconds = env::var("PAYMENT_SETTLE_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+
+        match void_abandoned(&pool, gateway.as_ref(), None).await {
+            Ok(failed) if failed > 0 => eprintln!("payment settlement: {failed} intents still to void"),
+            Ok(_) => {}
+            Err(e) => eprintln!("payment settlement: {e}"),
+        }
+
+        match settle_refunds(&pool, gateway.as_ref(), None).await {
+            Ok(failed) if failed > 0 => eprintln!("payment settlement: {failed} refunds still pending"),
+            Ok(_) => {}
+            Err(e) => eprintln!("payment settlement: {e}"),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use sqlx::PgPool;
+use std::{env, time::Duration};
+
+use crate::webhooks::deliver_due;
+
+/// Posts queued webhook deliveries to owners' endpoints. Retries and dead
+/// letters are handled by `webhooks::deliver_due`.
+pub async fn deliver_webhooks(pool: PgPool) {
+    let seconds = env::var("WEBHOOK_POLL_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+
+        match deliver_due(&pool, 50).await {
+            Ok(delivered) if delivered > 0 => println!("Delivered {delivered} webhooks"),
+            Ok(_) => {}
+            Err(e) => eprintln!("webhook delivery: {e}"),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+/// Every state a booking can be in. All status changes are checked against
+/// `can_become`, so the legal moves live in one place: single bookings
+/// through `transition`, bulk updates by guarding on `sources`.
+///
+/// ```text
+/// held ──> pending_payment ──> confirmed ──> checked_in ──> checked_out
+///   │            │                 │
+///   │            │                 └──> no_show
+///   └────────────┴─────────────────┴──> cancelled
+/// held, pending_payment ──> expired
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookingStatus {
+    Held,
+    PendingPayment,
+    Confirmed,
+    CheckedIn,
+    CheckedOut,
+    NoShow,
+    Cancelled,
+    Expired,
+}
+
+/// Statuses in which a booking takes its room for its dates. Must match the
+/// `bookings_no_overlap` constraint.
+pub const OCCUPYING: [&str; 4] = ["held", "pending_payment", "confirmed", "checked_in"];
+
+impl BookingStatus {
+    pub const ALL: [Self; 8] = [
+        Self::Held,
+        Self::PendingPayment,
+        Self::Confirmed,
+        Self::CheckedIn,
+        Self::CheckedOut,
+        Self::NoShow,
+        Self::Cancelled,
+        Self::Expired,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "held" => Some(Self::Held),
+            "pending_payment" => Some(Self::PendingPayment),
+            "confirmed" => Some(Self::Confirmed),
+            "checked_in" => Some(Self::CheckedIn),
+            "checked_out" => Some(Self::CheckedOut),
+            "no_show" => Some(Self::NoShow),
+            "cancelled" => Some(Self::Cancelled),
+            "expired" => Some(Self::Expired),
+            _ => None,
+        }
+    }
+
+    /// Reads the nullable status column, which defaults to confirmed.
+    pub fn from_column(value: Option<&str>) -> Self {
+        value.and_then(Self::parse).unwrap_or(Self::Confirmed)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Held => "held",
+            Self::PendingPayment => "pending_payment",
+            Self::Confirmed => "confirmed",
+            Self::CheckedIn => "checked_in",
+            Self::CheckedOut => "checked_out",
+            Self::NoShow => "no_show",
+            Self::Cancelled => "cancelled",
+            Self::Expired => "expired",
+        }
+    }
+
+    pub fn can_become(self, next: Self) -> bool {
+        use BookingStatus::*;
+
+        matches!(
+            (self, next),
+            (Held, PendingPayment | Cancelled | Expired)
+                | (PendingPayment, Confirmed | Cancelled | Expired)
+                | (Confirmed, CheckedIn | NoShow | Cancelled)
+                | (CheckedIn, CheckedOut)
+        )
+    }
+
+    /// Error code for a move `can_become` rejects.
+    pub fn transition(self, next: Self) -> Result<(), &'static str> {
+        if self.can_become(next) {
+            return Ok(());
+        }
+
+        Err(match self {
+            Self::Cancelled => "ALREADY_CANCELLED",
+            Self::Expired => "HOLD_EXPIRED",
+            _ => "INVALID_STATUS_TRANSITION",
+        })
+    }
+
+    /// The statuses that may move to `next`, for the `status = ANY(..)`
+    /// guard of an UPDATE.
+    pub fn sources(next: Self) -> Vec<&'static str> {
+        Self::ALL
+            .into_iter()
+            .filter(|s| s.can_become(next))
+            .map(|s| s.as_str())
+            .collect()
+    }
+
+    /// Cancelled or expired: the booking no longer takes part in its
+    /// reservation's status or totals.
+    pub fn is_void(self) -> bool {
+        matches!(self, Self::Cancelled | Self::Expired)
+    }
+
+    /// Error code for changing the stay of a booking in this status. Only a
+    /// confirmed booking can be modified.
+    pub fn modifiable(self) -> Result<(), &'static str> {
+        match self {
+            Self::Confirmed => Ok(()),
+            Self::Cancelled => Err("ALREADY_CANCELLED"),
+            Self::Expired => Err("HOLD_EXPIRED"),
+            _ => Err("INVALID_STATUS_TRANSITION"),
+        }
+    }
+}
+
+/// Status of a multi-room reservation from the statuses of its bookings.
+/// Rooms still to be paid for come first, then guests in house; once every
+/// room is done it is `checked_out`, or `no_show` if nobody came. Rooms
+/// that were cancelled show only next to ones that are still confirmed.
+pub fn reservation_status(bookings: &[BookingStatus]) -> &'static str {
+    use BookingStatus::*;
+
+    let live: Vec<_> = bookings.iter().copied().filter(|s| !s.is_void()).collect();
+
+    if live.is_empty() {
+        "cancelled"
+    } else if live.iter().any(|s| matches!(s, Held | PendingPayment)) {
+        "pending_payment"
+    } else if live.contains(&CheckedIn) {
+        "checked_in"
+    } else if live.iter().all(|s| *s == NoShow) {
+        "no_show"
+    } else if live.iter().all(|s| matches!(s, CheckedOut | NoShow)) {
+        "checked_out"
+    } else if live.len() < bookings.len() {
+        "partially_cancelled"
+    } else {
+        "confirmed"
+    }
+}
@@ -0,0 +1,36 @@
+use axum::async_trait;
+use std::{env, path::PathBuf};
+
+use super::{mail_from, Email, MailError, Mailer};
+
+/// Writes each email to `<MAIL_FILE_DIR>/<id>.eml` instead of sending it,
+/// for development and tests.
+pub struct FileMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn from_env() -> Self {
+        let dir = env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "mail".to_string());
+
+        Self {
+            dir: PathBuf::from(dir),
+            from: mail_from(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = email.to_message(&self.from)?;
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let path = self.dir.join(format!("{}.eml", email.id));
+        tokio::fs::write(path, message).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use axum::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use std::{env, sync::Arc};
+use uuid::Uuid;
+
+pub mod file;
+pub mod outbox;
+pub mod smtp;
+mod templates;
+
+pub use templates::Template;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("mail server replied {0}: {1}")]
+    Rejected(u16, String),
+    #[error("mail server timed out")]
+    Timeout,
+    #[error("invalid address: {0:?}")]
+    InvalidAddress(String),
+    #[error("line break in an SMTP command")]
+    LineBreak,
+}
+
+/// A plain-text email, as taken off the outbox.
+pub struct Email {
+    /// The outbox row, reused as the Message-ID so retries can be spotted.
+    pub id: Uuid,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Email {
+    /// The message in RFC 5322 form with CRLF line endings. Fails rather
+    /// than write an address that could smuggle in another header.
+    pub fn to_message(&self, from: &str) -> Result<String, MailError> {
+        if !is_valid_address(&self.to) {
+            return Err(MailError::InvalidAddress(self.to.clone()));
+        }
+
+        if !is_plain(from) {
+            return Err(MailError::InvalidAddress(from.to_string()));
+        }
+
+        let domain = from.rsplit('@').next().map(|d| d.trim_end_matches('>')).unwrap_or("localhost");
+
+        let mut message = format!(
+            "From: {from}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{domain}>\r\n\
+             MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+            self.to,
+            encode_header(&self.subject),
+            Utc::now().to_rfc2822(),
+            self.id,
+        );
+
+        // A bare CR is dropped too, since some servers treat it as a line end.
+        for line in self.body.lines() {
+            message.push_str(&line.replace('\r', ""));
+            message.push_str("\r\n");
+        }
+
+        Ok(message)
+    }
+}
+
+/// Printable ASCII only, so nothing can end the header line early.
+fn is_plain(value: &str) -> bool {
+    value.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+
+/// A bare `local@domain` address that is safe to put in a header and an
+/// SMTP command: printable ASCII with no spaces, brackets or separators.
+pub fn is_valid_address(address: &str) -> bool {
+    let Some((local, domain)) = address.split_once('@') else {
+        return false;
+    };
+
+    !local.is_empty()
+        && !domain.is_empty()
+        && address.len() <= 254
+        && address
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"<>()[]\\,;:\"".contains(&b))
+        && !domain.contains('@')
+}
+
+/// Plain printable ASCII goes as it is; anything else is sent as RFC 2047
+/// encoded words, short enough to keep each folded line within limits.
+fn encode_header(value: &str) -> String {
+    if is_plain(value) {
+        return value.to_string();
+    }
+
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+
+    for c in value.chars() {
+        // 45 bytes encode to 60 characters, which with the markers stays
+        // under the 75 allowed per word. Characters are never split.
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+            chunk.clear();
+        }
+
+        chunk.push(c);
+    }
+
+    words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+
+    words.join("\r\n ")
+}
+
+/// Delivers outbox emails. Errors are retried by the outbox worker, so a
+/// backend only needs to make one attempt.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+pub fn create_mailer() -> Arc<dyn Mailer> {
+    let backend = env::var("MAIL_BACKEND").unwrap_or_else(|_| "file".to_string());
+
+    match backend.as_str() {
+        "file" => Arc::new(file::FileMailer::from_env()),
+        "smtp" => Arc::new(smtp::SmtpMailer::from_env()),
+        other => panic!("Unknown MAIL_BACKEND: {other}"),
+    }
+}
+
+fn mail_from() -> String {
+    env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string())
+}
@@ -0,0 +1,200 @@
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::env;
+use uuid::Uuid;
+
+use super::{
+    templates::{self, BookingDetails, Template},
+    is_valid_address, Email, MailError, Mailer,
+};
+
+/// Queues a booking email in the caller's transaction, so it goes out if
+/// and only if the change it describes commits. Queuing the same template
+/// for a booking again does nothing.
+pub(crate) async fn enqueue(conn: &mut PgConnection, booking_id: Uuid, template: Template) -> sqlx::Result<()> {
+    let b = sqlx::query!(
+        r#"
+        SELECT
+            u.name AS guest_name,
+            u.email,
+            h.name AS hotel_name,
+            h.city,
+            h.country,
+            r.room_number,
+            b.user_id,
+            b.check_in_date,
+            b.check_out_date,
+            b.guests,
+            b.total_price,
+            b.currency,
+            b.cancelled_by,
+            b.cancellation_reason,
+            (SELECT COALESCE(sum(f.amount), 0) FROM refunds f WHERE f.booking_id = b.id) AS "refunded!"
+        FROM bookings b
+        JOIN users u ON u.id = b.user_id
+        JOIN hotels h ON h.id = b.hotel_id
+        JOIN rooms r ON r.id = b.room_id
+        WHERE b.id = $1
+        "#,
+        booking_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let details = BookingDetails {
+        booking_id,
+        guest_name: b.guest_name,
+        hotel_name: b.hotel_name,
+        city: b.city,
+        country: b.country,
+        room_number: b.room_number,
+        check_in: b.check_in_date,
+        check_out: b.check_out_date,
+        guests: b.guests,
+        total: b.total_price,
+        currency: b.currency,
+        hotel_cancellation_reason: b
+            .cancelled_by
+            .filter(|by| *by != b.user_id)
+            .and(b.cancellation_reason),
+        refunded: b.refunded,
+    };
+
+    let (subject, body) = templates::render(template, &details);
+
+    // An address that could not be put in a header is kept on record as
+    // failed rather than handed to a mailer.
+    let (status, error) = if is_valid_address(&b.email) {
+        ("pending", None)
+    } else {
+        ("failed", Some(MailError::InvalidAddress(b.email.clone()).to_string()))
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO email_outbox (template, booking_id, recipient, subject, body, status, last_error)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (booking_id, template) DO NOTHING
+        "#,
+        template.as_str(),
+        booking_id,
+        b.email,
+        subject,
+        body,
+        status,
+        error
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Attempts before an email is marked failed and left alone.
+pub fn max_attempts() -> i32 {
+    env::var("MAIL_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8)
+}
+
+/// Wait before the next attempt: `MAIL_RETRY_BASE_SECONDS` (default 30),
+/// doubling each time, at most an hour.
+fn backoff(attempts: i32) -> Duration {
+    let base: i64 = env::var("MAIL_RETRY_BASE_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+
+    let seconds = base.max(1) << attempts.clamp(1, 8).saturating_sub(1);
+    Duration::seconds(seconds.min(3600))
+}
+
+/// How long a worker has to send a claimed email before another may take
+/// it over; well beyond the SMTP timeout.
+fn lease() -> Duration {
+    Duration::minutes(5)
+}
+
+/// Sends up to `limit` due emails and returns how many went out. Rows are
+/// claimed with a lease in a statement of their own, so several workers can
+/// share the outbox and no lock is held while a send is in progress. Each
+/// result is then recorded on its own, so a sent email stays sent.
+pub async fn deliver_due(pool: &PgPool, mailer: &dyn Mailer, limit: i64) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query!(
+        r#"
+        UPDATE email_outbox
+        SET locked_until = $2
+        WHERE id IN (
+            SELECT id
+            FROM email_outbox
+            WHERE status = 'pending'
+            AND next_attempt_at <= now() AT TIME ZONE 'utc'
+            AND (locked_until IS NULL OR locked_until <= now() AT TIME ZONE 'utc')
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, recipient, subject, body, attempts
+        "#,
+        limit,
+        Utc::now().naive_utc() + lease()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut sent = 0;
+
+    for row in due {
+        let email = Email {
+            id: row.id,
+            to: row.recipient,
+            subject: row.subject,
+            body: row.body,
+        };
+
+        let attempted_at = Utc::now().naive_utc();
+
+        match mailer.send(&email).await {
+            Ok(()) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE email_outbox
+                    SET status = 'sent', attempts = attempts + 1, sent_at = now() AT TIME ZONE 'utc',
+                        last_attempt_at = $2, last_error = NULL, locked_until = NULL
+                    WHERE id = $1
+                    "#,
+                    row.id,
+                    attempted_at
+                )
+                .execute(pool)
+                .await?;
+
+                sent += 1;
+            }
+            Err(e) => {
+                let attempts = row.attempts + 1;
+                let status = if attempts >= max_attempts() { "failed" } else { "pending" };
+
+                sqlx::query!(
+                    r#"
+                    UPDATE email_outbox
+                    SET status = $2, attempts = $3, next_attempt_at = $4, last_attempt_at = $5,
+                        last_error = $6, locked_until = NULL
+                    WHERE id = $1
+                    "#,
+                    row.id,
+                    status,
+                    attempts,
+                    attempted_at + backoff(attempts),
+                    attempted_at,
+                    e.to_string()
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
@@ -0,0 +1,139 @@
+use axum::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{env, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use super::{is_valid_address, mail_from, Email, MailError, Mailer};
+
+/// Sends through an SMTP relay over plain TCP, authenticating with AUTH
+/// PLAIN when credentials are set. There is no TLS, so point it at a relay
+/// on the same host or private network.
+pub struct SmtpMailer {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+    from: String,
+}
+
+impl SmtpMailer {
+    pub fn from_env() -> Self {
+        let username = env::var("SMTP_USERNAME").ok();
+        let password = env::var("SMTP_PASSWORD").ok();
+
+        Self {
+            host: env::var("SMTP_HOST").expect("SMTP_HOST must be set"),
+            port: env::var("SMTP_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(25),
+            credentials: username.zip(password),
+            from: mail_from(),
+        }
+    }
+
+    async fn deliver(&self, email: &Email) -> Result<(), MailError> {
+        // Checked before connecting, as both go into SMTP commands.
+        let message = email.to_message(&self.from)?;
+        let from = address(&self.from);
+
+        if !is_valid_address(from) {
+            return Err(MailError::InvalidAddress(from.to_string()));
+        }
+
+        let stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        let mut conn = Connection {
+            stream: BufReader::new(stream),
+        };
+
+        conn.expect(&[220]).await?;
+        conn.command("EHLO localhost", &[250]).await?;
+
+        if let Some((username, password)) = &self.credentials {
+            let token = STANDARD.encode(format!("\0{username}\0{password}"));
+            conn.command(&format!("AUTH PLAIN {token}"), &[235]).await?;
+        }
+
+        conn.command(&format!("MAIL FROM:<{from}>"), &[250]).await?;
+        conn.command(&format!("RCPT TO:<{}>", email.to), &[250, 251]).await?;
+        conn.command("DATA", &[354]).await?;
+
+        let mut data = String::new();
+
+        // Dot-stuffing (RFC 5321 section 4.5.2).
+        for line in message.split("\r\n") {
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+
+        data.push_str(".\r\n");
+        conn.stream.write_all(data.as_bytes()).await?;
+        conn.expect(&[250]).await?;
+
+        // The message is accepted; a failed goodbye does not matter.
+        let _ = conn.command("QUIT", &[221]).await;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        tokio::time::timeout(Duration::from_secs(30), self.deliver(email))
+            .await
+            .map_err(|_| MailError::Timeout)?
+    }
+}
+
+/// The bare address out of `Name <address>`.
+fn address(from: &str) -> &str {
+    match (from.find('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from.trim(),
+    }
+}
+
+struct Connection {
+    stream: BufReader<TcpStream>,
+}
+
+impl Connection {
+    async fn command(&mut self, line: &str, accepted: &[u16]) -> Result<(), MailError> {
+        if line.contains(['\r', '\n']) {
+            return Err(MailError::LineBreak);
+        }
+
+        self.stream.write_all(format!("{line}\r\n").as_bytes()).await?;
+        self.expect(accepted).await
+    }
+
+    /// Reads a possibly multi-line reply and checks its code.
+    async fn expect(&mut self, accepted: &[u16]) -> Result<(), MailError> {
+        loop {
+            let mut line = String::new();
+
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(MailError::Rejected(0, "connection closed".to_string()));
+            }
+
+            let code = line.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+
+            // "250-" continues the reply, "250 " ends it.
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+
+            if accepted.contains(&code) {
+                return Ok(());
+            }
+
+            return Err(MailError::Rejected(code, line.trim_end().to_string()));
+        }
+    }
+}
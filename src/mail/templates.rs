@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+use sqlx::types::BigDecimal;
+use uuid::Uuid;
+
+/// The emails sent about a booking. Each is sent at most once per booking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Template {
+    BookingConfirmed,
+    BookingCancelled,
+    PreArrivalReminder,
+    ReviewInvitation,
+}
+
+impl Template {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BookingConfirmed => "booking_confirmed",
+            Self::BookingCancelled => "booking_cancelled",
+            Self::PreArrivalReminder => "pre_arrival_reminder",
+            Self::ReviewInvitation => "review_invitation",
+        }
+    }
+}
+
+/// What the templates know about a booking, read in the transaction that
+/// queues the email.
+pub struct BookingDetails {
+    pub booking_id: Uuid,
+    pub guest_name: String,
+    pub hotel_name: String,
+    pub city: String,
+    pub country: String,
+    pub room_number: String,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    pub guests: i32,
+    pub total: BigDecimal,
+    pub currency: String,
+    /// Set when the hotel, not the guest, cancelled.
+    pub hotel_cancellation_reason: Option<String>,
+    pub refunded: BigDecimal,
+}
+
+/// Subject and plain-text body.
+pub fn render(template: Template, b: &BookingDetails) -> (String, String) {
+    let stay = format!(
+        "Hotel: {}, {}, {}\nRoom: {}\nCheck-in: {}\nCheck-out: {}\nGuests: {}\nBooking reference: {}",
+        b.hotel_name,
+        b.city,
+        b.country,
+        b.room_number,
+        b.check_in.format("%A %-d %B %Y"),
+        b.check_out.format("%A %-d %B %Y"),
+        b.guests,
+        b.booking_id
+    );
+
+    match template {
+        Template::BookingConfirmed => (
+            format!("Your booking at {} is confirmed", b.hotel_name),
+            format!(
+                "Hi {},\n\nThanks for booking with us. Your stay is confirmed.\n\n{}\nTotal paid: {} {}\n\nWe look forward to welcoming you.\n",
+                b.guest_name, stay, b.total, b.currency
+            ),
+        ),
+        Template::BookingCancelled => {
+            let opening = match &b.hotel_cancellation_reason {
+                Some(reason) => format!("Unfortunately {} had to cancel your booking: {}.", b.hotel_name, reason),
+                None => "Your booking has been cancelled as requested.".to_string(),
+            };
+
+            (
+                format!("Your booking at {} has been cancelled", b.hotel_name),
+                format!(
+                    "Hi {},\n\n{}\n\n{}\nRefunded: {} {}\n\nRefunds can take a few days to reach your account.\n",
+                    b.guest_name, opening, stay, b.refunded, b.currency
+                ),
+            )
+        }
+        Template::PreArrivalReminder => (
+            format!("See you soon at {}", b.hotel_name),
+            format!(
+                "Hi {},\n\nA reminder that your stay is coming up.\n\n{}\n\nSafe travels.\n",
+                b.guest_name, stay
+            ),
+        ),
+        Template::ReviewInvitation => (
+            format!("How was your stay at {}?", b.hotel_name),
+            format!(
+                "Hi {},\n\nThanks for staying at {}. We would love to hear how it went; you can leave a review from your bookings.\n\n{}\n",
+                b.guest_name, b.hotel_name, stay
+            ),
+        ),
+    }
+}
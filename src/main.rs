@@ -2,10 +2,23 @@ use axum::{Router, routing::get};
 use tokio::net::TcpListener;
 use std::env;
 
+mod availability;
 mod db;
+mod events;
 mod handlers;
+mod ical;
+mod invoices;
+mod jobs;
+mod lifecycle;
+mod mail;
 mod models;
+mod outbound;
+mod payments;
+mod pricing;
 mod routes;
+mod state;
+mod storage;
+mod webhooks;
 
 
 #[tokio::main]
@@ -17,9 +30,20 @@ async fn main() {
 
     let pool = db::create_pool().await;
 
+    let gateway = payments::create_gateway();
+
+    jobs::spawn_all(pool.clone(), mail::create_mailer(), gateway.clone());
+
+    let state = state::AppState {
+        events: events::listen(pool.clone()),
+        pool,
+        storage: storage::create_storage(),
+        payments: gateway,
+    };
+
     let app = Router::new()
         .route("/ping", get(|| async { "pong" }))
-        .merge(crate::routes::create_routes(pool));
+        .merge(crate::routes::create_routes(state));
 
     let listener = TcpListener::bind(addr)
         .await
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateRoomBlockRequest {
+    pub startDate: Option<String>,
+    pub endDate: Option<String>,
+    pub reason: Option<String>,
+    pub force: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct RoomBlockResponse {
+    pub id: String,
+    pub roomId: String,
+    pub startDate: String,
+    pub endDate: String,
+    pub reason: String,
+    pub createdAt: String,
+    pub affectedBookings: Vec<AffectedBookingResponse>,
+}
+
+/// A confirmed booking that overlaps a block.
+#[derive(Serialize)]
+pub struct AffectedBookingResponse {
+    pub id: String,
+    pub userId: String,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+}
+
+#[derive(Serialize)]
+pub struct DeleteRoomBlockResponse {
+    pub id: String,
+}
@@ -1,11 +1,44 @@
 use serde::{Deserialize, Serialize};
 
+use super::{cancellation_policies::CancellationTermsResponse, payments::PaymentResponse};
+
 #[derive(Deserialize)]
 pub struct CreateBookingRequest {
     pub roomId: String,
     pub checkInDate: String,
     pub checkOutDate: String,
     pub guests: i32,
+    pub paymentMethod: Option<String>,
+    pub guestDetails: Option<GuestDetailsRequest>,
+    pub promoCode: Option<String>,
+    /// Points to spend as a discount on the stay.
+    pub loyaltyPoints: Option<i32>,
+}
+
+/// Who is actually staying. `adults` defaults to `guests` minus the
+/// children; when given, the two must add up to `guests`.
+#[derive(Clone, Deserialize)]
+pub struct GuestDetailsRequest {
+    pub leadGuestName: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub adults: Option<i32>,
+    #[serde(default)]
+    pub childrenAges: Vec<i32>,
+    /// Local time at the hotel, `HH:MM`.
+    pub estimatedArrival: Option<String>,
+    pub specialRequests: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GuestDetailsResponse {
+    pub leadGuestName: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub adults: i32,
+    pub childrenAges: Vec<i32>,
+    pub estimatedArrival: Option<String>,
+    pub specialRequests: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -17,19 +50,64 @@ pub struct BookingResponse {
     pub checkInDate: String,
     pub checkOutDate: String,
     pub guests: i32,
+    pub subtotal: String,
+    pub taxes: Vec<LineItemResponse>,
+    pub fees: Vec<LineItemResponse>,
+    pub discounts: Vec<LineItemResponse>,
     pub totalPrice: String,
+    pub currency: String,
     pub status: String,
+    pub holdExpiresAt: Option<String>,
     pub bookingDate: String,
+    pub nights: Vec<NightlyRateResponse>,
+    pub payment: Option<PaymentResponse>,
+    pub cancellationPolicy: CancellationTermsResponse,
+    pub guestDetails: Option<GuestDetailsResponse>,
+}
+
+#[derive(Serialize)]
+pub struct NightlyRateResponse {
+    pub date: String,
+    pub price: String,
+}
+
+#[derive(Serialize)]
+pub struct LineItemResponse {
+    pub name: String,
+    pub amount: String,
+    pub inclusive: bool,
+}
+
+#[derive(Serialize)]
+pub struct QuoteResponse {
+    pub roomId: String,
+    pub hotelId: String,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+    pub available: bool,
+    pub restrictions: Vec<String>,
+    pub nights: Vec<NightlyRateResponse>,
+    pub subtotal: String,
+    pub taxes: Vec<LineItemResponse>,
+    pub fees: Vec<LineItemResponse>,
+    pub discounts: Vec<LineItemResponse>,
+    pub totalPrice: String,
+    pub currency: String,
+    pub displayCurrency: String,
+    pub cancellationPolicy: CancellationTermsResponse,
 }
 
 #[derive(Deserialize)]
 pub struct BookingListQuery {
     pub status: Option<String>,
+    pub currency: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct BookingListResponse {
     pub id: String,
+    pub reservationId: Option<String>,
     pub roomId: String,
     pub hotelId: String,
     pub hotelName: String,
@@ -38,9 +116,76 @@ pub struct BookingListResponse {
     pub checkInDate: String,
     pub checkOutDate: String,
     pub guests: i32,
+    pub subtotal: String,
+    pub taxes: Vec<LineItemResponse>,
+    pub fees: Vec<LineItemResponse>,
+    pub discounts: Vec<LineItemResponse>,
     pub totalPrice: String,
+    pub currency: String,
+    pub displayCurrency: String,
     pub status: String,
+    pub holdExpiresAt: Option<String>,
     pub bookingDate: String,
+    pub cancelledAt: Option<String>,
+    /// `customer`, or `hotel` when the hotel cancelled the booking.
+    pub cancelledBy: Option<String>,
+    pub cancellationReason: Option<String>,
+    pub checkedInAt: Option<String>,
+    pub checkedOutAt: Option<String>,
+    pub guestDetails: Option<GuestDetailsResponse>,
+}
+
+/// Fields left out keep their current value.
+#[derive(Deserialize)]
+pub struct ModifyBookingRequest {
+    pub roomId: Option<String>,
+    pub checkInDate: Option<String>,
+    pub checkOutDate: Option<String>,
+    pub guests: Option<i32>,
+    /// Replaces the stored details as a whole.
+    pub guestDetails: Option<GuestDetailsRequest>,
+}
+
+#[derive(Serialize)]
+pub struct ModifyBookingResponse {
+    pub id: String,
+    pub roomId: String,
+    pub hotelId: String,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+    pub subtotal: String,
+    pub taxes: Vec<LineItemResponse>,
+    pub fees: Vec<LineItemResponse>,
+    pub discounts: Vec<LineItemResponse>,
+    pub totalPrice: String,
+    pub previousTotalPrice: String,
+    pub priceDifference: String,
+    pub currency: String,
+    pub status: String,
+    pub modifiedAt: String,
+    pub nights: Vec<NightlyRateResponse>,
+    pub guestDetails: Option<GuestDetailsResponse>,
+    /// Loyalty points given back because the new price could not use them.
+    pub pointsReturned: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmBookingRequest {
+    pub paymentMethod: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ConfirmBookingResponse {
+    pub id: String,
+    pub status: String,
+    pub holdExpiresAt: Option<String>,
+    pub payment: PaymentResponse,
+}
+
+#[derive(Deserialize)]
+pub struct CancelHotelBookingRequest {
+    pub reason: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -48,4 +193,61 @@ pub struct CancelBookingResponse {
     pub id: String,
     pub status: String,
     pub cancelledAt: String,
-}
\ No newline at end of file
+    pub cancellationPolicy: String,
+    pub refundAmount: String,
+    pub penaltyAmount: String,
+    pub currency: String,
+}
+
+#[derive(Deserialize)]
+pub struct HotelBookingListQuery {
+    pub status: Option<String>,
+    /// Only stays arriving on this date.
+    pub checkInDate: Option<String>,
+}
+
+/// A booking as the hotel sees it.
+#[derive(Serialize)]
+pub struct HotelBookingResponse {
+    pub id: String,
+    pub roomId: String,
+    pub roomNumber: String,
+    pub customerName: String,
+    pub customerEmail: String,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+    pub totalPrice: String,
+    pub currency: String,
+    pub status: String,
+    pub bookingDate: String,
+    pub checkedInAt: Option<String>,
+    pub checkedOutAt: Option<String>,
+    pub guestDetails: Option<GuestDetailsResponse>,
+}
+
+/// An email queued about a booking, as the hotel's front desk sees it.
+#[derive(Serialize)]
+pub struct BookingEmailResponse {
+    pub id: String,
+    pub template: String,
+    pub recipient: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    pub lastAttemptAt: Option<String>,
+    /// Only while the email is still pending.
+    pub nextAttemptAt: Option<String>,
+    pub lastError: Option<String>,
+    pub createdAt: String,
+    pub sentAt: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BookingStatusResponse {
+    pub id: String,
+    pub status: String,
+    pub checkedInAt: Option<String>,
+    pub checkedOutAt: Option<String>,
+    pub noShowAt: Option<String>,
+}
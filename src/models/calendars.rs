@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct CalendarFeedResponse {
+    pub roomId: String,
+    /// Secret iCal URL other platforms can subscribe to; anyone holding it
+    /// can read the room's busy dates.
+    pub url: String,
+    pub createdAt: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateCalendarImportRequest {
+    pub name: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CalendarImportResponse {
+    pub id: String,
+    pub roomId: String,
+    pub name: String,
+    pub url: String,
+    pub lastSyncedAt: Option<String>,
+    pub lastError: Option<String>,
+    pub createdAt: String,
+}
+
+#[derive(Serialize)]
+pub struct CalendarSyncResponse {
+    pub importId: String,
+    /// Events now mirrored as room blocks.
+    pub events: usize,
+    /// Blocks removed because their event is no longer in the calendar.
+    pub removed: u64,
+    /// Bookings that overlap nights sold elsewhere.
+    pub conflictingBookings: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteCalendarImportResponse {
+    pub id: String,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// Either `nonRefundable`, or a free window with an optional late penalty
+/// (100% when left out).
+#[derive(Deserialize)]
+pub struct CreateCancellationPolicyRequest {
+    pub name: Option<String>,
+    pub freeCancellationDays: Option<i32>,
+    pub penaltyPercent: Option<String>,
+    pub nonRefundable: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct CancellationPolicyResponse {
+    pub id: String,
+    pub hotelId: String,
+    pub name: String,
+    pub freeCancellationDays: Option<i32>,
+    pub penaltyPercent: String,
+    pub nonRefundable: bool,
+}
+
+/// The terms a booking is made under. `penaltyPercent` is empty when late
+/// cancellation is not allowed at all.
+#[derive(Serialize)]
+pub struct CancellationTermsResponse {
+    pub name: String,
+    pub freeCancellationDays: Option<i32>,
+    pub penaltyPercent: Option<String>,
+    pub nonRefundable: bool,
+}
+
+#[derive(Serialize)]
+pub struct DeleteCancellationPolicyResponse {
+    pub id: String,
+}
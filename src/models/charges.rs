@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateChargeRequest {
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub calculation: Option<String>,
+    pub amount: Option<String>,
+    pub basis: Option<String>,
+    pub inclusive: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ChargeResponse {
+    pub id: String,
+    pub hotelId: String,
+    pub name: String,
+    pub category: String,
+    pub calculation: String,
+    pub amount: String,
+    pub basis: String,
+    pub inclusive: bool,
+}
+
+#[derive(Serialize)]
+pub struct DeleteChargeResponse {
+    pub id: String,
+}
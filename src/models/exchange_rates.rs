@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Deserialize)]
+pub struct UploadExchangeRatesRequest {
+    pub baseCurrency: Option<String>,
+    pub rates: Option<HashMap<String, String>>,
+}
+
+#[derive(Serialize)]
+pub struct ExchangeRatesResponse {
+    pub baseCurrency: Option<String>,
+    pub rates: BTreeMap<String, String>,
+    pub updatedAt: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CurrencyQuery {
+    pub currency: Option<String>,
+}
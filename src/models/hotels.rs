@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::photos::PhotoResponse;
+
 #[derive(Deserialize)]
 pub struct CreateHotelRequest {
     pub name: Option<String>,
@@ -7,6 +9,7 @@ pub struct CreateHotelRequest {
     pub city: Option<String>,
     pub country: Option<String>,
     pub amenities: Option<Vec<String>>,
+    pub currency: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -18,6 +21,7 @@ pub struct HotelResponse {
     pub city: String,
     pub country: String,
     pub amenities: Vec<String>,
+    pub currency: String,
     pub rating: f64,
     pub totalReviews: i32,
 }
@@ -29,6 +33,9 @@ pub struct HotelSearchQuery {
     pub minPrice: Option<String>,
     pub maxPrice: Option<String>,
     pub minRating: Option<f64>,
+    pub currency: Option<String>,
+    pub checkInDate: Option<String>,
+    pub checkOutDate: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -41,7 +48,10 @@ pub struct HotelListResponse {
     pub amenities: Vec<String>,
     pub rating: f64,
     pub totalReviews: i32,
+    pub currency: String,
+    pub displayCurrency: String,
     pub minPricePerNight: String,
+    pub coverPhoto: Option<PhotoResponse>,
 }
 
 #[derive(Serialize)]
@@ -55,7 +65,10 @@ pub struct HotelDetailResponse {
     pub amenities: Vec<String>,
     pub rating: f64,
     pub totalReviews: i32,
+    pub currency: String,
+    pub displayCurrency: String,
     pub rooms: Vec<HotelRoomResponse>,
+    pub photos: Vec<PhotoResponse>,
 }
 
 #[derive(Serialize)]
@@ -65,4 +78,7 @@ pub struct HotelRoomResponse {
     pub roomType: String,
     pub pricePerNight: String,
     pub maxOccupancy: i32,
+    pub maxAdults: Option<i32>,
+    pub maxChildren: Option<i32>,
+    pub photos: Vec<PhotoResponse>,
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct InvoiceQuery {
+    /// `json` (default) or `html` for a printable document.
+    pub format: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct InvoiceResponse {
+    #[serde(flatten)]
+    pub invoice: InvoiceDocumentResponse,
+    pub creditNotes: Vec<InvoiceDocumentResponse>,
+}
+
+#[derive(Serialize)]
+pub struct InvoiceDocumentResponse {
+    pub id: String,
+    pub bookingId: String,
+    /// `invoice` or `credit_note`.
+    pub kind: String,
+    pub number: String,
+    /// For a credit note, the number of the invoice it corrects.
+    pub correctsInvoice: Option<String>,
+    pub status: String,
+    pub reason: Option<String>,
+    pub issuedAt: String,
+    pub seller: InvoicePartyResponse,
+    pub customer: InvoicePartyResponse,
+    pub leadGuestName: Option<String>,
+    pub roomNumber: String,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+    pub lines: Vec<InvoiceLineResponse>,
+    pub total: String,
+    pub currency: String,
+}
+
+#[derive(Serialize)]
+pub struct InvoicePartyResponse {
+    pub name: String,
+    pub address: Option<String>,
+    pub taxId: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct InvoiceLineResponse {
+    pub kind: String,
+    pub description: String,
+    pub amount: String,
+    pub inclusive: bool,
+}
+
+#[derive(Deserialize)]
+pub struct BillingDetailsRequest {
+    pub legalName: Option<String>,
+    pub address: Option<String>,
+    pub taxId: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BillingDetailsResponse {
+    pub hotelId: String,
+    pub legalName: String,
+    pub address: Option<String>,
+    pub taxId: Option<String>,
+    pub email: Option<String>,
+    pub updatedAt: String,
+}
@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct LoyaltyResponse {
+    pub balance: i64,
+    /// Currency points are earned and redeemed in.
+    pub currency: String,
+    /// Points that take one unit of `currency` off a booking.
+    pub pointsPerUnit: i64,
+    pub tier: String,
+    pub completedStays: i64,
+    pub nextTier: Option<String>,
+    pub staysToNextTier: Option<i64>,
+    pub transactions: Vec<LoyaltyTransactionResponse>,
+}
+
+#[derive(Serialize)]
+pub struct LoyaltyTransactionResponse {
+    pub id: String,
+    pub bookingId: Option<String>,
+    /// `earned`, `redeemed` or `reversed`.
+    pub kind: String,
+    pub points: i32,
+    pub createdAt: String,
+}
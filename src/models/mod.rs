@@ -1,6 +1,24 @@
+#![allow(non_snake_case)]
+
 pub mod auth;
 pub mod response;
 pub mod hotels;
 pub mod rooms;
 pub mod bookings;
-pub mod reviews;
\ No newline at end of file
+pub mod reviews;
+pub mod photos;
+pub mod rates;
+pub mod charges;
+pub mod exchange_rates;
+pub mod restrictions;
+pub mod blocks;
+pub mod reservations;
+pub mod payments;
+pub mod cancellation_policies;
+pub mod notifications;
+pub mod waitlist;
+pub mod promo_codes;
+pub mod loyalty;
+pub mod invoices;
+pub mod calendars;
+pub mod webhooks;
\ No newline at end of file
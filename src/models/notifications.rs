@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct NotificationResponse {
+    pub id: String,
+    pub r#type: String,
+    pub bookingId: Option<String>,
+    pub message: String,
+    pub createdAt: String,
+    pub readAt: Option<String>,
+}
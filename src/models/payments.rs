@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct PaymentResponse {
+    pub id: String,
+    pub provider: String,
+    pub reference: String,
+    pub status: String,
+    pub amount: String,
+    pub currency: String,
+    pub clientSecret: String,
+}
+
+#[derive(Serialize)]
+pub struct PaymentWebhookResponse {
+    pub paymentId: String,
+    pub status: String,
+    pub bookingStatus: String,
+    /// Given back when the capture came too late for its booking.
+    pub refundAmount: Option<String>,
+}
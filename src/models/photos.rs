@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct PhotoResponse {
+    pub id: String,
+    pub hotelId: String,
+    pub roomId: Option<String>,
+    pub url: String,
+    pub thumbnailUrl: String,
+    pub contentType: String,
+    pub width: i32,
+    pub height: i32,
+    pub position: i32,
+    pub isCover: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ReorderPhotosRequest {
+    pub photoIds: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct DeletePhotoResponse {
+    pub id: String,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Without `hotelId` the code is platform-wide, which only admins can
+/// create. Fixed discounts are in `currency`, the hotel's by default.
+#[derive(Deserialize)]
+pub struct CreatePromoCodeRequest {
+    pub code: Option<String>,
+    pub hotelId: Option<String>,
+    /// `percent` or `fixed`.
+    pub discountType: Option<String>,
+    pub amount: Option<String>,
+    pub currency: Option<String>,
+    pub minNights: Option<i32>,
+    pub validFrom: Option<String>,
+    pub validUntil: Option<String>,
+    pub maxUses: Option<i32>,
+    pub maxUsesPerUser: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct PromoCodeListQuery {
+    pub hotelId: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PromoCodeResponse {
+    pub id: String,
+    pub code: String,
+    pub hotelId: Option<String>,
+    pub discountType: String,
+    pub amount: String,
+    pub currency: Option<String>,
+    pub minNights: i32,
+    pub validFrom: Option<String>,
+    pub validUntil: Option<String>,
+    pub maxUses: Option<i32>,
+    pub maxUsesPerUser: Option<i32>,
+    pub uses: i32,
+    pub active: bool,
+    pub createdAt: String,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateRateRequest {
+    pub r#type: Option<String>,
+    pub startDate: Option<String>,
+    pub endDate: Option<String>,
+    pub daysOfWeek: Option<Vec<i32>>,
+    pub pricePerNight: Option<String>,
+    pub cancellationPolicyId: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RateResponse {
+    pub id: String,
+    pub roomId: String,
+    pub r#type: String,
+    pub startDate: Option<String>,
+    pub endDate: Option<String>,
+    pub daysOfWeek: Option<Vec<i32>>,
+    pub pricePerNight: String,
+    pub cancellationPolicyId: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteRateResponse {
+    pub id: String,
+}
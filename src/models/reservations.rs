@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use super::bookings::GuestDetailsRequest;
+
+#[derive(Deserialize)]
+pub struct CreateReservationRequest {
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub rooms: Vec<ReservationRoomRequest>,
+    pub paymentMethod: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReservationRoomRequest {
+    pub roomId: String,
+    pub guests: i32,
+    pub guestDetails: Option<GuestDetailsRequest>,
+}
+
+/// Without `bookingIds` every room still booked is cancelled.
+#[derive(Deserialize)]
+pub struct CancelReservationRequest {
+    pub bookingIds: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct ReservationResponse {
+    pub id: String,
+    pub hotelId: String,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub status: String,
+    pub subtotal: String,
+    pub totalPrice: String,
+    pub refundedAmount: String,
+    pub currency: String,
+    pub bookings: Vec<ReservationBookingResponse>,
+    pub createdAt: String,
+}
+
+#[derive(Serialize)]
+pub struct ReservationBookingResponse {
+    pub id: String,
+    pub roomId: String,
+    pub roomNumber: String,
+    pub roomType: String,
+    pub guests: i32,
+    pub subtotal: String,
+    pub totalPrice: String,
+    pub status: String,
+}
@@ -23,4 +23,13 @@ impl<T> ApiResponse<T> {
             error: Some(code.to_string()),
         }
     }
-}
\ No newline at end of file
+
+    /// An error that still carries details the caller needs to act on.
+    pub fn error_with_data(code: &str, data: T) -> Self {
+        Self {
+            success: false,
+            data: Some(data),
+            error: Some(code.to_string()),
+        }
+    }
+}
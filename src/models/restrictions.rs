@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateRestrictionRequest {
+    pub roomId: Option<String>,
+    pub r#type: Option<String>,
+    pub value: Option<i32>,
+    pub startDate: Option<String>,
+    pub endDate: Option<String>,
+    pub daysOfWeek: Option<Vec<i32>>,
+}
+
+#[derive(Serialize)]
+pub struct RestrictionResponse {
+    pub id: String,
+    pub hotelId: String,
+    pub roomId: Option<String>,
+    pub r#type: String,
+    pub value: Option<i32>,
+    pub startDate: Option<String>,
+    pub endDate: Option<String>,
+    pub daysOfWeek: Option<Vec<i32>>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteRestrictionResponse {
+    pub id: String,
+}
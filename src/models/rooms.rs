@@ -6,6 +6,10 @@ pub struct CreateRoomRequest {
     pub roomType: Option<String>,
     pub pricePerNight: Option<String>,
     pub maxOccupancy: Option<i32>,
+    /// Optional limits within `maxOccupancy`.
+    pub maxAdults: Option<i32>,
+    pub maxChildren: Option<i32>,
+    pub cancellationPolicyId: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -16,4 +20,7 @@ pub struct RoomResponse {
     pub roomType: String,
     pub pricePerNight: String,
     pub maxOccupancy: i32,
+    pub maxAdults: Option<i32>,
+    pub maxChildren: Option<i32>,
+    pub cancellationPolicyId: Option<String>,
 }
\ No newline at end of file
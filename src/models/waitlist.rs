@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Either `roomId`, or `hotelId` with `roomType` to wait for any room of
+/// that type.
+#[derive(Deserialize)]
+pub struct CreateWaitlistRequest {
+    pub roomId: Option<String>,
+    pub hotelId: Option<String>,
+    pub roomType: Option<String>,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+}
+
+#[derive(Serialize)]
+pub struct WaitlistEntryResponse {
+    pub id: String,
+    pub hotelId: String,
+    pub roomId: Option<String>,
+    pub roomType: Option<String>,
+    pub checkInDate: String,
+    pub checkOutDate: String,
+    pub guests: i32,
+    pub status: String,
+    /// The held booking offered to the customer; confirm it before
+    /// `offerExpiresAt` to keep the room.
+    pub offeredBookingId: Option<String>,
+    pub offerExpiresAt: Option<String>,
+    pub createdAt: String,
+}
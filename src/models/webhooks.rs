@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    /// Only returned when the subscription is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub createdAt: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryListQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WebhookDeliveryResponse {
+    pub id: String,
+    pub subscriptionId: String,
+    pub eventId: String,
+    pub eventType: String,
+    pub status: String,
+    pub attempts: i32,
+    pub nextAttemptAt: Option<String>,
+    pub lastStatusCode: Option<i32>,
+    pub lastError: Option<String>,
+    pub payload: serde_json::Value,
+    pub createdAt: String,
+    pub deliveredAt: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteWebhookResponse {
+    pub id: String,
+}
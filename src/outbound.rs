@@ -0,0 +1,118 @@
+use reqwest::{redirect::Policy, Client, Url};
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use url::Host;
+
+/// Why the server will not make a request to an owner-supplied URL.
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundError {
+    #[error("only http and https URLs with a host are allowed")]
+    InvalidUrl,
+    #[error("could not resolve {0}")]
+    Unresolved(String),
+    #[error("{0} is not a public address")]
+    NotPublic(IpAddr),
+}
+
+/// Hosts exempt from the public address check, from the comma-separated
+/// `OUTBOUND_ALLOWED_HOSTS`. Only meant for local development and tests
+/// that stand in for remote endpoints.
+fn is_allowed_host(host: &str) -> bool {
+    env::var("OUTBOUND_ALLOWED_HOSTS")
+        .unwrap_or_default()
+        .split(',')
+        .any(|h| !h.trim().is_empty() && h.trim().eq_ignore_ascii_case(host))
+}
+
+/// Resolves the URL's host and checks every address it has is public.
+pub async fn resolve(url: &Url) -> Result<Vec<SocketAddr>, OutboundError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(OutboundError::InvalidUrl);
+    }
+
+    let port = url.port_or_known_default().ok_or(OutboundError::InvalidUrl)?;
+
+    let addrs: Vec<SocketAddr> = match url.host().ok_or(OutboundError::InvalidUrl)? {
+        Host::Ipv4(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Ipv6(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Domain(domain) => tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|_| OutboundError::Unresolved(domain.to_string()))?
+            .collect(),
+    };
+
+    if addrs.is_empty() {
+        return Err(OutboundError::Unresolved(url.host_str().unwrap_or_default().to_string()));
+    }
+
+    if !is_allowed_host(url.host_str().unwrap_or_default()) {
+        if let Some(addr) = addrs.iter().find(|a| !is_public(a.ip())) {
+            return Err(OutboundError::NotPublic(addr.ip()));
+        }
+    }
+
+    Ok(addrs)
+}
+
+/// A client for requests to `url`, pinned to the addresses `resolve`
+/// approved so a second lookup cannot send it elsewhere. Redirects are not
+/// followed, since their target was never checked.
+pub async fn client_for(url: &Url, timeout: Duration) -> Result<Client, OutboundError> {
+    let addrs = resolve(url).await?;
+
+    let mut builder = Client::builder().timeout(timeout).redirect(Policy::none());
+
+    if let Some(Host::Domain(domain)) = url.host() {
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+
+    Ok(builder.build().unwrap())
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments, 192.0.0.0/24.
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15.
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved, 240.0.0.0/4.
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, fc00::/7.
+        || (first & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10.
+        || (first & 0xffc0) == 0xfe80
+        // Documentation, 2001:db8::/32.
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
@@ -0,0 +1,102 @@
+use axum::async_trait;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::types::BigDecimal;
+use std::env;
+use uuid::Uuid;
+
+use super::{IntentStatus, PaymentError, PaymentGateway, PaymentIntent, PaymentOutcome, WebhookEvent};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// In-process provider for local development and tests. The payment method
+/// picks the outcome:
+///
+/// - `mock_card` (the default) is captured immediately
+/// - `mock_async` stays pending until a signed webhook reports the result
+/// - `mock_declined` is declined
+pub struct MockGateway {
+    webhook_secret: String,
+}
+
+impl MockGateway {
+    pub fn from_env() -> Self {
+        Self {
+            webhook_secret: env::var("PAYMENT_WEBHOOK_SECRET")
+                .unwrap_or_else(|_| "mock_webhook_secret".to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockWebhook {
+    r#type: String,
+    payment_id: String,
+}
+
+#[async_trait]
+impl PaymentGateway for MockGateway {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_intent(
+        &self,
+        _booking_id: Uuid,
+        _amount: &BigDecimal,
+        _currency: &str,
+        method: Option<&str>,
+    ) -> Result<PaymentIntent, PaymentError> {
+        let status = match method.unwrap_or("mock_card") {
+            "mock_card" => IntentStatus::Succeeded,
+            "mock_async" => IntentStatus::Pending,
+            "mock_declined" => IntentStatus::Declined,
+            other => return Err(PaymentError::UnknownMethod(other.to_string())),
+        };
+
+        let reference = format!("mock_pi_{}", Uuid::new_v4().simple());
+
+        Ok(PaymentIntent {
+            client_secret: format!("{reference}_secret"),
+            reference,
+            status,
+        })
+    }
+
+    async fn cancel(&self, _reference: &str) -> Result<(), PaymentError> {
+        Ok(())
+    }
+
+    async fn refund(&self, _reference: &str, _amount: &BigDecimal) -> Result<String, PaymentError> {
+        Ok(format!("mock_re_{}", Uuid::new_v4().simple()))
+    }
+
+    /// Expects `X-Payment-Signature` to be the hex HMAC-SHA256 of the body.
+    fn parse_webhook(&self, body: &[u8], signature: Option<&str>) -> Result<WebhookEvent, PaymentError> {
+        let signature = signature
+            .and_then(|s| hex::decode(s).ok())
+            .ok_or(PaymentError::InvalidSignature)?;
+
+        let mut mac = HmacSha256::new_from_slice(self.webhook_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| PaymentError::InvalidSignature)?;
+
+        let event: MockWebhook =
+            serde_json::from_slice(body).map_err(|_| PaymentError::MalformedPayload)?;
+
+        let outcome = match event.r#type.as_str() {
+            "payment.succeeded" => PaymentOutcome::Succeeded,
+            "payment.failed" => PaymentOutcome::Failed,
+            _ => return Err(PaymentError::MalformedPayload),
+        };
+
+        Ok(WebhookEvent {
+            reference: event.payment_id,
+            outcome,
+        })
+    }
+}
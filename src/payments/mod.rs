@@ -0,0 +1,81 @@
+use axum::async_trait;
+use sqlx::types::BigDecimal;
+use std::{env, sync::Arc};
+use uuid::Uuid;
+
+pub mod mock;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentError {
+    #[error("unknown payment method: {0}")]
+    UnknownMethod(String),
+    #[error("webhook signature does not match")]
+    InvalidSignature,
+    #[error("malformed webhook payload")]
+    MalformedPayload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntentStatus {
+    /// Captured straight away.
+    Succeeded,
+    /// The result arrives later through the webhook.
+    Pending,
+    Declined,
+}
+
+pub struct PaymentIntent {
+    /// The provider's id for the intent, echoed back in webhooks.
+    pub reference: String,
+    /// Handed to the client so it can complete the payment with the provider.
+    pub client_secret: String,
+    pub status: IntentStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentOutcome {
+    Succeeded,
+    Failed,
+}
+
+pub struct WebhookEvent {
+    pub reference: String,
+    pub outcome: PaymentOutcome,
+}
+
+/// A payment provider. Intents are created before the booking is written,
+/// outside its transaction; capture results may come back later through
+/// `parse_webhook`.
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    /// Stored with each payment so webhooks are matched to the right provider.
+    fn name(&self) -> &'static str;
+
+    async fn create_intent(
+        &self,
+        booking_id: Uuid,
+        amount: &BigDecimal,
+        currency: &str,
+        method: Option<&str>,
+    ) -> Result<PaymentIntent, PaymentError>;
+
+    /// Voids an intent that was never captured, so the provider will not
+    /// capture it later. Voiding one that is already void is not an error.
+    async fn cancel(&self, reference: &str) -> Result<(), PaymentError>;
+
+    /// Returns part or all of a captured payment. Gives the provider's id for
+    /// the refund.
+    async fn refund(&self, reference: &str, amount: &BigDecimal) -> Result<String, PaymentError>;
+
+    /// Verifies the signature over the raw request body before parsing it.
+    fn parse_webhook(&self, body: &[u8], signature: Option<&str>) -> Result<WebhookEvent, PaymentError>;
+}
+
+pub fn create_gateway() -> Arc<dyn PaymentGateway> {
+    let backend = env::var("PAYMENT_GATEWAY").unwrap_or_else(|_| "mock".to_string());
+
+    match backend.as_str() {
+        "mock" => Arc::new(mock::MockGateway::from_env()),
+        other => panic!("Unknown PAYMENT_GATEWAY: {other}"),
+    }
+}
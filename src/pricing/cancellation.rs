@@ -0,0 +1,106 @@
+use chrono::NaiveDate;
+use sqlx::{types::BigDecimal, PgConnection};
+use uuid::Uuid;
+
+/// Terms a booking can be cancelled under, as snapshotted when it was made.
+pub struct CancellationPolicy {
+    pub name: String,
+    /// Cancelling at least this many days before check-in refunds everything.
+    /// `None` means there is no free window.
+    pub free_cancellation_days: Option<i32>,
+    /// Share of the amount paid that is kept when cancelling later than the
+    /// free window. `None` means late cancellation is not allowed at all.
+    pub penalty_percent: Option<BigDecimal>,
+}
+
+pub struct Refund {
+    pub amount: BigDecimal,
+    pub penalty: BigDecimal,
+}
+
+impl CancellationPolicy {
+    /// Applies to bookings made without a policy: free until the day before
+    /// check-in, not cancellable after that.
+    pub fn standard() -> Self {
+        Self {
+            name: "standard".to_string(),
+            free_cancellation_days: Some(1),
+            penalty_percent: None,
+        }
+    }
+
+    pub fn is_non_refundable(&self) -> bool {
+        self.free_cancellation_days.is_none()
+            && self.penalty_percent.as_ref().is_some_and(|p| *p == BigDecimal::from(100))
+    }
+
+    /// Whether a booking under these terms can still be changed on `today`.
+    /// Changes close with the free window, so a change cannot be used to get
+    /// money back the policy would keep.
+    pub fn allows_changes(&self, check_in: NaiveDate, today: NaiveDate) -> bool {
+        self.free_cancellation_days
+            .is_some_and(|d| (check_in - today).num_days() >= d as i64)
+    }
+
+    /// What cancelling on `today` gives back out of `paid`, or `None` if the
+    /// policy no longer allows cancelling.
+    pub fn refund_for(&self, paid: &BigDecimal, check_in: NaiveDate, today: NaiveDate) -> Option<Refund> {
+        let days_before = (check_in - today).num_days();
+
+        if self.free_cancellation_days.is_some_and(|d| days_before >= d as i64) {
+            return Some(Refund {
+                amount: paid.clone(),
+                penalty: BigDecimal::from(0),
+            });
+        }
+
+        let percent = self.penalty_percent.as_ref()?;
+        let penalty = (paid * percent / BigDecimal::from(100)).round(2);
+
+        Some(Refund {
+            amount: paid - &penalty,
+            penalty,
+        })
+    }
+}
+
+pub async fn load_policy(conn: &mut PgConnection, policy_id: Uuid) -> Option<CancellationPolicy> {
+    sqlx::query!(
+        r#"
+        SELECT name, free_cancellation_days, penalty_percent
+        FROM cancellation_policies
+        WHERE id = $1
+        "#,
+        policy_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()
+    .map(|p| CancellationPolicy {
+        name: p.name,
+        free_cancellation_days: p.free_cancellation_days,
+        penalty_percent: Some(p.penalty_percent),
+    })
+}
+
+/// The policy a booking was made under, or the standard terms if it was
+/// made without one.
+pub async fn load_booking_policy(conn: &mut PgConnection, booking_id: Uuid) -> CancellationPolicy {
+    sqlx::query!(
+        r#"
+        SELECT name, free_cancellation_days, penalty_percent
+        FROM booking_cancellation_policies
+        WHERE booking_id = $1
+        "#,
+        booking_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()
+    .map(|p| CancellationPolicy {
+        name: p.name,
+        free_cancellation_days: p.free_cancellation_days,
+        penalty_percent: Some(p.penalty_percent),
+    })
+    .unwrap_or_else(CancellationPolicy::standard)
+}
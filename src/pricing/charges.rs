@@ -0,0 +1,163 @@
+use sqlx::{types::BigDecimal, PgConnection};
+use uuid::Uuid;
+
+use super::LineItem;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChargeCategory {
+    Tax,
+    Fee,
+}
+
+impl ChargeCategory {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tax" => Some(Self::Tax),
+            "fee" => Some(Self::Fee),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tax => "tax",
+            Self::Fee => "fee",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Calculation {
+    Percentage,
+    Fixed,
+}
+
+impl Calculation {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "percentage" => Some(Self::Percentage),
+            "fixed" => Some(Self::Fixed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Percentage => "percentage",
+            Self::Fixed => "fixed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChargeBasis {
+    Night,
+    Stay,
+    Guest,
+}
+
+impl ChargeBasis {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "per_night" => Some(Self::Night),
+            "per_stay" => Some(Self::Stay),
+            "per_guest" => Some(Self::Guest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Night => "per_night",
+            Self::Stay => "per_stay",
+            Self::Guest => "per_guest",
+        }
+    }
+}
+
+pub struct ChargeRule {
+    pub name: String,
+    pub category: ChargeCategory,
+    pub calculation: Calculation,
+    pub amount: BigDecimal,
+    pub basis: ChargeBasis,
+    pub inclusive: bool,
+}
+
+impl ChargeRule {
+    /// Amount this rule contributes to a stay. Percentages apply to the room
+    /// subtotal; an inclusive percentage is the share of the subtotal that
+    /// already is the tax, e.g. 18% inclusive of 118 is 18.
+    pub fn amount_for(&self, subtotal: &BigDecimal, nights: i64, guests: i32) -> BigDecimal {
+        let hundred = BigDecimal::from(100);
+
+        let amount = match self.calculation {
+            Calculation::Percentage if self.inclusive => {
+                subtotal * &self.amount / (&hundred + &self.amount)
+            }
+            Calculation::Percentage => subtotal * &self.amount / hundred,
+            Calculation::Fixed => {
+                let units = match self.basis {
+                    ChargeBasis::Night => nights,
+                    ChargeBasis::Stay => 1,
+                    ChargeBasis::Guest => guests as i64,
+                };
+                &self.amount * BigDecimal::from(units)
+            }
+        };
+
+        amount.round(2)
+    }
+}
+
+pub struct AppliedCharge {
+    pub category: ChargeCategory,
+    pub item: LineItem,
+}
+
+pub fn apply_charges(
+    rules: &[ChargeRule],
+    subtotal: &BigDecimal,
+    nights: i64,
+    guests: i32,
+) -> Vec<AppliedCharge> {
+    rules
+        .iter()
+        .map(|r| AppliedCharge {
+            category: r.category,
+            item: LineItem {
+                name: r.name.clone(),
+                amount: r.amount_for(subtotal, nights, guests),
+                inclusive: r.inclusive,
+            },
+        })
+        .collect()
+}
+
+/// Tax and fee rules configured on a hotel, in the order they were added.
+pub async fn load_charge_rules(conn: &mut PgConnection, hotel_id: Uuid) -> Vec<ChargeRule> {
+    sqlx::query!(
+        r#"
+        SELECT name, category, calculation, amount, basis, inclusive
+        FROM hotel_charges
+        WHERE hotel_id = $1
+        ORDER BY created_at, id
+        "#,
+        hotel_id
+    )
+    .fetch_all(conn)
+    .await
+    .unwrap()
+    .into_iter()
+    .filter_map(|r| {
+        Some(ChargeRule {
+            name: r.name,
+            category: ChargeCategory::parse(&r.category)?,
+            calculation: Calculation::parse(&r.calculation)?,
+            amount: r.amount,
+            basis: ChargeBasis::parse(&r.basis)?,
+            inclusive: r.inclusive,
+        })
+    })
+    .collect()
+}
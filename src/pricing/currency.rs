@@ -0,0 +1,89 @@
+use sqlx::{types::BigDecimal, PgConnection};
+use std::collections::HashMap;
+
+/// Normalizes an ISO 4217 style code, e.g. "eur" -> "EUR".
+pub fn parse_currency(value: &str) -> Option<String> {
+    let value = value.trim();
+
+    if value.len() == 3 && value.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(value.to_ascii_uppercase())
+    } else {
+        None
+    }
+}
+
+/// Converts stored prices into a currency the caller asked to see them in.
+/// Charging always happens in the hotel's own currency; this is display only.
+pub struct Converter {
+    target: Option<String>,
+    rates: HashMap<String, BigDecimal>,
+}
+
+impl Converter {
+    /// Leaves every amount in the currency it is priced in.
+    pub fn none() -> Self {
+        Self { target: None, rates: HashMap::new() }
+    }
+
+    /// Loads the uploaded rate table for `target`. Fails if the code is
+    /// malformed or no rate has been uploaded for it.
+    pub async fn load(
+        conn: &mut PgConnection,
+        target: Option<&str>,
+    ) -> Result<Self, &'static str> {
+        let target = match target {
+            Some(v) => Some(parse_currency(v).ok_or("INVALID_REQUEST")?),
+            None => return Ok(Self::none()),
+        };
+
+        let rates: HashMap<String, BigDecimal> =
+            sqlx::query!("SELECT currency, rate FROM exchange_rates")
+                .fetch_all(conn)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.currency, r.rate))
+                .collect();
+
+        if !target.as_ref().is_some_and(|t| rates.contains_key(t)) {
+            return Err("UNSUPPORTED_CURRENCY");
+        }
+
+        Ok(Self { target, rates })
+    }
+
+    /// Currency that amounts priced in `from` are displayed in. Falls back to
+    /// `from` itself when there is no rate to convert it with.
+    pub fn display_currency(&self, from: &str) -> String {
+        match self.factor(from) {
+            Some(_) => self.target.clone().unwrap_or_else(|| from.to_string()),
+            None => from.to_string(),
+        }
+    }
+
+    /// Whether amounts priced in `from` are actually converted, rather than
+    /// passed through because a rate is missing.
+    pub fn converts(&self, from: &str) -> bool {
+        self.factor(from).is_some()
+    }
+
+    pub fn convert(&self, amount: &BigDecimal, from: &str) -> BigDecimal {
+        match self.factor(from) {
+            Some(factor) => (amount * factor).round(2),
+            None => amount.clone(),
+        }
+    }
+
+    fn factor(&self, from: &str) -> Option<BigDecimal> {
+        let target = self.target.as_deref()?;
+
+        if target == from {
+            return None;
+        }
+
+        let from_rate = self.rates.get(from)?;
+        let target_rate = self.rates.get(target)?;
+
+        Some(target_rate / from_rate)
+    }
+}
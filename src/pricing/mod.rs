@@ -0,0 +1,98 @@
+use chrono::NaiveDate;
+use sqlx::{types::BigDecimal, PgConnection};
+use uuid::Uuid;
+
+pub mod cancellation;
+pub mod charges;
+pub mod currency;
+pub mod promotions;
+pub mod rates;
+
+pub use cancellation::CancellationPolicy;
+pub use charges::{Calculation, ChargeBasis, ChargeCategory};
+pub use currency::{parse_currency, Converter};
+pub use promotions::PromoCode;
+pub use rates::{NightlyRate, RateKind};
+
+pub struct LineItem {
+    pub name: String,
+    pub amount: BigDecimal,
+    /// Already part of the nightly rates, so shown but not added to the total.
+    pub inclusive: bool,
+}
+
+/// Everything that determines what a stay costs.
+pub struct Stay<'a> {
+    pub hotel_id: Uuid,
+    pub room_id: Uuid,
+    pub base_price: &'a BigDecimal,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    pub guests: i32,
+    /// Discounts the room subtotal before taxes and fees are worked out.
+    pub promo: Option<&'a PromoCode>,
+    /// Value of loyalty points spent on the stay, taken off after the promo
+    /// code. Callers check it does not exceed what is left.
+    pub points_value: Option<&'a BigDecimal>,
+}
+
+pub struct StayPrice {
+    pub nights: Vec<NightlyRate>,
+    pub subtotal: BigDecimal,
+    pub taxes: Vec<LineItem>,
+    pub fees: Vec<LineItem>,
+    pub discounts: Vec<LineItem>,
+    pub total: BigDecimal,
+}
+
+/// Full price of a stay: the nightly breakdown plus everything charged on
+/// top of it.
+pub async fn price_stay(conn: &mut PgConnection, stay: &Stay<'_>) -> StayPrice {
+    let rules = rates::load_rate_rules(conn, stay.room_id, stay.check_in, stay.check_out).await;
+    let nights = rates::nightly_rates(stay.base_price, &rules, stay.check_in, stay.check_out);
+
+    let subtotal = nights
+        .iter()
+        .fold(BigDecimal::from(0), |acc, n| acc + &n.price);
+
+    let mut discounts: Vec<LineItem> = stay.promo.map(|p| p.line_item(&subtotal)).into_iter().collect();
+
+    if let Some(value) = stay.points_value {
+        discounts.push(LineItem {
+            name: "Loyalty points".to_string(),
+            amount: value.clone(),
+            inclusive: false,
+        });
+    }
+
+    let discounted = discounts
+        .iter()
+        .fold(subtotal.clone(), |acc, d| acc - &d.amount);
+
+    let charge_rules = charges::load_charge_rules(conn, stay.hotel_id).await;
+
+    let mut taxes = Vec::new();
+    let mut fees = Vec::new();
+
+    for charge in charges::apply_charges(&charge_rules, &discounted, nights.len() as i64, stay.guests) {
+        match charge.category {
+            ChargeCategory::Tax => taxes.push(charge.item),
+            ChargeCategory::Fee => fees.push(charge.item),
+        }
+    }
+
+    let total = taxes
+        .iter()
+        .chain(fees.iter())
+        .filter(|i| !i.inclusive)
+        .fold(discounted, |acc, i| acc + &i.amount);
+
+    StayPrice {
+        nights,
+        subtotal,
+        taxes,
+        fees,
+        discounts,
+        total,
+    }
+}
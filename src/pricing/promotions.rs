@@ -0,0 +1,271 @@
+use chrono::NaiveDate;
+use sqlx::{types::BigDecimal, PgConnection};
+use uuid::Uuid;
+
+use super::LineItem;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiscountKind {
+    Percent,
+    Fixed,
+}
+
+impl DiscountKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "percent" => Some(Self::Percent),
+            "fixed" => Some(Self::Fixed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Percent => "percent",
+            Self::Fixed => "fixed",
+        }
+    }
+}
+
+/// A promo code as configured by a hotel owner or an admin.
+pub struct PromoCode {
+    pub id: Uuid,
+    pub code: String,
+    /// `None` for platform-wide codes.
+    pub hotel_id: Option<Uuid>,
+    pub kind: DiscountKind,
+    /// Percent off the room subtotal, or a fixed amount in `currency`.
+    pub amount: BigDecimal,
+    pub currency: Option<String>,
+    pub min_nights: i32,
+    /// Dates the code can be redeemed on, both inclusive.
+    pub valid_from: Option<NaiveDate>,
+    pub valid_until: Option<NaiveDate>,
+}
+
+impl PromoCode {
+    /// Whether the code can be used on a stay at `hotel_id`, charged in
+    /// `currency`, booked on `today`. Usage caps are checked on redemption.
+    pub fn check(
+        &self,
+        hotel_id: Uuid,
+        currency: &str,
+        nights: i64,
+        today: NaiveDate,
+    ) -> Result<(), &'static str> {
+        let applies = self.hotel_id.is_none_or(|h| h == hotel_id)
+            && self.currency.as_deref().is_none_or(|c| c == currency)
+            && nights >= self.min_nights as i64
+            && self.valid_from.is_none_or(|d| today >= d)
+            && self.valid_until.is_none_or(|d| today <= d);
+
+        if applies {
+            Ok(())
+        } else {
+            Err("PROMO_CODE_NOT_APPLICABLE")
+        }
+    }
+
+    /// The discount on a room subtotal, never more than the subtotal.
+    pub fn discount_for(&self, subtotal: &BigDecimal) -> BigDecimal {
+        let discount = match self.kind {
+            DiscountKind::Percent => (subtotal * &self.amount / BigDecimal::from(100)).round(2),
+            DiscountKind::Fixed => self.amount.clone(),
+        };
+
+        discount.min(subtotal.clone())
+    }
+
+    pub fn line_item(&self, subtotal: &BigDecimal) -> LineItem {
+        LineItem {
+            name: format!("Promo code {}", self.code),
+            amount: self.discount_for(subtotal),
+            inclusive: false,
+        }
+    }
+}
+
+/// Looks up an active code, ignoring case.
+pub async fn find_code(conn: &mut PgConnection, code: &str) -> Option<PromoCode> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, code, hotel_id, discount_type, amount, currency, min_nights, valid_from, valid_until
+        FROM promo_codes
+        WHERE upper(code) = upper($1) AND active
+        "#,
+        code.trim()
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()?;
+
+    Some(PromoCode {
+        id: row.id,
+        code: row.code,
+        hotel_id: row.hotel_id,
+        kind: DiscountKind::parse(&row.discount_type)?,
+        amount: row.amount,
+        currency: row.currency,
+        min_nights: row.min_nights,
+        valid_from: row.valid_from,
+        valid_until: row.valid_until,
+    })
+}
+
+/// The code a booking was made with and the date it was redeemed, even if
+/// the code has since been deactivated.
+pub async fn load_redeemed(conn: &mut PgConnection, booking_id: Uuid) -> Option<(PromoCode, NaiveDate)> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            p.id,
+            p.code,
+            p.hotel_id,
+            p.discount_type,
+            p.amount,
+            p.currency,
+            p.min_nights,
+            p.valid_from,
+            p.valid_until,
+            r.created_at AS redeemed_at
+        FROM promo_redemptions r
+        JOIN promo_codes p ON p.id = r.promo_code_id
+        WHERE r.booking_id = $1
+        "#,
+        booking_id
+    )
+    .fetch_optional(conn)
+    .await
+    .unwrap()?;
+
+    let promo = PromoCode {
+        id: row.id,
+        code: row.code,
+        hotel_id: row.hotel_id,
+        kind: DiscountKind::parse(&row.discount_type)?,
+        amount: row.amount,
+        currency: row.currency,
+        min_nights: row.min_nights,
+        valid_from: row.valid_from,
+        valid_until: row.valid_until,
+    };
+
+    Some((promo, row.redeemed_at.date()))
+}
+
+/// Whether the code still has uses left overall and for `user_id`. Only
+/// advisory, for quotes; `redeem` is what enforces the caps.
+pub async fn has_uses_left(conn: &mut PgConnection, promo_id: Uuid, user_id: Uuid) -> bool {
+    sqlx::query_scalar!(
+        r#"
+        SELECT
+            (p.max_uses IS NULL OR p.uses < p.max_uses)
+            AND (
+                p.max_uses_per_user IS NULL
+                OR (SELECT count(*) FROM promo_redemptions r
+                    WHERE r.promo_code_id = p.id AND r.user_id = $2) < p.max_uses_per_user
+            ) AS "available!"
+        FROM promo_codes p
+        WHERE p.id = $1
+        "#,
+        promo_id,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .unwrap()
+}
+
+/// Counts one use of a code for a booking. The increment takes the code's
+/// row lock, so concurrent bookings cannot exceed either cap; the caller
+/// rolls back on error.
+pub async fn redeem(
+    conn: &mut PgConnection,
+    promo_id: Uuid,
+    user_id: Uuid,
+    booking_id: Uuid,
+    discount: &BigDecimal,
+) -> Result<(), &'static str> {
+    let per_user_cap = sqlx::query_scalar!(
+        r#"
+        UPDATE promo_codes
+        SET uses = uses + 1
+        WHERE id = $1 AND (max_uses IS NULL OR uses < max_uses)
+        RETURNING max_uses_per_user
+        "#,
+        promo_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .unwrap()
+    .ok_or("PROMO_CODE_EXHAUSTED")?;
+
+    if let Some(cap) = per_user_cap {
+        let used = sqlx::query_scalar!(
+            r#"
+            SELECT count(*) AS "count!"
+            FROM promo_redemptions
+            WHERE promo_code_id = $1 AND user_id = $2
+            "#,
+            promo_id,
+            user_id
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+        if used >= cap as i64 {
+            return Err("PROMO_CODE_EXHAUSTED");
+        }
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO promo_redemptions (promo_code_id, user_id, booking_id, amount)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        promo_id,
+        user_id,
+        booking_id,
+        discount
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+
+    Ok(())
+}
+
+/// Gives back the use a cancelled or lapsed booking made of its code, so
+/// it counts against neither cap.
+pub async fn release(conn: &mut PgConnection, booking_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query!(
+        r#"
+        WITH released AS (
+            DELETE FROM promo_redemptions
+            WHERE booking_id = $1
+            RETURNING promo_code_id
+        )
+        UPDATE promo_codes
+        SET uses = uses - 1
+        WHERE id IN (SELECT promo_code_id FROM released)
+        "#,
+        booking_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Records the discount a modified booking now gets under its code.
+pub async fn update_redemption(conn: &mut PgConnection, booking_id: Uuid, discount: &BigDecimal) {
+    sqlx::query!(
+        "UPDATE promo_redemptions SET amount = $2 WHERE booking_id = $1",
+        booking_id,
+        discount
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
@@ -0,0 +1,136 @@
+use chrono::{Datelike, NaiveDate};
+use sqlx::{types::BigDecimal, PgConnection};
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RateKind {
+    DateRange,
+    DayOfWeek,
+    Date,
+}
+
+impl RateKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "date_range" => Some(Self::DateRange),
+            "day_of_week" => Some(Self::DayOfWeek),
+            "date" => Some(Self::Date),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DateRange => "date_range",
+            Self::DayOfWeek => "day_of_week",
+            Self::Date => "date",
+        }
+    }
+}
+
+pub struct RateRule {
+    pub kind: RateKind,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub days_of_week: Option<Vec<i32>>,
+    pub price: BigDecimal,
+    pub cancellation_policy_id: Option<Uuid>,
+}
+
+impl RateRule {
+    fn applies_to(&self, date: NaiveDate) -> bool {
+        let in_range = self.start_date.is_none_or(|s| date >= s)
+            && self.end_date.is_none_or(|e| date <= e);
+
+        let on_day = match &self.days_of_week {
+            Some(days) => days.contains(&(date.weekday().number_from_monday() as i32)),
+            None => true,
+        };
+
+        in_range && on_day
+    }
+
+    fn span_days(&self) -> i64 {
+        match (self.start_date, self.end_date) {
+            (Some(s), Some(e)) => (e - s).num_days(),
+            _ => i64::MAX,
+        }
+    }
+}
+
+pub struct NightlyRate {
+    pub date: NaiveDate,
+    pub price: BigDecimal,
+    /// Policy of the rate that priced this night, if it has one.
+    pub cancellation_policy_id: Option<Uuid>,
+}
+
+/// Resolves the price of every night in `[check_in, check_out)`.
+///
+/// When several rules cover a night, explicit dates beat day-of-week
+/// overrides, which beat plain date ranges. Within a kind the narrower
+/// range wins, and after that the most recently added rule. Nights no
+/// rule covers fall back to the room's base price.
+pub fn nightly_rates(
+    base_price: &BigDecimal,
+    rules: &[RateRule],
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+) -> Vec<NightlyRate> {
+    check_in
+        .iter_days()
+        .take_while(|d| *d < check_out)
+        .map(|date| {
+            let rule = rules
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.applies_to(date))
+                .max_by_key(|(i, r)| (r.kind, Reverse(r.span_days()), *i))
+                .map(|(_, r)| r);
+
+            NightlyRate {
+                date,
+                price: rule.map_or_else(|| base_price.clone(), |r| r.price.clone()),
+                cancellation_policy_id: rule.and_then(|r| r.cancellation_policy_id),
+            }
+        })
+        .collect()
+}
+
+/// Rate rules for a room that overlap the stay, oldest first.
+pub async fn load_rate_rules(
+    conn: &mut PgConnection,
+    room_id: Uuid,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+) -> Vec<RateRule> {
+    sqlx::query!(
+        r#"
+        SELECT kind, start_date, end_date, days_of_week, price_per_night, cancellation_policy_id
+        FROM room_rates
+        WHERE room_id = $1
+        AND (start_date IS NULL OR start_date < $3)
+        AND (end_date IS NULL OR end_date >= $2)
+        ORDER BY created_at, id
+        "#,
+        room_id,
+        check_in,
+        check_out
+    )
+    .fetch_all(conn)
+    .await
+    .unwrap()
+    .into_iter()
+    .filter_map(|r| {
+        Some(RateRule {
+            kind: RateKind::parse(&r.kind)?,
+            start_date: r.start_date,
+            end_date: r.end_date,
+            days_of_week: r.days_of_week,
+            price: r.price_per_night,
+            cancellation_policy_id: r.cancellation_policy_id,
+        })
+    })
+    .collect()
+}
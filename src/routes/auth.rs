@@ -1,11 +1,11 @@
 use axum::{Router, routing::post};
-use sqlx::PgPool;
+use crate::state::AppState;
 
 use crate::handlers::auth::{signup, login};
 
-pub fn auth_routes(pool: PgPool) -> Router {
+pub fn auth_routes(state: AppState) -> Router {
     Router::new()
         .route("/api/auth/signup", post(signup))
         .route("/api/auth/login", post(login))
-        .with_state(pool)
+        .with_state(state)
 }
\ No newline at end of file
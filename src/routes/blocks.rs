@@ -0,0 +1,17 @@
+use axum::{Router, routing::{post, delete}};
+use crate::state::AppState;
+
+use crate::handlers::blocks::{create_block, list_blocks, delete_block};
+
+pub fn block_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/blocks",
+            post(create_block).get(list_blocks),
+        )
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/blocks/:blockId",
+            delete(delete_block),
+        )
+        .with_state(state)
+}
@@ -1,11 +1,26 @@
-use axum::{Router, routing::{post, put}};
-use sqlx::PgPool;
+use axum::{Router, middleware, routing::{patch, post, put}};
+use crate::state::AppState;
 
-use crate::handlers::bookings::{create_booking, list_bookings, cancel_booking};
+use crate::handlers::idempotency::idempotent;
+use crate::handlers::bookings::{create_booking, hold_booking, confirm_booking, quote_booking, list_bookings, modify_booking,
+    cancel_booking, cancel_hotel_booking};
 
-pub fn booking_routes(pool: PgPool) -> Router {
+pub fn booking_routes(state: AppState) -> Router {
     Router::new()
-        .route("/api/bookings", post(create_booking).get(list_bookings))
+        .route(
+            "/api/bookings",
+            post(create_booking)
+                .layer(middleware::from_fn_with_state(state.clone(), idempotent))
+                .get(list_bookings),
+        )
+        .route("/api/bookings/quote", post(quote_booking))
+        .route("/api/bookings/hold", post(hold_booking))
+        .route(
+            "/api/bookings/:bookingId/confirm",
+            put(confirm_booking).layer(middleware::from_fn_with_state(state.clone(), idempotent)),
+        )
+        .route("/api/bookings/:bookingId", patch(modify_booking))
         .route("/api/bookings/:bookingId/cancel", put(cancel_booking))
-        .with_state(pool)
+        .route("/api/hotels/:hotelId/bookings/:bookingId/cancel", put(cancel_hotel_booking))
+        .with_state(state)
 }
\ No newline at end of file
@@ -0,0 +1,27 @@
+use axum::{Router, routing::{delete, get, post}};
+use crate::state::AppState;
+
+use crate::handlers::calendars::{
+    create_calendar_import, delete_calendar_import, export_calendar, get_calendar_feed, list_calendar_imports,
+    rotate_calendar_feed, sync_calendar_import,
+};
+
+pub fn calendar_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/calendars/:token", get(export_calendar))
+        .route("/api/hotels/:hotelId/rooms/:roomId/calendar/feed", get(get_calendar_feed))
+        .route("/api/hotels/:hotelId/rooms/:roomId/calendar/feed/rotate", post(rotate_calendar_feed))
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/calendar/imports",
+            post(create_calendar_import).get(list_calendar_imports),
+        )
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/calendar/imports/:importId",
+            delete(delete_calendar_import),
+        )
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/calendar/imports/:importId/sync",
+            post(sync_calendar_import),
+        )
+        .with_state(state)
+}
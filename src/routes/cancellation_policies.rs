@@ -0,0 +1,18 @@
+use axum::{Router, routing::{post, delete}};
+use crate::state::AppState;
+
+use crate::handlers::cancellation_policies::{create_cancellation_policy, list_cancellation_policies,
+    delete_cancellation_policy};
+
+pub fn cancellation_policy_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/hotels/:hotelId/cancellation-policies",
+            post(create_cancellation_policy).get(list_cancellation_policies),
+        )
+        .route(
+            "/api/hotels/:hotelId/cancellation-policies/:policyId",
+            delete(delete_cancellation_policy),
+        )
+        .with_state(state)
+}
@@ -0,0 +1,17 @@
+use axum::{Router, routing::{post, delete}};
+use crate::state::AppState;
+
+use crate::handlers::charges::{create_charge, list_charges, delete_charge};
+
+pub fn charge_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/hotels/:hotelId/charges",
+            post(create_charge).get(list_charges),
+        )
+        .route(
+            "/api/hotels/:hotelId/charges/:chargeId",
+            delete(delete_charge),
+        )
+        .with_state(state)
+}
@@ -0,0 +1,10 @@
+use axum::{Router, routing::get};
+use crate::state::AppState;
+
+use crate::handlers::events::hotel_event_stream;
+
+pub fn event_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/hotels/:hotelId/events", get(hotel_event_stream))
+        .with_state(state)
+}
@@ -0,0 +1,13 @@
+use axum::{Router, routing::put};
+use crate::state::AppState;
+
+use crate::handlers::exchange_rates::{upload_exchange_rates, get_exchange_rates};
+
+pub fn exchange_rate_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/exchange-rates",
+            put(upload_exchange_rates).get(get_exchange_rates),
+        )
+        .with_state(state)
+}
@@ -0,0 +1,15 @@
+use axum::{Router, routing::{get, put}};
+use crate::state::AppState;
+
+use crate::handlers::front_desk::{check_in_booking, check_out_booking, list_hotel_bookings, mark_no_show,
+    list_booking_emails};
+
+pub fn front_desk_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/hotels/:hotelId/bookings", get(list_hotel_bookings))
+        .route("/api/hotels/:hotelId/bookings/:bookingId/emails", get(list_booking_emails))
+        .route("/api/hotels/:hotelId/bookings/:bookingId/check-in", put(check_in_booking))
+        .route("/api/hotels/:hotelId/bookings/:bookingId/check-out", put(check_out_booking))
+        .route("/api/hotels/:hotelId/bookings/:bookingId/no-show", put(mark_no_show))
+        .with_state(state)
+}
@@ -1,11 +1,17 @@
-use axum::{Router, routing::{post, get}};
-use sqlx::PgPool;
+use axum::{Router, middleware, routing::{post, get}};
+use crate::state::AppState;
 
+use crate::handlers::idempotency::idempotent;
 use crate::handlers::hotels::{create_hotel, list_hotels, get_hotel_by_id};
 
-pub fn hotel_routes(pool: PgPool) -> Router {
+pub fn hotel_routes(state: AppState) -> Router {
     Router::new()
-        .route("/api/hotels", post(create_hotel).get(list_hotels))
+        .route(
+            "/api/hotels",
+            post(create_hotel)
+                .layer(middleware::from_fn_with_state(state.clone(), idempotent))
+                .get(list_hotels),
+        )
         .route("/api/hotels/:hotelId", get(get_hotel_by_id))
-        .with_state(pool)
+        .with_state(state)
 }
\ No newline at end of file
@@ -0,0 +1,14 @@
+use axum::{Router, routing::get};
+use crate::state::AppState;
+
+use crate::handlers::invoices::{get_billing_details, get_invoice, update_billing_details};
+
+pub fn invoice_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/bookings/:bookingId/invoice", get(get_invoice))
+        .route(
+            "/api/hotels/:hotelId/billing-details",
+            get(get_billing_details).put(update_billing_details),
+        )
+        .with_state(state)
+}
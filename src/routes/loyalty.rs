@@ -0,0 +1,10 @@
+use axum::{Router, routing::get};
+use crate::state::AppState;
+
+use crate::handlers::loyalty::get_loyalty;
+
+pub fn loyalty_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/users/me/loyalty", get(get_loyalty))
+        .with_state(state)
+}
@@ -1,17 +1,60 @@
 use axum::Router;
-use sqlx::PgPool;
+use tower_http::services::ServeDir;
+
+use crate::state::AppState;
 
 pub mod auth;
 pub mod hotels;
 pub mod rooms;
 pub mod bookings;
 pub mod reviews;
+pub mod photos;
+pub mod rates;
+pub mod charges;
+pub mod exchange_rates;
+pub mod restrictions;
+pub mod blocks;
+pub mod reservations;
+pub mod payments;
+pub mod cancellation_policies;
+pub mod notifications;
+pub mod front_desk;
+pub mod waitlist;
+pub mod promo_codes;
+pub mod loyalty;
+pub mod invoices;
+pub mod calendars;
+pub mod webhooks;
+pub mod events;
+
+pub fn create_routes(state: AppState) -> Router {
+    let router = Router::new()
+        .merge(auth::auth_routes(state.clone()))
+        .merge(hotels::hotel_routes(state.clone()))
+        .merge(rooms::room_routes(state.clone()))
+        .merge(bookings::booking_routes(state.clone()))
+        .merge(reviews::review_route(state.clone()))
+        .merge(photos::photo_routes(state.clone()))
+        .merge(rates::rate_routes(state.clone()))
+        .merge(charges::charge_routes(state.clone()))
+        .merge(exchange_rates::exchange_rate_routes(state.clone()))
+        .merge(restrictions::restriction_routes(state.clone()))
+        .merge(blocks::block_routes(state.clone()))
+        .merge(reservations::reservation_routes(state.clone()))
+        .merge(payments::payment_routes(state.clone()))
+        .merge(cancellation_policies::cancellation_policy_routes(state.clone()))
+        .merge(notifications::notification_routes(state.clone()))
+        .merge(front_desk::front_desk_routes(state.clone()))
+        .merge(waitlist::waitlist_routes(state.clone()))
+        .merge(promo_codes::promo_code_routes(state.clone()))
+        .merge(loyalty::loyalty_routes(state.clone()))
+        .merge(invoices::invoice_routes(state.clone()))
+        .merge(calendars::calendar_routes(state.clone()))
+        .merge(webhooks::webhook_routes(state.clone()))
+        .merge(events::event_routes(state.clone()));
 
-pub fn create_routes(pool: PgPool) -> Router {
-    Router::new()
-        .merge(auth::auth_routes(pool.clone()))
-        .merge(hotels::hotel_routes(pool.clone()))
-        .merge(rooms::room_routes(pool.clone()))
-        .merge(bookings::booking_routes(pool.clone()))
-        .merge(reviews::review_route(pool))
+    match state.storage.local_root() {
+        Some(root) => router.nest_service("/media", ServeDir::new(root)),
+        None => router,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,11 @@
+use axum::{Router, routing::{get, put}};
+use crate::state::AppState;
+
+use crate::handlers::notifications::{list_notifications, mark_notification_read};
+
+pub fn notification_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/users/me/notifications", get(list_notifications))
+        .route("/api/users/me/notifications/:notificationId/read", put(mark_notification_read))
+        .with_state(state)
+}
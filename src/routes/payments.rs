@@ -0,0 +1,10 @@
+use axum::{Router, routing::post};
+use crate::state::AppState;
+
+use crate::handlers::payments::payment_webhook;
+
+pub fn payment_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/payments/webhook", post(payment_webhook))
+        .with_state(state)
+}
@@ -0,0 +1,21 @@
+use axum::{Router, extract::DefaultBodyLimit, routing::{post, put, delete}};
+use crate::state::AppState;
+
+use crate::handlers::photos::{
+    upload_hotel_photo, upload_room_photo, reorder_hotel_photos, reorder_room_photos,
+    set_cover_photo, delete_photo, max_photo_bytes,
+};
+
+pub fn photo_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/hotels/:hotelId/photos", post(upload_hotel_photo))
+        .route("/api/hotels/:hotelId/photos/order", put(reorder_hotel_photos))
+        .route("/api/hotels/:hotelId/rooms/:roomId/photos", post(upload_room_photo))
+        .route("/api/hotels/:hotelId/rooms/:roomId/photos/order", put(reorder_room_photos))
+        .route("/api/photos/:photoId/cover", put(set_cover_photo))
+        .route("/api/photos/:photoId", delete(delete_photo))
+        // Leave headroom over the file limit for multipart framing and
+        // the other form fields.
+        .layer(DefaultBodyLimit::max(max_photo_bytes() + 64 * 1024))
+        .with_state(state)
+}
@@ -0,0 +1,11 @@
+use axum::{Router, routing::{delete, post}};
+use crate::state::AppState;
+
+use crate::handlers::promo_codes::{create_promo_code, list_promo_codes, deactivate_promo_code};
+
+pub fn promo_code_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/promo-codes", post(create_promo_code).get(list_promo_codes))
+        .route("/api/promo-codes/:promoCodeId", delete(deactivate_promo_code))
+        .with_state(state)
+}
@@ -0,0 +1,17 @@
+use axum::{Router, routing::{post, delete}};
+use crate::state::AppState;
+
+use crate::handlers::rates::{create_rate, list_rates, delete_rate};
+
+pub fn rate_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/rates",
+            post(create_rate).get(list_rates),
+        )
+        .route(
+            "/api/hotels/:hotelId/rooms/:roomId/rates/:rateId",
+            delete(delete_rate),
+        )
+        .with_state(state)
+}
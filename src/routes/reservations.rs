@@ -0,0 +1,16 @@
+use axum::{Router, middleware, routing::{get, post, put}};
+use crate::state::AppState;
+
+use crate::handlers::idempotency::idempotent;
+use crate::handlers::reservations::{create_reservation, get_reservation, cancel_reservation};
+
+pub fn reservation_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/reservations",
+            post(create_reservation).layer(middleware::from_fn_with_state(state.clone(), idempotent)),
+        )
+        .route("/api/reservations/:reservationId", get(get_reservation))
+        .route("/api/reservations/:reservationId/cancel", put(cancel_reservation))
+        .with_state(state)
+}
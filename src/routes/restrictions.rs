@@ -0,0 +1,17 @@
+use axum::{Router, routing::{post, delete}};
+use crate::state::AppState;
+
+use crate::handlers::restrictions::{create_restriction, list_restrictions, delete_restriction};
+
+pub fn restriction_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/hotels/:hotelId/restrictions",
+            post(create_restriction).get(list_restrictions),
+        )
+        .route(
+            "/api/hotels/:hotelId/restrictions/:restrictionId",
+            delete(delete_restriction),
+        )
+        .with_state(state)
+}
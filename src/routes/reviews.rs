@@ -1,13 +1,14 @@
-use axum::{Router, routing::post};
-use sqlx::PgPool;
+use axum::{Router, middleware, routing::post};
+use crate::state::AppState;
 
+use crate::handlers::idempotency::idempotent;
 use crate::handlers::reviews::create_review;
 
-pub fn review_route(pool: PgPool) -> Router {
+pub fn review_route(state: AppState) -> Router {
     Router::new()
         .route(
             "/api/reviews",
-            post(create_review),
+            post(create_review).layer(middleware::from_fn_with_state(state.clone(), idempotent)),
         )
-        .with_state(pool)
+        .with_state(state)
 }
\ No newline at end of file
@@ -1,13 +1,14 @@
-use axum::{Router, routing::post};
-use sqlx::PgPool;
+use axum::{Router, middleware, routing::post};
+use crate::state::AppState;
 
+use crate::handlers::idempotency::idempotent;
 use crate::handlers::rooms::create_room;
 
-pub fn room_routes(pool: PgPool) -> Router {
+pub fn room_routes(state: AppState) -> Router {
     Router::new()
         .route(
             "/api/hotels/:hotelId/rooms",
-            post(create_room),
+            post(create_room).layer(middleware::from_fn_with_state(state.clone(), idempotent)),
         )
-        .with_state(pool)
+        .with_state(state)
 }
\ No newline at end of file
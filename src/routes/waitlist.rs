@@ -0,0 +1,12 @@
+use axum::{Router, routing::{delete, get, post}};
+use crate::state::AppState;
+
+use crate::handlers::waitlist::{join_waitlist, list_waitlist, leave_waitlist};
+
+pub fn waitlist_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/waitlist", post(join_waitlist))
+        .route("/api/waitlist/:entryId", delete(leave_waitlist))
+        .route("/api/users/me/waitlist", get(list_waitlist))
+        .with_state(state)
+}
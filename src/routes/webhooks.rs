@@ -0,0 +1,18 @@
+use axum::{Router, routing::{delete, get, post}};
+use crate::state::AppState;
+
+use crate::handlers::webhooks::{
+    create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks, replay_webhook_delivery,
+};
+
+pub fn webhook_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/api/webhooks", post(create_webhook).get(list_webhooks))
+        .route("/api/webhooks/:webhookId", delete(delete_webhook))
+        .route("/api/webhooks/:webhookId/deliveries", get(list_webhook_deliveries))
+        .route(
+            "/api/webhooks/:webhookId/deliveries/:deliveryId/replay",
+            post(replay_webhook_delivery),
+        )
+        .with_state(state)
+}
@@ -0,0 +1,37 @@
+use axum::extract::FromRef;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::{events::HotelEvents, payments::PaymentGateway, storage::ObjectStorage};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub storage: Arc<dyn ObjectStorage>,
+    pub payments: Arc<dyn PaymentGateway>,
+    pub events: HotelEvents,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn ObjectStorage> {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn PaymentGateway> {
+    fn from_ref(state: &AppState) -> Self {
+        state.payments.clone()
+    }
+}
+
+impl FromRef<AppState> for HotelEvents {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
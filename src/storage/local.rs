@@ -0,0 +1,54 @@
+use axum::async_trait;
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use super::{ObjectStorage, StorageError};
+
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    pub fn from_env() -> Self {
+        let root = env::var("STORAGE_LOCAL_DIR").unwrap_or_else(|_| "uploads".to_string());
+        let base_url = env::var("PUBLIC_BASE_URL").unwrap_or_default();
+
+        Self {
+            root: PathBuf::from(root),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Vec<u8>, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.root.join(key);
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/media/{}", self.base_url, key)
+    }
+
+    fn local_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
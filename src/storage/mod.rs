@@ -0,0 +1,41 @@
+use axum::async_trait;
+use std::{env, path::Path, sync::Arc};
+
+pub mod local;
+pub mod s3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("object store returned {0}")]
+    Status(u16),
+}
+
+/// Blob store for uploaded files. Keys are relative, slash-separated paths
+/// such as `hotels/<hotelId>/<photoId>.jpg`.
+#[async_trait]
+pub trait ObjectStorage: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+
+    fn public_url(&self, key: &str) -> String;
+
+    /// Directory to serve under `/media` when files live on local disk.
+    fn local_root(&self) -> Option<&Path> {
+        None
+    }
+}
+
+pub fn create_storage() -> Arc<dyn ObjectStorage> {
+    let backend = env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string());
+
+    match backend.as_str() {
+        "local" => Arc::new(local::LocalStorage::from_env()),
+        "s3" => Arc::new(s3::S3Storage::from_env()),
+        other => panic!("Unknown STORAGE_BACKEND: {other}"),
+    }
+}
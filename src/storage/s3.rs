@@ -0,0 +1,145 @@
+use axum::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, Url};
+use sha2::{Digest, Sha256};
+use std::env;
+
+use super::{ObjectStorage, StorageError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// S3-compatible backend using path-style addressing, so it works against
+/// AWS as well as MinIO and similar local stand-ins.
+pub struct S3Storage {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    public_url: String,
+}
+
+impl S3Storage {
+    pub fn from_env() -> Self {
+        let endpoint = env::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set");
+        let bucket = env::var("S3_BUCKET").expect("S3_BUCKET must be set");
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+
+        let public_url = env::var("S3_PUBLIC_URL")
+            .unwrap_or_else(|_| format!("{endpoint}/{bucket}"));
+
+        Self {
+            client: Client::new(),
+            endpoint,
+            bucket,
+            region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            access_key: env::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY must be set"),
+            secret_key: env::var("S3_SECRET_KEY").expect("S3_SECRET_KEY must be set"),
+            public_url: public_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<(), StorageError> {
+        let path = format!("/{}/{}", self.bucket, uri_encode(key));
+        let url = Url::parse(&format!("{}{}", self.endpoint, path)).expect("invalid S3_ENDPOINT");
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method.as_str(),
+            path,
+            host,
+            payload_hash,
+            amz_date,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let k_date = hmac(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes());
+        let k_region = hmac(&k_date, self.region.as_bytes());
+        let k_service = hmac(&k_region, b"s3");
+        let k_signing = hmac(&k_service, b"aws4_request");
+        let signature = hex::encode(hmac(&k_signing, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key, scope, signature
+        );
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body);
+
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            return Err(StorageError::Status(response.status().as_u16()));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for S3Storage {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError> {
+        self.send(Method::PUT, key, bytes, Some(content_type)).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.send(Method::DELETE, key, Vec::new(), None).await
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,254 @@
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{PgConnection, PgPool};
+use std::env;
+use uuid::Uuid;
+
+use crate::outbound;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Events owners can subscribe to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A booking was confirmed. Holds and unpaid bookings that lapse are
+    /// never announced.
+    BookingCreated,
+    BookingCancelled,
+    ReviewCreated,
+}
+
+impl Event {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "booking.created" => Some(Self::BookingCreated),
+            "booking.cancelled" => Some(Self::BookingCancelled),
+            "review.created" => Some(Self::ReviewCreated),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BookingCreated => "booking.created",
+            Self::BookingCancelled => "booking.cancelled",
+            Self::ReviewCreated => "review.created",
+        }
+    }
+}
+
+/// Queues a delivery of the event to every subscription of the hotel's
+/// owner that wants it, in the caller's transaction.
+async fn emit(conn: &mut PgConnection, hotel_id: Uuid, event: Event, data: Value) {
+    let event_id = Uuid::new_v4();
+
+    let payload = json!({
+        "id": event_id,
+        "type": event.as_str(),
+        "createdAt": Utc::now().to_rfc3339(),
+        "data": data,
+    });
+
+    sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (subscription_id, event_id, event_type, payload)
+        SELECT s.id, $2, $3::text, $4
+        FROM webhook_subscriptions s
+        JOIN hotels h ON h.owner_id = s.owner_id
+        WHERE h.id = $1 AND $3::text = ANY(s.events)
+        "#,
+        hotel_id,
+        event_id,
+        event.as_str(),
+        payload.to_string()
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+/// Emits `booking.created` or `booking.cancelled` with the booking as it
+/// stands in the caller's transaction.
+pub(crate) async fn booking_event(conn: &mut PgConnection, booking_id: Uuid, event: Event) {
+    let b = sqlx::query!(
+        r#"
+        SELECT hotel_id, room_id, user_id, check_in_date, check_out_date, guests,
+               total_price, currency, status, cancelled_at, cancellation_reason
+        FROM bookings
+        WHERE id = $1
+        "#,
+        booking_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    let data = json!({
+        "id": booking_id,
+        "hotelId": b.hotel_id,
+        "roomId": b.room_id,
+        "userId": b.user_id,
+        "checkInDate": b.check_in_date.to_string(),
+        "checkOutDate": b.check_out_date.to_string(),
+        "guests": b.guests,
+        "totalPrice": b.total_price.to_string(),
+        "currency": b.currency,
+        "status": b.status,
+        "cancelledAt": b.cancelled_at.map(|d| d.and_utc().to_rfc3339()),
+        "cancellationReason": b.cancellation_reason,
+    });
+
+    emit(conn, b.hotel_id, event, data).await;
+}
+
+pub(crate) async fn review_created(conn: &mut PgConnection, review_id: Uuid) {
+    let r = sqlx::query!(
+        "SELECT hotel_id, booking_id, user_id, rating, comment FROM reviews WHERE id = $1",
+        review_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+
+    let data = json!({
+        "id": review_id,
+        "hotelId": r.hotel_id,
+        "bookingId": r.booking_id,
+        "userId": r.user_id,
+        "rating": r.rating,
+        "comment": r.comment,
+    });
+
+    emit(conn, r.hotel_id, Event::ReviewCreated, data).await;
+}
+
+/// Hex HMAC-SHA256 of the payload, sent as `X-Webhook-Signature`.
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Attempts before a delivery is dead-lettered and left for a replay.
+pub fn max_attempts() -> i32 {
+    env::var("WEBHOOK_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8)
+}
+
+/// Wait before the next attempt: a minute, doubling each time, at most six
+/// hours.
+fn backoff(attempts: i32) -> Duration {
+    let seconds = 60i64 << attempts.clamp(1, 10).saturating_sub(1);
+    Duration::seconds(seconds.min(6 * 3600))
+}
+
+/// Posts a delivery to its endpoint. The URL is checked again on every
+/// attempt, since what its host resolves to can change after subscribing.
+async fn post(url: &str, headers: [(&str, String); 3], payload: String) -> Result<reqwest::Response, String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+
+    let client = outbound::client_for(&url, std::time::Duration::from_secs(10))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut request = client.post(url).header("Content-Type", "application/json");
+
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    request.body(payload).send().await.map_err(|e| e.to_string())
+}
+
+/// How long a worker has to post a claimed delivery before another may
+/// take it over; well beyond the request timeout.
+fn lease() -> Duration {
+    Duration::minutes(5)
+}
+
+/// Posts up to `limit` due deliveries and returns how many were accepted.
+/// Any 2xx counts as delivered; everything else, redirects included, is
+/// retried. Deliveries are claimed with a lease in a statement of their own,
+/// so no lock is held while posting, and each result is recorded on its own.
+pub async fn deliver_due(pool: &PgPool, limit: i64) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries d
+        SET locked_until = $2
+        FROM webhook_subscriptions s
+        WHERE s.id = d.subscription_id
+        AND d.id IN (
+            SELECT id
+            FROM webhook_deliveries
+            WHERE status = 'pending'
+            AND next_attempt_at <= now() AT TIME ZONE 'utc'
+            AND (locked_until IS NULL OR locked_until <= now() AT TIME ZONE 'utc')
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING d.id, d.event_type, d.payload, d.attempts, s.url, s.secret
+        "#,
+        limit,
+        Utc::now().naive_utc() + lease()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut delivered = 0;
+
+    for d in due {
+        let headers = [
+            ("X-Webhook-Event", d.event_type),
+            ("X-Webhook-Delivery", d.id.to_string()),
+            ("X-Webhook-Signature", sign(&d.secret, &d.payload)),
+        ];
+
+        let (status_code, error) = match post(&d.url, headers, d.payload).await {
+            Ok(r) if r.status().is_success() => (Some(r.status().as_u16() as i32), None),
+            Ok(r) => (Some(r.status().as_u16() as i32), Some(format!("endpoint returned {}", r.status()))),
+            Err(e) => (None, Some(e)),
+        };
+
+        let attempts = d.attempts + 1;
+
+        let status = match &error {
+            None => "delivered",
+            Some(_) if attempts >= max_attempts() => "dead",
+            Some(_) => "pending",
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2::text,
+                attempts = $3,
+                next_attempt_at = $4,
+                last_status_code = $5,
+                last_error = $6,
+                delivered_at = CASE WHEN $2::text = 'delivered' THEN now() AT TIME ZONE 'utc' END,
+                locked_until = NULL
+            WHERE id = $1
+            "#,
+            d.id,
+            status,
+            attempts,
+            Utc::now().naive_utc() + backoff(attempts),
+            status_code,
+            error
+        )
+        .execute(pool)
+        .await?;
+
+        if error.is_none() {
+            delivered += 1;
+        }
+    }
+
+    Ok(delivered)
+}
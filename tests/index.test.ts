@@ -1,4 +1,9 @@
-import { describe, test, expect, beforeAll } from 'vitest';
+import { describe, test, expect, beforeAll, afterAll } from 'vitest';
+import { createHash, createHmac } from 'node:crypto';
+import { createServer, type Server } from 'node:http';
+import { readFile, rename, rm, writeFile } from 'node:fs/promises';
+import { join } from 'node:path';
+import type { AddressInfo } from 'node:net';
 
 const BASE_URL = 'http://localhost:3000';
 
@@ -17,6 +22,48 @@ async function apiRequest(
   return { status: response.status, body };
 }
 
+const PAYMENT_WEBHOOK_SECRET = process.env.PAYMENT_WEBHOOK_SECRET ?? 'mock_webhook_secret';
+
+async function paymentWebhook(
+  type: string,
+  paymentId: string,
+  secret = PAYMENT_WEBHOOK_SECRET
+): Promise<{ status: number; body: any }> {
+  const payload = JSON.stringify({ type, paymentId });
+  const signature = createHmac('sha256', secret).update(payload).digest('hex');
+  return apiRequest('/api/payments/webhook', {
+    method: 'POST',
+    headers: { 'X-Payment-Signature': signature },
+    body: payload,
+  });
+}
+
+// 1x1 red PNG
+const PNG_PIXEL = Uint8Array.from(
+  atob('iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAIAAACQd1PeAAAADElEQVR4nGP4z8AAAAMBAQDJ/pLvAAAAAElFTkSuQmCC'),
+  (c) => c.charCodeAt(0)
+);
+
+async function uploadPhoto(
+  endpoint: string,
+  token: string,
+  file: Blob,
+  fields: Record<string, string> = {}
+): Promise<{ status: number; body: any }> {
+  const form = new FormData();
+  form.append('file', file, 'photo.png');
+  for (const [key, value] of Object.entries(fields)) {
+    form.append(key, value);
+  }
+  const response = await fetch(`${BASE_URL}${endpoint}`, {
+    method: 'POST',
+    headers: { Authorization: `Bearer ${token}` },
+    body: form,
+  });
+  const body = await response.json();
+  return { status: response.status, body };
+}
+
 let ownerToken: string;
 let customerToken: string;
 let customer2Token: string;
@@ -1125,6 +1172,329 @@ describe('Hotel Management System - Comprehensive Tests', () => {
     });
   });
   
+  describe('Currencies', () => {
+    test('should default hotel currency to USD', async () => {
+      const { body } = await apiRequest(`/api/hotels/${hotelId}`, {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(body.data.currency).toBe('USD');
+      expect(body.data.displayCurrency).toBe('USD');
+    });
+    
+    test('should store the currency given at hotel creation', async () => {
+      const { status, body } = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Euro Hotel',
+          city: 'Lisbon',
+          country: 'Portugal',
+          currency: 'eur',
+        }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.currency).toBe('EUR');
+    });
+    
+    test('should return INVALID_REQUEST for malformed hotel currency', async () => {
+      const { status, body } = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Bad Currency Hotel',
+          city: 'Lisbon',
+          country: 'Portugal',
+          currency: 'EURO',
+        }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+    
+    test('should return INVALID_REQUEST for malformed display currency', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${hotelId}?currency=12`, {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+    
+    test('should only let admins upload exchange rates', async () => {
+      const { status, body } = await apiRequest('/api/exchange-rates', {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          baseCurrency: 'USD',
+          rates: { EUR: '0.92' },
+        }),
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+  });
+  
+  describe('POST /api/hotels/:hotelId/photos', () => {
+    let photoId: string;
+    
+    test('should return FORBIDDEN for customer role', async () => {
+      const { status, body } = await uploadPhoto(
+        `/api/hotels/${hotelId}/photos`,
+        customerToken,
+        new Blob([PNG_PIXEL], { type: 'image/png' })
+      );
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should reject files that are not images', async () => {
+      const { status, body } = await uploadPhoto(
+        `/api/hotels/${hotelId}/photos`,
+        ownerToken,
+        new Blob(['not an image'], { type: 'image/png' })
+      );
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_IMAGE');
+    });
+    
+    test('should upload hotel photo and make the first one the cover', async () => {
+      const { status, body } = await uploadPhoto(
+        `/api/hotels/${hotelId}/photos`,
+        ownerToken,
+        new Blob([PNG_PIXEL], { type: 'image/png' })
+      );
+      
+      expect(status).toBe(201);
+      expect(body.success).toBe(true);
+      expect(body.data.hotelId).toBe(hotelId);
+      expect(body.data.roomId).toBeNull();
+      expect(body.data.contentType).toBe('image/png');
+      expect(body.data.isCover).toBe(true);
+      expect(body.data).toHaveProperty('url');
+      expect(body.data).toHaveProperty('thumbnailUrl');
+      
+      photoId = body.data.id;
+    });
+    
+    test('should upload room photo', async () => {
+      const { status, body } = await uploadPhoto(
+        `/api/hotels/${hotelId}/rooms/${roomId}/photos`,
+        ownerToken,
+        new Blob([PNG_PIXEL], { type: 'image/png' })
+      );
+      
+      expect(status).toBe(201);
+      expect(body.data.roomId).toBe(roomId);
+    });
+    
+    test('should include photos in hotel details', async () => {
+      const { body } = await apiRequest(`/api/hotels/${hotelId}`, {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(body.data.photos.map((p: any) => p.id)).toContain(photoId);
+      const room = body.data.rooms.find((r: any) => r.id === roomId);
+      expect(room.photos.length).toBeGreaterThan(0);
+    });
+    
+    test('should reject reorder that does not list every photo', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${hotelId}/photos/order`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({ photoIds: [] }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+  });
+  
+  // Runs against an S3-compatible store such as MinIO. The backend must be
+  // started with STORAGE_BACKEND=s3 and the same S3_* settings as the tests.
+  describe.skipIf(!process.env.S3_ENDPOINT)('Photos in S3 storage', () => {
+    const endpoint = (process.env.S3_ENDPOINT ?? '').replace(/\/+$/, '');
+    const bucket = process.env.S3_BUCKET ?? '';
+    const region = process.env.S3_REGION ?? 'us-east-1';
+    const publicUrl = (process.env.S3_PUBLIC_URL ?? `${endpoint}/${bucket}`).replace(/\/+$/, '');
+    let photo: any;
+    
+    const sha256 = (data: string | Uint8Array) => createHash('sha256').update(data).digest('hex');
+    const hmac = (key: string | Buffer, data: string) => createHmac('sha256', key).update(data).digest();
+    
+    // Path-style request signed with AWS Signature Version 4.
+    const s3Request = (method: string, path: string) => {
+      const url = new URL(`${endpoint}${path}`);
+      const amzDate = new Date().toISOString().replace(/[-:]/g, '').replace(/\.\d{3}/, '');
+      const date = amzDate.slice(0, 8);
+      const payloadHash = sha256('');
+      const canonicalRequest = [
+        method,
+        url.pathname,
+        '',
+        `host:${url.host}`,
+        `x-amz-content-sha256:${payloadHash}`,
+        `x-amz-date:${amzDate}`,
+        '',
+        'host;x-amz-content-sha256;x-amz-date',
+        payloadHash,
+      ].join('\n');
+      const scope = `${date}/${region}/s3/aws4_request`;
+      const stringToSign = ['AWS4-HMAC-SHA256', amzDate, scope, sha256(canonicalRequest)].join('\n');
+      let key = hmac(`AWS4${process.env.S3_SECRET_KEY}`, date);
+      for (const part of [region, 's3', 'aws4_request']) {
+        key = hmac(key, part);
+      }
+      const signature = createHmac('sha256', key).update(stringToSign).digest('hex');
+      
+      return fetch(url, {
+        method,
+        headers: {
+          'x-amz-date': amzDate,
+          'x-amz-content-sha256': payloadHash,
+          Authorization: `AWS4-HMAC-SHA256 Credential=${process.env.S3_ACCESS_KEY}/${scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature=${signature}`,
+        },
+      });
+    };
+    
+    const objectPath = (url: string) => {
+      expect(url.startsWith(`${publicUrl}/`)).toBe(true);
+      return `/${bucket}/${url.slice(publicUrl.length + 1)}`;
+    };
+    
+    beforeAll(async () => {
+      // Creating a bucket that already exists is refused, which is fine.
+      await s3Request('PUT', `/${bucket}`);
+    });
+    
+    test('should store the photo and its thumbnail in the bucket', async () => {
+      const { status, body } = await uploadPhoto(
+        `/api/hotels/${hotelId}/photos`,
+        ownerToken,
+        new Blob([PNG_PIXEL], { type: 'image/png' })
+      );
+      
+      expect(status).toBe(201);
+      photo = body.data;
+      
+      const original = await s3Request('GET', objectPath(photo.url));
+      
+      expect(original.status).toBe(200);
+      expect(original.headers.get('content-type')).toBe('image/png');
+      expect(new Uint8Array(await original.arrayBuffer())).toEqual(PNG_PIXEL);
+      
+      const thumbnail = await s3Request('GET', objectPath(photo.thumbnailUrl));
+      
+      expect(thumbnail.status).toBe(200);
+      expect(thumbnail.headers.get('content-type')).toBe('image/jpeg');
+    });
+    
+    test('should remove both objects when the photo is deleted', async () => {
+      const { status } = await apiRequest(`/api/photos/${photo.id}`, {
+        method: 'DELETE',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      expect((await s3Request('GET', objectPath(photo.url))).status).toBe(404);
+      expect((await s3Request('GET', objectPath(photo.thumbnailUrl))).status).toBe(404);
+    });
+  });
+  
+  describe('POST /api/hotels/:hotelId/rooms/:roomId/rates', () => {
+    test('should return FORBIDDEN for customer role', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${hotelId}/rooms/${roomId}/rates`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          type: 'date',
+          startDate: '2027-07-10',
+          pricePerNight: '9000',
+        }),
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should return INVALID_REQUEST for date range without end date', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${hotelId}/rooms/${roomId}/rates`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          type: 'date_range',
+          startDate: '2027-07-01',
+          pricePerNight: '6000',
+        }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+    
+    test('should price each night using the most specific rule', async () => {
+      const rates = [
+        { type: 'date_range', startDate: '2027-07-01', endDate: '2027-07-31', pricePerNight: '6000' },
+        { type: 'day_of_week', startDate: '2027-07-01', endDate: '2027-07-31', daysOfWeek: [6, 7], pricePerNight: '7000' },
+        { type: 'date', startDate: '2027-07-09', pricePerNight: '9000' },
+      ];
+      
+      for (const rate of rates) {
+        const { status } = await apiRequest(`/api/hotels/${hotelId}/rooms/${roomId}/rates`, {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+          body: JSON.stringify(rate),
+        });
+        expect(status).toBe(201);
+      }
+      
+      // Thu 8th, Fri 9th, Sat 10th
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId,
+          checkInDate: '2027-07-08',
+          checkOutDate: '2027-07-11',
+          guests: 2,
+        }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.nights.map((n: any) => Number(n.price))).toEqual([6000, 9000, 7000]);
+      expect(Number(body.data.totalPrice)).toBe(22000);
+    });
+  });
+  
   describe('POST /api/bookings', () => {
     const futureDate1 = '2026-03-15';
     const futureDate2 = '2026-03-18';
@@ -1390,327 +1760,3599 @@ describe('Hotel Management System - Comprehensive Tests', () => {
     
   });
   
-  describe('GET /api/bookings', () => {
-    test('should return UNAUTHORIZED without token', async () => {
-      const { status, body } = await apiRequest('/api/bookings');
-      
-      expect(status).toBe(401);
-      expect(body.error).toBe('UNAUTHORIZED');
-    });
+  describe('POST /api/hotels/:hotelId/charges', () => {
+    let chargeHotelId: string;
+    let chargeRoomId: string;
     
-    test('should return all bookings for current user', async () => {
-      const { status, body } = await apiRequest('/api/bookings', {
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          name: 'Taxed Hotel',
+          city: 'Jaipur',
+          country: 'India',
+        }),
       });
+      chargeHotelId = hotelRes.body.data.id;
       
-      expect(status).toBe(200);
-      expect(body.success).toBe(true);
-      expect(Array.isArray(body.data)).toBe(true);
-      expect(body.data.length).toBeGreaterThan(0);
-      
-      const booking = body.data[0];
-      expect(booking).toHaveProperty('id');
-      expect(booking).toHaveProperty('hotelName');
-      expect(booking).toHaveProperty('roomNumber');
-      expect(booking).toHaveProperty('roomType');
-    });
-    
-    test('should filter bookings by status=confirmed', async () => {
-      const { status, body } = await apiRequest('/api/bookings?status=confirmed', {
+      const roomRes = await apiRequest(`/api/hotels/${chargeHotelId}/rooms`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          roomNumber: '201',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
       });
-      
-      expect(status).toBe(200);
-      body.data.forEach((booking: any) => {
-        expect(booking.status).toBe('confirmed');
-      });
+      chargeRoomId = roomRes.body.data.id;
     });
     
-    test('should return only current users bookings', async () => {
-      const { body: body1 } = await apiRequest('/api/bookings', {
+    test('should return FORBIDDEN for customer role', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${chargeHotelId}/charges`, {
+        method: 'POST',
         headers: {
           Authorization: `Bearer ${customerToken}`,
         },
+        body: JSON.stringify({
+          name: 'GST',
+          category: 'tax',
+          calculation: 'percentage',
+          amount: '12',
+        }),
       });
       
-      const { body: body2 } = await apiRequest('/api/bookings', {
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should return INVALID_REQUEST for fixed charge without basis', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${chargeHotelId}/charges`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customer2Token}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          name: 'Cleaning',
+          category: 'fee',
+          calculation: 'fixed',
+          amount: '200',
+        }),
       });
       
-      expect(body1.data.length).toBeGreaterThan(0);
-      expect(body2.data.length).toBeGreaterThan(0);
-      
-      const booking1Ids = body1.data.map((b: any) => b.id);
-      const booking2Ids = body2.data.map((b: any) => b.id);
-      
-      const hasOverlap = booking1Ids.some((id: string) => booking2Ids.includes(id));
-      expect(hasOverlap).toBe(false);
-    });
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
     
-    test('should include all required fields in booking response', async () => {
-      const { body } = await apiRequest('/api/bookings', {
+    test('should itemize taxes and fees on bookings', async () => {
+      const charges = [
+        { name: 'GST', category: 'tax', calculation: 'percentage', amount: '12' },
+        { name: 'City tax', category: 'tax', calculation: 'fixed', amount: '50', basis: 'per_guest' },
+        { name: 'Cleaning', category: 'fee', calculation: 'fixed', amount: '200', basis: 'per_stay' },
+      ];
+      
+      for (const charge of charges) {
+        const { status } = await apiRequest(`/api/hotels/${chargeHotelId}/charges`, {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+          body: JSON.stringify(charge),
+        });
+        expect(status).toBe(201);
+      }
+      
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
         headers: {
           Authorization: `Bearer ${customerToken}`,
         },
-      });
-      
-      const booking = body.data[0];
-      expect(booking).toHaveProperty('roomId');
-      expect(booking).toHaveProperty('hotelId');
-      expect(booking).toHaveProperty('checkInDate');
-      expect(booking).toHaveProperty('checkOutDate');
-      expect(booking).toHaveProperty('guests');
-      expect(booking).toHaveProperty('totalPrice');
-      expect(booking).toHaveProperty('status');
-      expect(booking).toHaveProperty('bookingDate');
-    });
-    
-    test('should return empty array for user with no bookings', async () => {
-      const newCustomer = await apiRequest('/api/auth/signup', {
-        method: 'POST',
         body: JSON.stringify({
-          name: 'No Bookings Customer',
-          email: `nobookings${Date.now()}@example.com`,
-          password: 'pass123',
-          role: 'customer',
+          roomId: chargeRoomId,
+          checkInDate: '2027-10-01',
+          checkOutDate: '2027-10-03',
+          guests: 2,
         }),
       });
       
-      const loginRes = await apiRequest('/api/auth/login', {
-        method: 'POST',
-        body: JSON.stringify({
-          email: newCustomer.body.data.email,
-          password: 'pass123',
-        }),
-      });
+      // 2000 subtotal + 240 GST + 100 city tax + 200 cleaning
+      expect(status).toBe(201);
+      expect(Number(body.data.subtotal)).toBe(2000);
+      expect(body.data.taxes.length).toBe(2);
+      expect(body.data.fees.length).toBe(1);
+      expect(Number(body.data.totalPrice)).toBe(2540);
       
-      const { status, body } = await apiRequest('/api/bookings', {
+      const list = await apiRequest('/api/bookings', {
         headers: {
-          Authorization: `Bearer ${loginRes.body.data.token}`,
+          Authorization: `Bearer ${customerToken}`,
         },
       });
-      
-      expect(status).toBe(200);
-      expect(body.data).toEqual([]);
+      const listed = list.body.data.find((b: any) => b.id === body.data.id);
+      expect(Number(listed.subtotal)).toBe(2000);
+      expect(listed.taxes.length).toBe(2);
+      expect(listed.fees.length).toBe(1);
     });
   });
   
-  describe('PUT /api/bookings/:bookingId/cancel', () => {
-    let cancelTestBookingId: string;
+  describe('POST /api/hotels/:hotelId/restrictions', () => {
+    let restrictedHotelId: string;
+    let restrictedRoomId: string;
     
     beforeAll(async () => {
-      const res = await apiRequest('/api/bookings', {
+      const hotelRes = await apiRequest('/api/hotels', {
         method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
         body: JSON.stringify({
-          roomId: room2Id,
-          checkInDate: '2026-12-15',
-          checkOutDate: '2026-12-18',
-          guests: 2,
+          name: 'Restricted Hotel',
+          city: 'Udaipur',
+          country: 'India',
         }),
       });
-      cancelTestBookingId = res.body.data.id;
+      restrictedHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${restrictedHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '301',
+          roomType: 'Suite',
+          pricePerNight: '3000',
+          maxOccupancy: 2,
+        }),
+      });
+      restrictedRoomId = roomRes.body.data.id;
     });
     
-    test('should return UNAUTHORIZED without token', async () => {
-      const { status, body } = await apiRequest(`/api/bookings/${cancelTestBookingId}/cancel`, {
-        method: 'PUT',
+    const book = (path: string, checkInDate: string, checkOutDate: string) =>
+      apiRequest(path, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: restrictedRoomId,
+          checkInDate,
+          checkOutDate,
+          guests: 1,
+        }),
       });
-      
-      expect(status).toBe(401);
-      expect(body.error).toBe('UNAUTHORIZED');
-    });
     
-    test('should cancel booking successfully', async () => {
-      const { status, body } = await apiRequest(`/api/bookings/${cancelTestBookingId}/cancel`, {
-        method: 'PUT',
+    test('should return FORBIDDEN for customer role', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${restrictedHotelId}/restrictions`, {
+        method: 'POST',
         headers: {
           Authorization: `Bearer ${customerToken}`,
         },
+        body: JSON.stringify({ type: 'min_nights', value: 2 }),
       });
       
-      expect(status).toBe(200);
-      expect(body.success).toBe(true);
-      expect(body.data.id).toBe(cancelTestBookingId);
-      expect(body.data.status).toBe('cancelled');
-      expect(body.data).toHaveProperty('cancelledAt');
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
     });
     
-    test('should return ALREADY_CANCELLED when cancelling again', async () => {
-      const { status, body } = await apiRequest(`/api/bookings/${cancelTestBookingId}/cancel`, {
-        method: 'PUT',
+    test('should return INVALID_REQUEST for closed-to-arrival with a value', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${restrictedHotelId}/restrictions`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({ type: 'closed_to_arrival', value: 2 }),
       });
       
       expect(status).toBe(400);
-      expect(body.error).toBe('ALREADY_CANCELLED');
+      expect(body.error).toBe('INVALID_REQUEST');
     });
     
-    test('should return FORBIDDEN when cancelling another users booking', async () => {
-      const { status, body } = await apiRequest(`/api/bookings/${bookingId}/cancel`, {
-        method: 'PUT',
+    test('should enforce minimum stay within a date range', async () => {
+      const { status } = await apiRequest(`/api/hotels/${restrictedHotelId}/restrictions`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customer2Token}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          type: 'min_nights',
+          value: 3,
+          startDate: '2027-12-20',
+          endDate: '2027-12-31',
+        }),
       });
+      expect(status).toBe(201);
       
-      expect(status).toBe(403);
-      expect(body.error).toBe('FORBIDDEN');
+      const quote = await book('/api/bookings/quote', '2027-12-24', '2027-12-26');
+      expect(quote.body.data.available).toBe(false);
+      expect(quote.body.data.restrictions).toEqual(['MIN_STAY_NOT_MET']);
+      
+      const rejected = await book('/api/bookings', '2027-12-24', '2027-12-26');
+      expect(rejected.status).toBe(400);
+      expect(rejected.body.error).toBe('MIN_STAY_NOT_MET');
+      
+      const outside = await book('/api/bookings/quote', '2027-11-10', '2027-11-12');
+      expect(outside.body.data.restrictions).toEqual([]);
     });
     
-    test('should return BOOKING_NOT_FOUND for invalid booking id', async () => {
-      const { status, body } = await apiRequest('/api/bookings/invalid_id/cancel', {
-        method: 'PUT',
+    test('should reject arrivals on closed days', async () => {
+      await apiRequest(`/api/hotels/${restrictedHotelId}/restrictions`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          type: 'closed_to_arrival',
+          roomId: restrictedRoomId,
+          daysOfWeek: [7],
+        }),
       });
       
-      expect(status).toBe(404);
-      expect(body.error).toBe('BOOKING_NOT_FOUND');
+      // 2027-11-07 is a Sunday
+      const { status, body } = await book('/api/bookings', '2027-11-07', '2027-11-09');
+      expect(status).toBe(400);
+      expect(body.error).toBe('CLOSED_TO_ARRIVAL');
     });
     
-    test('should return CANCELLATION_DEADLINE_PASSED for booking within 24 hours', async () => {
-      const tomorrow = new Date();
-      tomorrow.setDate(tomorrow.getDate() + 1);
-      const tomorrowStr = tomorrow.toISOString().split('T')[0];
-      
-      const dayAfter = new Date();
-      dayAfter.setDate(dayAfter.getDate() + 2);
-      const dayAfterStr = dayAfter.toISOString().split('T')[0];
-      
-      const bookingRes = await apiRequest('/api/bookings', {
+    test('should reject stays beyond the booking horizon', async () => {
+      await apiRequest(`/api/hotels/${restrictedHotelId}/restrictions`, {
         method: 'POST',
         headers: {
-          Authorization: `Bearer ${customer2Token}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
-        body: JSON.stringify({
-          roomId: room2Id,
-          checkInDate: tomorrowStr,
-          checkOutDate: dayAfterStr,
-          guests: 2,
-        }),
+        body: JSON.stringify({ type: 'booking_horizon', value: 365 }),
       });
       
-      if (bookingRes.status === 201) {
-        const { status, body } = await apiRequest(`/api/bookings/${bookingRes.body.data.id}/cancel`, {
-          method: 'PUT',
-          headers: {
-            Authorization: `Bearer ${customer2Token}`,
-          },
-        });
-        
-        expect(status).toBe(400);
-        expect(body.error).toBe('CANCELLATION_DEADLINE_PASSED');
-      }
+      const { status, body } = await book('/api/bookings', '2030-01-08', '2030-01-10');
+      expect(status).toBe(400);
+      expect(body.error).toBe('BEYOND_BOOKING_HORIZON');
     });
+  });
+  
+  describe('POST /api/hotels/:hotelId/rooms/:roomId/blocks', () => {
+    let blockHotelId: string;
+    let blockRoomId: string;
+    let blockedBookingId: string;
     
-    test('should allow cancelled room to be booked again', async () => {
-      const createRes = await apiRequest('/api/bookings', {
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
         method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
         body: JSON.stringify({
-          roomId: room2Id,
-          checkInDate: '2026-07-15',
-          checkOutDate: '2026-07-18',
-          guests: 2,
+          name: 'Renovation Hotel',
+          city: 'Shimla',
+          country: 'India',
         }),
       });
+      blockHotelId = hotelRes.body.data.id;
       
-      await apiRequest(`/api/bookings/${createRes.body.data.id}/cancel`, {
-        method: 'PUT',
+      const roomRes = await apiRequest(`/api/hotels/${blockHotelId}/rooms`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          roomNumber: '401',
+          roomType: 'Standard',
+          pricePerNight: '2500',
+          maxOccupancy: 2,
+        }),
       });
+      blockRoomId = roomRes.body.data.id;
       
-      const { status } = await apiRequest('/api/bookings', {
+      const bookingRes = await apiRequest('/api/bookings', {
         method: 'POST',
         headers: {
-          Authorization: `Bearer ${customer2Token}`,
+          Authorization: `Bearer ${customerToken}`,
         },
         body: JSON.stringify({
-          roomId: room2Id,
-          checkInDate: '2026-07-15',
-          checkOutDate: '2026-07-18',
-          guests: 2,
+          roomId: blockRoomId,
+          checkInDate: '2027-05-10',
+          checkOutDate: '2027-05-12',
+          guests: 1,
         }),
       });
-      
-      expect(status).toBe(201);
+      blockedBookingId = bookingRes.body.data.id;
     });
     
-    test('should filter cancelled bookings with status query', async () => {
-      const { status, body } = await apiRequest('/api/bookings?status=cancelled', {
+    const createBlock = (block: Record<string, unknown>, token = ownerToken) =>
+      apiRequest(`/api/hotels/${blockHotelId}/rooms/${blockRoomId}/blocks`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${token}`,
         },
+        body: JSON.stringify(block),
       });
+    
+    test('should return FORBIDDEN for customer role', async () => {
+      const { status, body } = await createBlock(
+        { startDate: '2027-06-01', endDate: '2027-06-05', reason: 'Renovation' },
+        customerToken
+      );
       
-      expect(status).toBe(200);
-      body.data.forEach((booking: any) => {
-        expect(booking.status).toBe('cancelled');
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should reject blocks over confirmed bookings and list them', async () => {
+      const { status, body } = await createBlock({
+        startDate: '2027-05-11',
+        endDate: '2027-05-15',
+        reason: 'Plumbing',
       });
+      
+      expect(status).toBe(409);
+      expect(body.error).toBe('BOOKINGS_AFFECTED');
+      expect(body.data.map((b: any) => b.id)).toEqual([blockedBookingId]);
     });
     
-    test('should set cancelledAt timestamp', async () => {
-      const createRes = await apiRequest('/api/bookings', {
+    test('should create a forced block and report affected bookings', async () => {
+      const { status, body } = await createBlock({
+        startDate: '2027-05-11',
+        endDate: '2027-05-15',
+        reason: 'Plumbing',
+        force: true,
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.affectedBookings.length).toBe(1);
+    });
+    
+    test('should make blocked dates unavailable for booking', async () => {
+      const { status, body } = await apiRequest('/api/bookings', {
         method: 'POST',
         headers: {
-          Authorization: `Bearer ${customer2Token}`,
+          Authorization: `Bearer ${customerToken}`,
         },
         body: JSON.stringify({
-          roomId: room2Id,
-          checkInDate: '2026-08-15',
-          checkOutDate: '2026-08-18',
-          guests: 2,
+          roomId: blockRoomId,
+          checkInDate: '2027-05-14',
+          checkOutDate: '2027-05-16',
+          guests: 1,
         }),
       });
       
-      const { body } = await apiRequest(`/api/bookings/${createRes.body.data.id}/cancel`, {
-        method: 'PUT',
+      expect(status).toBe(400);
+      expect(body.error).toBe('ROOM_NOT_AVAILABLE');
+    });
+    
+    test('should hide blocked rooms from date searches', async () => {
+      const blocked = await apiRequest('/api/hotels?city=Shimla&checkInDate=2027-05-13&checkOutDate=2027-05-14', {
         headers: {
-          Authorization: `Bearer ${customer2Token}`,
+          Authorization: `Bearer ${customerToken}`,
         },
       });
+      expect(blocked.body.data.some((h: any) => h.id === blockHotelId)).toBe(false);
       
-      expect(body.data.cancelledAt).toBeDefined();
+      const free = await apiRequest('/api/hotels?city=Shimla&checkInDate=2027-05-16&checkOutDate=2027-05-18', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      expect(free.body.data.some((h: any) => h.id === blockHotelId)).toBe(true);
     });
+  });
+  
+  describe('Concurrent bookings', () => {
+    let raceRoomId: string;
     
-    test('should allow cancellation more than 24 hours before checkin', async () => {
-      const createRes = await apiRequest('/api/bookings', {
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
         method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
         body: JSON.stringify({
-          roomId: room2Id,
-          checkInDate: '2026-09-15',
-          checkOutDate: '2026-09-18',
-          guests: 2,
+          name: 'Popular Hotel',
+          city: 'Manali',
+          country: 'India',
         }),
       });
       
-      const { status } = await apiRequest(`/api/bookings/${createRes.body.data.id}/cancel`, {
-        method: 'PUT',
+      const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+        method: 'POST',
         headers: {
-          Authorization: `Bearer ${customerToken}`,
+          Authorization: `Bearer ${ownerToken}`,
         },
+        body: JSON.stringify({
+          roomNumber: '501',
+          roomType: 'Standard',
+          pricePerNight: '1800',
+          maxOccupancy: 2,
+        }),
       });
-      
-      expect(status).toBe(200);
+      raceRoomId = roomRes.body.data.id;
+    });
+    
+    test('should confirm exactly one of many simultaneous overlapping bookings', async () => {
+      const attempts = Array.from({ length: 25 }, (_, i) =>
+        apiRequest('/api/bookings', {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${i % 2 === 0 ? customerToken : customer2Token}`,
+          },
+          body: JSON.stringify({
+            roomId: raceRoomId,
+            checkInDate: '2027-06-10',
+            checkOutDate: `2027-06-1${1 + (i % 4)}`,
+            guests: 1,
+          }),
+        })
+      );
+      
+      const results = await Promise.all(attempts);
+      
+      const confirmed = results.filter((r) => r.status === 201);
+      const rejected = results.filter((r) => r.status !== 201);
+      
+      expect(confirmed.length).toBe(1);
+      expect(rejected.every((r) => r.status === 400 && r.body.error === 'ROOM_NOT_AVAILABLE')).toBe(true);
+    });
+  });
+  
+  describe('POST /api/bookings/quote', () => {
+    test('should return FORBIDDEN for owner role', async () => {
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2027-09-01',
+          checkOutDate: '2027-09-03',
+          guests: 2,
+        }),
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should quote the same total that booking charges', async () => {
+      const request = {
+        roomId: room2Id,
+        checkInDate: '2027-09-01',
+        checkOutDate: '2027-09-03',
+        guests: 2,
+      };
+      
+      const quote = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(request),
+      });
+      
+      expect(quote.status).toBe(200);
+      expect(quote.body.data.available).toBe(true);
+      expect(quote.body.data.nights.length).toBe(2);
+      expect(quote.body.data).toHaveProperty('subtotal');
+      expect(quote.body.data).toHaveProperty('taxes');
+      expect(quote.body.data).toHaveProperty('fees');
+      expect(quote.body.data).toHaveProperty('discounts');
+      
+      const booking = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(request),
+      });
+      
+      expect(booking.status).toBe(201);
+      expect(booking.body.data.totalPrice).toBe(quote.body.data.totalPrice);
+    });
+    
+    test('should report unavailable dates without failing', async () => {
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2027-09-02',
+          checkOutDate: '2027-09-04',
+          guests: 2,
+        }),
+      });
+      
+      expect(status).toBe(200);
+      expect(body.data.available).toBe(false);
+    });
+    
+    test('should return INVALID_CAPACITY like booking does', async () => {
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2027-09-10',
+          checkOutDate: '2027-09-12',
+          guests: 50,
+        }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_CAPACITY');
+    });
+  });
+  
+  describe('PATCH /api/bookings/:bookingId', () => {
+    let modifyRoomId: string;
+    let modifyBookingId: string;
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Flexible Hotel',
+          city: 'Kochi',
+          country: 'India',
+        }),
+      });
+      
+      const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '601',
+          roomType: 'Standard',
+          pricePerNight: '2000',
+          maxOccupancy: 2,
+        }),
+      });
+      modifyRoomId = roomRes.body.data.id;
+      
+      const booking = async (token: string, checkInDate: string, checkOutDate: string) =>
+        apiRequest('/api/bookings', {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${token}`,
+          },
+          body: JSON.stringify({
+            roomId: modifyRoomId,
+            checkInDate,
+            checkOutDate,
+            guests: 1,
+          }),
+        });
+      
+      modifyBookingId = (await booking(customerToken, '2027-07-01', '2027-07-03')).body.data.id;
+      await booking(customer2Token, '2027-07-05', '2027-07-08');
+    });
+    
+    const modify = (changes: Record<string, unknown>, token = customerToken) =>
+      apiRequest(`/api/bookings/${modifyBookingId}`, {
+        method: 'PATCH',
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+        body: JSON.stringify(changes),
+      });
+    
+    test('should return FORBIDDEN for another customer', async () => {
+      const { status, body } = await modify({ checkOutDate: '2027-07-04' }, customer2Token);
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should reprice the stay and record the difference', async () => {
+      const { status, body } = await modify({ checkOutDate: '2027-07-04' });
+      
+      expect(status).toBe(200);
+      expect(body.data.checkOutDate).toBe('2027-07-04');
+      expect(Number(body.data.previousTotalPrice)).toBe(4000);
+      expect(Number(body.data.totalPrice)).toBe(6000);
+      expect(Number(body.data.priceDifference)).toBe(2000);
+      expect(body.data.nights.length).toBe(3);
+    });
+    
+    test('should return ROOM_NOT_AVAILABLE when new dates overlap another booking', async () => {
+      const { status, body } = await modify({ checkOutDate: '2027-07-06' });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ROOM_NOT_AVAILABLE');
+    });
+    
+    test('should return INVALID_CAPACITY for too many guests', async () => {
+      const { status, body } = await modify({ guests: 3 });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_CAPACITY');
+    });
+    
+    test('should return ROOM_NOT_IN_HOTEL for a room in another hotel', async () => {
+      const { status, body } = await modify({ roomId });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ROOM_NOT_IN_HOTEL');
+    });
+  });
+  
+  describe('POST /api/reservations', () => {
+    let groupHotelId: string;
+    let groupRoomIds: string[] = [];
+    let reservationId: string;
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Family Hotel',
+          city: 'Ooty',
+          country: 'India',
+        }),
+      });
+      groupHotelId = hotelRes.body.data.id;
+      
+      for (const [roomNumber, pricePerNight] of [['701', '1000'], ['702', '1500']]) {
+        const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+          body: JSON.stringify({
+            roomNumber,
+            roomType: 'Family',
+            pricePerNight,
+            maxOccupancy: 3,
+          }),
+        });
+        groupRoomIds.push(roomRes.body.data.id);
+      }
+    });
+    
+    const reserve = (rooms: { roomId: string; guests: number }[]) =>
+      apiRequest('/api/reservations', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          checkInDate: '2027-08-10',
+          checkOutDate: '2027-08-12',
+          rooms,
+        }),
+      });
+    
+    test('should book nothing if any room fails', async () => {
+      const { status, body } = await reserve([
+        { roomId: groupRoomIds[0], guests: 2 },
+        { roomId: groupRoomIds[1], guests: 5 },
+      ]);
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_CAPACITY');
+      
+      const single = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: groupRoomIds[0],
+          checkInDate: '2027-08-10',
+          checkOutDate: '2027-08-12',
+          guests: 2,
+        }),
+      });
+      expect(single.body.data.available).toBe(true);
+    });
+    
+    test('should return ROOM_NOT_IN_HOTEL for rooms of different hotels', async () => {
+      const { status, body } = await reserve([
+        { roomId: groupRoomIds[0], guests: 2 },
+        { roomId, guests: 1 },
+      ]);
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ROOM_NOT_IN_HOTEL');
+    });
+    
+    test('should book all rooms with combined pricing', async () => {
+      const { status, body } = await reserve([
+        { roomId: groupRoomIds[0], guests: 2 },
+        { roomId: groupRoomIds[1], guests: 3 },
+      ]);
+      
+      expect(status).toBe(201);
+      expect(body.data.status).toBe('confirmed');
+      expect(body.data.bookings.length).toBe(2);
+      expect(Number(body.data.totalPrice)).toBe(5000);
+      reservationId = body.data.id;
+    });
+    
+    test('should cancel individual rooms', async () => {
+      const reservation = await apiRequest(`/api/reservations/${reservationId}`, {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      const first = reservation.body.data.bookings[0];
+      
+      const { status, body } = await apiRequest(`/api/reservations/${reservationId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({ bookingIds: [first.id] }),
+      });
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('partially_cancelled');
+      expect(Number(body.data.totalPrice)).toBe(3000);
+    });
+    
+    test('should follow its rooms through check-in and check-out', async () => {
+      const today = new Date().toISOString().split('T')[0];
+      const tomorrow = new Date(Date.now() + 24 * 60 * 60 * 1000).toISOString().split('T')[0];
+      
+      const created = await apiRequest('/api/reservations', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          checkInDate: today,
+          checkOutDate: tomorrow,
+          rooms: groupRoomIds.map((roomId) => ({ roomId, guests: 1 })),
+        }),
+      });
+      const { id, bookings } = created.body.data;
+      
+      const frontDesk = (bookingId: string, action: string) =>
+        apiRequest(`/api/hotels/${groupHotelId}/bookings/${bookingId}/${action}`, {
+          method: 'PUT',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+        });
+      
+      const statusOf = async () =>
+        (await apiRequest(`/api/reservations/${id}`, {
+          headers: {
+            Authorization: `Bearer ${customerToken}`,
+          },
+        })).body.data.status;
+      
+      await frontDesk(bookings[0].id, 'check-in');
+      expect(await statusOf()).toBe('checked_in');
+      
+      await frontDesk(bookings[1].id, 'check-in');
+      for (const booking of bookings) {
+        await frontDesk(booking.id, 'check-out');
+      }
+      expect(await statusOf()).toBe('checked_out');
+    });
+    
+    test('should return FORBIDDEN for another customer', async () => {
+      const { status, body } = await apiRequest(`/api/reservations/${reservationId}`, {
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+  });
+  
+  describe('POST /api/bookings/hold', () => {
+    let holdRoomId: string;
+    let heldBookingId: string;
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Checkout Hotel',
+          city: 'Pune',
+          country: 'India',
+        }),
+      });
+      
+      const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '801',
+          roomType: 'Standard',
+          pricePerNight: '2000',
+          maxOccupancy: 2,
+        }),
+      });
+      holdRoomId = roomRes.body.data.id;
+    });
+    
+    const stay = () => ({
+      roomId: holdRoomId,
+      checkInDate: '2027-09-10',
+      checkOutDate: '2027-09-12',
+      guests: 2,
+    });
+    
+    test('should hold a room with an expiry', async () => {
+      const { status, body } = await apiRequest('/api/bookings/hold', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(stay()),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.status).toBe('held');
+      expect(body.data.totalPrice).toBe('4000');
+      expect(new Date(body.data.holdExpiresAt).getTime()).toBeGreaterThan(Date.now());
+      heldBookingId = body.data.id;
+    });
+    
+    test('should treat a held room as unavailable', async () => {
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify(stay()),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ROOM_NOT_AVAILABLE');
+    });
+    
+    test('should not let another customer confirm the hold', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${heldBookingId}/confirm`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should confirm a live hold', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${heldBookingId}/confirm`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('confirmed');
+    });
+    
+    test('should reject confirming a booking that is not held', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${heldBookingId}/confirm`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('BOOKING_NOT_HELD');
+    });
+  });
+  
+  describe('Payments', () => {
+    let payRoomId: string;
+    let pendingBookingId: string;
+    let pendingReference: string;
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Card Hotel',
+          city: 'Nashik',
+          country: 'India',
+        }),
+      });
+      
+      const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '901',
+          roomType: 'Standard',
+          pricePerNight: '3000',
+          maxOccupancy: 2,
+        }),
+      });
+      payRoomId = roomRes.body.data.id;
+    });
+    
+    const book = (checkInDate: string, checkOutDate: string, paymentMethod?: string) =>
+      apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: payRoomId,
+          checkInDate,
+          checkOutDate,
+          guests: 1,
+          paymentMethod,
+        }),
+      });
+    
+    test('should confirm a booking when the payment is captured immediately', async () => {
+      const { status, body } = await book('2027-10-01', '2027-10-02');
+      
+      expect(status).toBe(201);
+      expect(body.data.status).toBe('confirmed');
+      expect(body.data.holdExpiresAt).toBeNull();
+      expect(body.data.payment.status).toBe('succeeded');
+      expect(body.data.payment.amount).toBe('3000');
+    });
+    
+    test('should reject a declined payment without booking the room', async () => {
+      const declined = await book('2027-10-05', '2027-10-06', 'mock_declined');
+      
+      expect(declined.status).toBe(402);
+      expect(declined.body.error).toBe('PAYMENT_DECLINED');
+      
+      const retry = await book('2027-10-05', '2027-10-06');
+      expect(retry.status).toBe(201);
+    });
+    
+    test('should keep the booking pending until the webhook arrives', async () => {
+      const { status, body } = await book('2027-10-10', '2027-10-12', 'mock_async');
+      
+      expect(status).toBe(201);
+      expect(body.data.status).toBe('pending_payment');
+      expect(body.data.holdExpiresAt).toBeTruthy();
+      expect(body.data.payment.status).toBe('pending');
+      pendingBookingId = body.data.id;
+      pendingReference = body.data.payment.reference;
+      
+      const competing = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({
+          roomId: payRoomId,
+          checkInDate: '2027-10-11',
+          checkOutDate: '2027-10-13',
+          guests: 1,
+        }),
+      });
+      expect(competing.body.error).toBe('ROOM_NOT_AVAILABLE');
+    });
+    
+    test('should reject a webhook with a bad signature', async () => {
+      const { status, body } = await paymentWebhook('payment.succeeded', pendingReference, 'wrong');
+      
+      expect(status).toBe(401);
+      expect(body.error).toBe('INVALID_SIGNATURE');
+    });
+    
+    test('should confirm the booking on a captured payment webhook', async () => {
+      const { status, body } = await paymentWebhook('payment.succeeded', pendingReference);
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('succeeded');
+      expect(body.data.bookingStatus).toBe('confirmed');
+      
+      const list = await apiRequest('/api/bookings?status=confirmed', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      expect(list.body.data.some((b: any) => b.id === pendingBookingId)).toBe(true);
+    });
+    
+    test('should ignore a repeated webhook', async () => {
+      const { status, body } = await paymentWebhook('payment.failed', pendingReference);
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('succeeded');
+      expect(body.data.bookingStatus).toBe('confirmed');
+    });
+    
+    test('should release the room when the payment fails', async () => {
+      const pending = await book('2027-10-20', '2027-10-21', 'mock_async');
+      const { body } = await paymentWebhook('payment.failed', pending.body.data.payment.reference);
+      
+      expect(body.data.status).toBe('failed');
+      expect(body.data.bookingStatus).toBe('expired');
+      
+      const retry = await book('2027-10-20', '2027-10-21');
+      expect(retry.status).toBe(201);
+    });
+    
+    test('should refund a capture that arrives after the booking was cancelled', async () => {
+      const pending = await book('2027-10-25', '2027-10-26', 'mock_async');
+      
+      await apiRequest(`/api/bookings/${pending.body.data.id}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({}),
+      });
+      
+      const { status, body } = await paymentWebhook('payment.succeeded', pending.body.data.payment.reference);
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('succeeded');
+      expect(body.data.bookingStatus).toBe('cancelled');
+      expect(body.data.refundAmount).toBe('3000');
+      
+      const repeated = await paymentWebhook('payment.succeeded', pending.body.data.payment.reference);
+      
+      expect(repeated.body.data.refundAmount).toBe('3000');
+    });
+  });
+  
+  describe('Idempotency-Key on POST /api/bookings', () => {
+    let retryRoomId: string;
+    const idempotencyKey = `retry-${Date.now()}`;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const createWithKey = (checkOutOffset: number) =>
+      apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+          'Idempotency-Key': idempotencyKey,
+        },
+        body: JSON.stringify({
+          roomId: retryRoomId,
+          checkInDate: isoDate(40),
+          checkOutDate: isoDate(checkOutOffset),
+          guests: 1,
+        }),
+      });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Retry Hotel',
+          city: 'Kochi',
+          country: 'India',
+        }),
+      });
+      
+      const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1301',
+          roomType: 'Standard',
+          pricePerNight: '2000',
+          maxOccupancy: 2,
+        }),
+      });
+      retryRoomId = roomRes.body.data.id;
+    });
+    
+    test('should return the original booking when a request is retried', async () => {
+      const first = await createWithKey(42);
+      const retry = await createWithKey(42);
+      
+      expect(first.status).toBe(201);
+      expect(retry.status).toBe(201);
+      expect(retry.body.data.id).toBe(first.body.data.id);
+    });
+    
+    test('should reject the same key with a different request', async () => {
+      const { status, body } = await createWithKey(43);
+      
+      expect(status).toBe(409);
+      expect(body.error).toBe('IDEMPOTENCY_KEY_REUSED');
+    });
+    
+    test('should return the original reservation when a request is retried', async () => {
+      const createReservation = () =>
+        apiRequest('/api/reservations', {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${customerToken}`,
+            'Idempotency-Key': `${idempotencyKey}-reservation`,
+          },
+          body: JSON.stringify({
+            checkInDate: isoDate(44),
+            checkOutDate: isoDate(45),
+            rooms: [{ roomId: retryRoomId, guests: 1 }],
+          }),
+        });
+      
+      const first = await createReservation();
+      const retry = await createReservation();
+      
+      expect(first.status).toBe(201);
+      expect(retry.status).toBe(201);
+      expect(retry.body.data.id).toBe(first.body.data.id);
+    });
+  });
+  
+  describe('Guest details on POST /api/bookings', () => {
+    let familyHotelId: string;
+    let familyRoomId: string;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const bookWithGuests = (guests: number, guestDetails: object) =>
+      apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: familyRoomId,
+          checkInDate: isoDate(50),
+          checkOutDate: isoDate(52),
+          guests,
+          guestDetails,
+        }),
+      });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Family Hotel',
+          city: 'Mysore',
+          country: 'India',
+        }),
+      });
+      familyHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${familyHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1401',
+          roomType: 'Family',
+          pricePerNight: '3000',
+          maxOccupancy: 4,
+          maxAdults: 2,
+          maxChildren: 2,
+        }),
+      });
+      familyRoomId = roomRes.body.data.id;
+    });
+    
+    test('should reject guest counts that do not add up or exceed the room', async () => {
+      const { status, body } = await bookWithGuests(3, {
+        leadGuestName: 'Meera Iyer',
+        childrenAges: [6],
+        adults: 1,
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_GUEST_DETAILS');
+      
+      const tooManyAdults = await bookWithGuests(3, { leadGuestName: 'Meera Iyer' });
+      
+      expect(tooManyAdults.status).toBe(400);
+      expect(tooManyAdults.body.error).toBe('INVALID_CAPACITY');
+    });
+    
+    test('should reject children older than the child age limit', async () => {
+      const { status, body } = await bookWithGuests(2, {
+        leadGuestName: 'Meera Iyer',
+        childrenAges: [18],
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_GUEST_DETAILS');
+    });
+    
+    test('should reject guest details longer than they can be stored', async () => {
+      const longName = await bookWithGuests(2, { leadGuestName: 'M'.repeat(256) });
+      
+      expect(longName.status).toBe(400);
+      expect(longName.body.error).toBe('INVALID_GUEST_DETAILS');
+      
+      const longPhone = await bookWithGuests(2, {
+        leadGuestName: 'Meera Iyer',
+        phone: '9'.repeat(51),
+      });
+      
+      expect(longPhone.status).toBe(400);
+      expect(longPhone.body.error).toBe('INVALID_GUEST_DETAILS');
+    });
+    
+    test('should store guest details and show them to the owner', async () => {
+      const { status, body } = await bookWithGuests(4, {
+        leadGuestName: 'Meera Iyer',
+        phone: '+91 98450 00000',
+        childrenAges: [6, 9],
+        estimatedArrival: '21:15',
+        specialRequests: 'Late check-in',
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.guestDetails.adults).toBe(2);
+      expect(body.data.guestDetails.childrenAges).toEqual([6, 9]);
+      
+      const ownerView = await apiRequest(`/api/hotels/${familyHotelId}/bookings`, {
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      expect(ownerView.status).toBe(200);
+      const booking = ownerView.body.data.find((b: any) => b.id === body.data.id);
+      expect(booking.guestDetails.leadGuestName).toBe('Meera Iyer');
+      expect(booking.guestDetails.estimatedArrival).toBe('21:15');
+      expect(booking.guestDetails.specialRequests).toBe('Late check-in');
+    });
+  });
+  
+  describe('Promo codes', () => {
+    let promoHotelId: string;
+    let promoRoomId: string;
+    let promoBookingId: string;
+    const promoCode = `SPRING${Date.now()}`;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const stayWithCode = (checkIn: number, checkOut: number, code = promoCode) => ({
+      roomId: promoRoomId,
+      checkInDate: isoDate(checkIn),
+      checkOutDate: isoDate(checkOut),
+      guests: 1,
+      promoCode: code,
+    });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Promo Hotel',
+          city: 'Jaipur',
+          country: 'India',
+        }),
+      });
+      promoHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${promoHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1601',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
+      });
+      promoRoomId = roomRes.body.data.id;
+    });
+    
+    test('should let an owner create a code for their hotel', async () => {
+      const { status, body } = await apiRequest('/api/promo-codes', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          code: promoCode,
+          hotelId: promoHotelId,
+          discountType: 'percent',
+          amount: '10',
+          minNights: 2,
+          maxUsesPerUser: 1,
+        }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.uses).toBe(0);
+    });
+    
+    test('should not let customers create codes', async () => {
+      const { status } = await apiRequest('/api/promo-codes', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({ code: 'FREE', discountType: 'percent', amount: '100' }),
+      });
+      
+      expect(status).toBe(403);
+    });
+    
+    test('should reject stays shorter than the minimum nights', async () => {
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(stayWithCode(70, 71)),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('PROMO_CODE_NOT_APPLICABLE');
+    });
+    
+    test('should reject unknown codes', async () => {
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(stayWithCode(70, 72, 'NOT-A-CODE')),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_PROMO_CODE');
+    });
+    
+    test('should discount the booking and store the discount', async () => {
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(stayWithCode(70, 72, promoCode.toLowerCase())),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.subtotal).toBe('2000');
+      expect(body.data.discounts).toHaveLength(1);
+      expect(body.data.discounts[0].amount).toBe('200');
+      expect(body.data.totalPrice).toBe('1800');
+      promoBookingId = body.data.id;
+    });
+    
+    test('should enforce the per-customer cap', async () => {
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(stayWithCode(80, 82)),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('PROMO_CODE_EXHAUSTED');
+    });
+    
+    test('should give the use back when the booking is cancelled', async () => {
+      const cancelled = await apiRequest(`/api/bookings/${promoBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      expect(cancelled.status).toBe(200);
+      
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify(stayWithCode(80, 82)),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.totalPrice).toBe('1800');
+    });
+  });
+  
+  describe('GET /api/bookings/:bookingId/invoice', () => {
+    let invoiceHotelId: string;
+    let invoiceRoomId: string;
+    let invoiceBookingId: string;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const getInvoice = (token = customerToken) =>
+      apiRequest(`/api/bookings/${invoiceBookingId}/invoice`, {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+      });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Invoice Hotel',
+          city: 'Pune',
+          country: 'India',
+        }),
+      });
+      invoiceHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${invoiceHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1801',
+          roomType: 'Standard',
+          pricePerNight: '1500',
+          maxOccupancy: 2,
+        }),
+      });
+      invoiceRoomId = roomRes.body.data.id;
+      
+      await apiRequest(`/api/hotels/${invoiceHotelId}/billing-details`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          legalName: 'Pune Hospitality Pvt Ltd',
+          address: '12 FC Road, Pune',
+          taxId: 'GSTIN-27ABCDE',
+        }),
+      });
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: invoiceRoomId,
+          checkInDate: isoDate(75),
+          checkOutDate: isoDate(77),
+          guests: 1,
+          guestDetails: { leadGuestName: 'Asha Rao' },
+        }),
+      });
+      invoiceBookingId = bookingRes.body.data.id;
+    });
+    
+    test('should issue a numbered invoice with the billing details and stay', async () => {
+      const { status, body } = await getInvoice();
+      
+      expect(status).toBe(200);
+      expect(body.data.kind).toBe('invoice');
+      expect(body.data.number).toBe('INV-000001');
+      expect(body.data.seller.name).toBe('Pune Hospitality Pvt Ltd');
+      expect(body.data.seller.taxId).toBe('GSTIN-27ABCDE');
+      expect(body.data.leadGuestName).toBe('Asha Rao');
+      expect(body.data.lines.filter((l: any) => l.kind === 'night')).toHaveLength(2);
+      expect(parseFloat(body.data.total)).toBe(3000);
+      expect(body.data.creditNotes).toHaveLength(0);
+    });
+    
+    test('should return the same invoice to the hotel owner', async () => {
+      const { status, body } = await getInvoice(ownerToken);
+      
+      expect(status).toBe(200);
+      expect(body.data.number).toBe('INV-000001');
+    });
+    
+    test('should not show the invoice to other customers', async () => {
+      const { status } = await getInvoice(customer2Token);
+      
+      expect(status).toBe(403);
+    });
+    
+    test('should render the invoice as HTML', async () => {
+      const response = await fetch(`${BASE_URL}/api/bookings/${invoiceBookingId}/invoice?format=html`, {
+        headers: { Authorization: `Bearer ${customerToken}` },
+      });
+      const html = await response.text();
+      
+      expect(response.status).toBe(200);
+      expect(response.headers.get('content-type')).toContain('text/html');
+      expect(html).toContain('INV-000001');
+      expect(html).toContain('Pune Hospitality Pvt Ltd');
+    });
+    
+    test('should issue a credit note when the booking is refunded', async () => {
+      await apiRequest(`/api/bookings/${invoiceBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({}),
+      });
+      
+      const { status, body } = await getInvoice();
+      
+      expect(status).toBe(200);
+      expect(body.data.number).toBe('INV-000001');
+      expect(body.data.creditNotes).toHaveLength(1);
+      expect(body.data.creditNotes[0].number).toBe('CN-000001');
+      expect(body.data.creditNotes[0].correctsInvoice).toBe('INV-000001');
+      expect(parseFloat(body.data.creditNotes[0].total)).toBe(3000);
+    });
+  });
+  
+  describe('GET /api/bookings', () => {
+    test('should return UNAUTHORIZED without token', async () => {
+      const { status, body } = await apiRequest('/api/bookings');
+      
+      expect(status).toBe(401);
+      expect(body.error).toBe('UNAUTHORIZED');
+    });
+    
+    test('should return all bookings for current user', async () => {
+      const { status, body } = await apiRequest('/api/bookings', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      expect(body.success).toBe(true);
+      expect(Array.isArray(body.data)).toBe(true);
+      expect(body.data.length).toBeGreaterThan(0);
+      
+      const booking = body.data[0];
+      expect(booking).toHaveProperty('id');
+      expect(booking).toHaveProperty('hotelName');
+      expect(booking).toHaveProperty('roomNumber');
+      expect(booking).toHaveProperty('roomType');
+    });
+    
+    test('should filter bookings by status=confirmed', async () => {
+      const { status, body } = await apiRequest('/api/bookings?status=confirmed', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      body.data.forEach((booking: any) => {
+        expect(booking.status).toBe('confirmed');
+      });
+    });
+    
+    test('should return only current users bookings', async () => {
+      const { body: body1 } = await apiRequest('/api/bookings', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      const { body: body2 } = await apiRequest('/api/bookings', {
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      expect(body1.data.length).toBeGreaterThan(0);
+      expect(body2.data.length).toBeGreaterThan(0);
+      
+      const booking1Ids = body1.data.map((b: any) => b.id);
+      const booking2Ids = body2.data.map((b: any) => b.id);
+      
+      const hasOverlap = booking1Ids.some((id: string) => booking2Ids.includes(id));
+      expect(hasOverlap).toBe(false);
+    });
+    
+    test('should include all required fields in booking response', async () => {
+      const { body } = await apiRequest('/api/bookings', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      const booking = body.data[0];
+      expect(booking).toHaveProperty('roomId');
+      expect(booking).toHaveProperty('hotelId');
+      expect(booking).toHaveProperty('checkInDate');
+      expect(booking).toHaveProperty('checkOutDate');
+      expect(booking).toHaveProperty('guests');
+      expect(booking).toHaveProperty('totalPrice');
+      expect(booking).toHaveProperty('status');
+      expect(booking).toHaveProperty('bookingDate');
+    });
+    
+    test('should return empty array for user with no bookings', async () => {
+      const newCustomer = await apiRequest('/api/auth/signup', {
+        method: 'POST',
+        body: JSON.stringify({
+          name: 'No Bookings Customer',
+          email: `nobookings${Date.now()}@example.com`,
+          password: 'pass123',
+          role: 'customer',
+        }),
+      });
+      
+      const loginRes = await apiRequest('/api/auth/login', {
+        method: 'POST',
+        body: JSON.stringify({
+          email: newCustomer.body.data.email,
+          password: 'pass123',
+        }),
+      });
+      
+      const { status, body } = await apiRequest('/api/bookings', {
+        headers: {
+          Authorization: `Bearer ${loginRes.body.data.token}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      expect(body.data).toEqual([]);
+    });
+  });
+  
+  describe('Cancellation policies', () => {
+    let policyHotelId: string;
+    let flexiblePolicyId: string;
+    let nonRefundablePolicyId: string;
+    let policyRoomId: string;
+    
+    const daysFromNow = (days: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + days);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const book = (checkInDate: string, checkOutDate: string) =>
+      apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: policyRoomId,
+          checkInDate,
+          checkOutDate,
+          guests: 1,
+        }),
+      });
+    
+    const cancel = (id: string) =>
+      apiRequest(`/api/bookings/${id}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Policy Hotel',
+          city: 'Mysore',
+          country: 'India',
+        }),
+      });
+      policyHotelId = hotelRes.body.data.id;
+    });
+    
+    test('should create a free-until-N-days policy with a late penalty', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${policyHotelId}/cancellation-policies`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Flexible',
+          freeCancellationDays: 7,
+          penaltyPercent: '25',
+        }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.nonRefundable).toBe(false);
+      flexiblePolicyId = body.data.id;
+    });
+    
+    test('should create a non-refundable policy', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${policyHotelId}/cancellation-policies`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Non-refundable',
+          nonRefundable: true,
+        }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.penaltyPercent).toBe('100');
+      expect(body.data.nonRefundable).toBe(true);
+      nonRefundablePolicyId = body.data.id;
+    });
+    
+    test('should reject a non-refundable policy with a free window', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${policyHotelId}/cancellation-policies`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Confused',
+          nonRefundable: true,
+          freeCancellationDays: 3,
+        }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+    
+    test('should attach policies to a room and to a rate', async () => {
+      const roomRes = await apiRequest(`/api/hotels/${policyHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1001',
+          roomType: 'Deluxe',
+          pricePerNight: '4000',
+          maxOccupancy: 2,
+          cancellationPolicyId: flexiblePolicyId,
+        }),
+      });
+      
+      expect(roomRes.status).toBe(201);
+      expect(roomRes.body.data.cancellationPolicyId).toBe(flexiblePolicyId);
+      policyRoomId = roomRes.body.data.id;
+      
+      const rateRes = await apiRequest(`/api/hotels/${policyHotelId}/rooms/${policyRoomId}/rates`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          type: 'date_range',
+          startDate: '2027-12-20',
+          endDate: '2027-12-31',
+          pricePerNight: '6000',
+          cancellationPolicyId: nonRefundablePolicyId,
+        }),
+      });
+      
+      expect(rateRes.status).toBe(201);
+      expect(rateRes.body.data.cancellationPolicyId).toBe(nonRefundablePolicyId);
+    });
+    
+    test('should refund in full inside the free window', async () => {
+      const booking = await book('2027-11-01', '2027-11-03');
+      expect(booking.body.data.cancellationPolicy.name).toBe('Flexible');
+      
+      const { status, body } = await cancel(booking.body.data.id);
+      
+      expect(status).toBe(200);
+      expect(body.data.refundAmount).toBe('8000');
+      expect(body.data.penaltyAmount).toBe('0');
+    });
+    
+    test('should keep the penalty after the free window', async () => {
+      const booking = await book(daysFromNow(3), daysFromNow(4));
+      const { status, body } = await cancel(booking.body.data.id);
+      
+      expect(status).toBe(200);
+      expect(body.data.cancellationPolicy).toBe('Flexible');
+      expect(body.data.refundAmount).toBe('3000');
+      expect(body.data.penaltyAmount).toBe('1000');
+    });
+    
+    test('should refund nothing under a non-refundable rate', async () => {
+      const booking = await book('2027-12-24', '2027-12-25');
+      expect(booking.body.data.cancellationPolicy.nonRefundable).toBe(true);
+      
+      const { status, body } = await cancel(booking.body.data.id);
+      
+      expect(status).toBe(200);
+      expect(body.data.refundAmount).toBe('0');
+      expect(body.data.penaltyAmount).toBe('6000');
+    });
+    
+    test('should not change a booking after its free window', async () => {
+      const booking = await book(daysFromNow(5), daysFromNow(6));
+      
+      const { status, body } = await apiRequest(`/api/bookings/${booking.body.data.id}`, {
+        method: 'PATCH',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({ checkOutDate: daysFromNow(7) }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('MODIFICATION_DEADLINE_PASSED');
+    });
+    
+    test('should keep the terms a booking was made under', async () => {
+      const booking = await book('2027-10-10', '2027-10-11');
+      
+      await apiRequest(`/api/hotels/${policyHotelId}/cancellation-policies/${flexiblePolicyId}`, {
+        method: 'DELETE',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      const { body } = await cancel(booking.body.data.id);
+      expect(body.data.cancellationPolicy).toBe('Flexible');
+      expect(body.data.refundAmount).toBe('4000');
+    });
+  });
+  
+  describe('Waitlist', () => {
+    let waitHotelId: string;
+    let waitRoomId: string;
+    let takenBookingId: string;
+    let entryId: string;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const stay = () => ({
+      checkInDate: isoDate(60),
+      checkOutDate: isoDate(62),
+      guests: 2,
+    });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Waitlist Hotel',
+          city: 'Pune',
+          country: 'India',
+        }),
+      });
+      waitHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${waitHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1501',
+          roomType: 'Suite',
+          pricePerNight: '5000',
+          maxOccupancy: 2,
+        }),
+      });
+      waitRoomId = roomRes.body.data.id;
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({ roomId: waitRoomId, ...stay() }),
+      });
+      takenBookingId = bookingRes.body.data.id;
+    });
+    
+    test('should let a customer wait for a sold-out room type', async () => {
+      const soldOut = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({ roomId: waitRoomId, ...stay() }),
+      });
+      
+      expect(soldOut.body.error).toBe('ROOM_NOT_AVAILABLE');
+      
+      const { status, body } = await apiRequest('/api/waitlist', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({ hotelId: waitHotelId, roomType: 'suite', ...stay() }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.status).toBe('waiting');
+      entryId = body.data.id;
+    });
+    
+    test('should reject joining twice for the same stay', async () => {
+      const { status, body } = await apiRequest('/api/waitlist', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({ hotelId: waitHotelId, roomType: 'Suite', ...stay() }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ALREADY_WAITLISTED');
+    });
+    
+    test('should hold the freed room for the first customer waiting', async () => {
+      const cancel = await apiRequest(`/api/bookings/${takenBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(cancel.status).toBe(200);
+      
+      const { body } = await apiRequest('/api/users/me/waitlist', {
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      const entry = body.data.find((e: any) => e.id === entryId);
+      expect(entry.status).toBe('offered');
+      expect(entry.offeredBookingId).toBeTruthy();
+      expect(entry.offerExpiresAt).toBeTruthy();
+      
+      const notifications = await apiRequest('/api/users/me/notifications', {
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      expect(
+        notifications.body.data.some(
+          (n: any) => n.type === 'waitlist_offer' && n.bookingId === entry.offeredBookingId
+        )
+      ).toBe(true);
+    });
+  });
+  
+  describe('PUT /api/bookings/:bookingId/cancel', () => {
+    let cancelTestBookingId: string;
+    
+    beforeAll(async () => {
+      const res = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2026-12-15',
+          checkOutDate: '2026-12-18',
+          guests: 2,
+        }),
+      });
+      cancelTestBookingId = res.body.data.id;
+    });
+    
+    test('should return UNAUTHORIZED without token', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${cancelTestBookingId}/cancel`, {
+        method: 'PUT',
+      });
+      
+      expect(status).toBe(401);
+      expect(body.error).toBe('UNAUTHORIZED');
+    });
+    
+    test('should cancel booking successfully', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${cancelTestBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      expect(body.success).toBe(true);
+      expect(body.data.id).toBe(cancelTestBookingId);
+      expect(body.data.status).toBe('cancelled');
+      expect(body.data).toHaveProperty('cancelledAt');
+    });
+    
+    test('should return ALREADY_CANCELLED when cancelling again', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${cancelTestBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ALREADY_CANCELLED');
+    });
+    
+    test('should return FORBIDDEN when cancelling another users booking', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${bookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should return BOOKING_NOT_FOUND for invalid booking id', async () => {
+      const { status, body } = await apiRequest('/api/bookings/invalid_id/cancel', {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(404);
+      expect(body.error).toBe('BOOKING_NOT_FOUND');
+    });
+    
+    test('should return CANCELLATION_DEADLINE_PASSED for booking within 24 hours', async () => {
+      const tomorrow = new Date();
+      tomorrow.setDate(tomorrow.getDate() + 1);
+      const tomorrowStr = tomorrow.toISOString().split('T')[0];
+      
+      const dayAfter = new Date();
+      dayAfter.setDate(dayAfter.getDate() + 2);
+      const dayAfterStr = dayAfter.toISOString().split('T')[0];
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: tomorrowStr,
+          checkOutDate: dayAfterStr,
+          guests: 2,
+        }),
+      });
+      
+      if (bookingRes.status === 201) {
+        const { status, body } = await apiRequest(`/api/bookings/${bookingRes.body.data.id}/cancel`, {
+          method: 'PUT',
+          headers: {
+            Authorization: `Bearer ${customer2Token}`,
+          },
+        });
+        
+        expect(status).toBe(400);
+        expect(body.error).toBe('CANCELLATION_DEADLINE_PASSED');
+      }
+    });
+    
+    test('should allow cancelled room to be booked again', async () => {
+      const createRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2026-07-15',
+          checkOutDate: '2026-07-18',
+          guests: 2,
+        }),
+      });
+      
+      await apiRequest(`/api/bookings/${createRes.body.data.id}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      const { status } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2026-07-15',
+          checkOutDate: '2026-07-18',
+          guests: 2,
+        }),
+      });
+      
+      expect(status).toBe(201);
+    });
+    
+    test('should filter cancelled bookings with status query', async () => {
+      const { status, body } = await apiRequest('/api/bookings?status=cancelled', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      body.data.forEach((booking: any) => {
+        expect(booking.status).toBe('cancelled');
+      });
+    });
+    
+    test('should set cancelledAt timestamp', async () => {
+      const createRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2026-08-15',
+          checkOutDate: '2026-08-18',
+          guests: 2,
+        }),
+      });
+      
+      const { body } = await apiRequest(`/api/bookings/${createRes.body.data.id}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+      
+      expect(body.data.cancelledAt).toBeDefined();
+    });
+    
+    test('should allow cancellation more than 24 hours before checkin', async () => {
+      const createRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: room2Id,
+          checkInDate: '2026-09-15',
+          checkOutDate: '2026-09-18',
+          guests: 2,
+        }),
+      });
+      
+      const { status } = await apiRequest(`/api/bookings/${createRes.body.data.id}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+    });
+  });
+  
+  describe('PUT /api/hotels/:hotelId/bookings/:bookingId/cancel', () => {
+    let closingHotelId: string;
+    let closingBookingId: string;
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Closing Hotel',
+          city: 'Shimla',
+          country: 'India',
+        }),
+      });
+      closingHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${closingHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1101',
+          roomType: 'Standard',
+          pricePerNight: '2500',
+          maxOccupancy: 2,
+        }),
+      });
+      
+      const tomorrow = new Date();
+      tomorrow.setDate(tomorrow.getDate() + 1);
+      const dayAfter = new Date();
+      dayAfter.setDate(dayAfter.getDate() + 3);
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: roomRes.body.data.id,
+          checkInDate: tomorrow.toISOString().split('T')[0],
+          checkOutDate: dayAfter.toISOString().split('T')[0],
+          guests: 2,
+        }),
+      });
+      closingBookingId = bookingRes.body.data.id;
+    });
+    
+    const cancelAsHotel = (token: string, body: object) =>
+      apiRequest(`/api/hotels/${closingHotelId}/bookings/${closingBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+        body: JSON.stringify(body),
+      });
+    
+    test('should not let customers use the hotel cancellation', async () => {
+      const { status, body } = await cancelAsHotel(customerToken, { reason: 'Changed my mind' });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should require a reason', async () => {
+      const { status, body } = await cancelAsHotel(ownerToken, { reason: '  ' });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+    
+    test('should reject a reason that is too long', async () => {
+      const { status, body } = await cancelAsHotel(ownerToken, { reason: 'x'.repeat(1001) });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_REQUEST');
+    });
+    
+    test('should cancel with a full refund even inside the customer deadline', async () => {
+      const { status, body } = await cancelAsHotel(ownerToken, { reason: 'Water damage' });
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('cancelled');
+      expect(body.data.refundAmount).toBe('5000');
+      expect(body.data.penaltyAmount).toBe('0');
+    });
+    
+    test('should show who cancelled and why to the customer', async () => {
+      const { body } = await apiRequest('/api/bookings?status=cancelled', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      const booking = body.data.find((b: any) => b.id === closingBookingId);
+      expect(booking.cancelledBy).toBe('hotel');
+      expect(booking.cancellationReason).toBe('Water damage');
+    });
+    
+    test('should notify the customer', async () => {
+      const { status, body } = await apiRequest('/api/users/me/notifications', {
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      const notification = body.data.find((n: any) => n.bookingId === closingBookingId);
+      expect(notification.type).toBe('booking_cancelled_by_hotel');
+      expect(notification.message).toContain('Water damage');
+    });
+  });
+  
+  describe('Front desk lifecycle', () => {
+    let deskHotelId: string;
+    let deskRoomId: string;
+    let stayBookingId: string;
+    let laterBookingId: string;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const bookStay = async (checkIn: number, checkOut: number) => {
+      const res = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: deskRoomId,
+          checkInDate: isoDate(checkIn),
+          checkOutDate: isoDate(checkOut),
+          guests: 1,
+        }),
+      });
+      return res.body.data.id;
+    };
+    
+    const frontDesk = (bookingId: string, action: string, token = ownerToken) =>
+      apiRequest(`/api/hotels/${deskHotelId}/bookings/${bookingId}/${action}`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+      });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Front Desk Hotel',
+          city: 'Ooty',
+          country: 'India',
+        }),
+      });
+      deskHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${deskHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1201',
+          roomType: 'Standard',
+          pricePerNight: '1800',
+          maxOccupancy: 2,
+        }),
+      });
+      deskRoomId = roomRes.body.data.id;
+      
+      stayBookingId = await bookStay(0, 2);
+      laterBookingId = await bookStay(10, 12);
+    });
+    
+    test('should not let customers use the front desk', async () => {
+      const { status, body } = await frontDesk(stayBookingId, 'check-in', customerToken);
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should refuse check-in before the check-in date', async () => {
+      const { status, body } = await frontDesk(laterBookingId, 'check-in');
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('TOO_EARLY');
+    });
+    
+    test('should not allow review before check-out', async () => {
+      const { status, body } = await apiRequest('/api/reviews', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          bookingId: stayBookingId,
+          rating: 4,
+          comment: 'Nice stay',
+        }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('BOOKING_NOT_ELIGIBLE');
+    });
+    
+    test('should check the guest in', async () => {
+      const { status, body } = await frontDesk(stayBookingId, 'check-in');
+      
+      expect(status).toBe(200);
+      expect(body.data.status).toBe('checked_in');
+      expect(body.data.checkedInAt).toBeTruthy();
+    });
+    
+    test('should reject a no-show after check-in', async () => {
+      const { status, body } = await frontDesk(stayBookingId, 'no-show');
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_STATUS_TRANSITION');
+    });
+    
+    test('should check the guest out and allow a review', async () => {
+      const checkOut = await frontDesk(stayBookingId, 'check-out');
+      
+      expect(checkOut.status).toBe(200);
+      expect(checkOut.body.data.status).toBe('checked_out');
+      expect(checkOut.body.data.checkedOutAt).toBeTruthy();
+      
+      const { status } = await apiRequest('/api/reviews', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          bookingId: stayBookingId,
+          rating: 4,
+          comment: 'Nice stay',
+        }),
+      });
+      
+      expect(status).toBe(201);
+    });
+    
+    test('should not cancel a completed stay', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${stayBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_STATUS_TRANSITION');
+    });
+    
+    test('should not modify a completed stay', async () => {
+      const { status, body } = await apiRequest(`/api/bookings/${stayBookingId}`, {
+        method: 'PATCH',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({ guests: 2 }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_STATUS_TRANSITION');
+    });
+  });
+  
+  describe('Loyalty points', () => {
+    let loyaltyHotelId: string;
+    let loyaltyRoomId: string;
+    let startingBalance: number;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const stay = (checkIn: number, checkOut: number, loyaltyPoints?: number) => ({
+      roomId: loyaltyRoomId,
+      checkInDate: isoDate(checkIn),
+      checkOutDate: isoDate(checkOut),
+      guests: 1,
+      loyaltyPoints,
+    });
+    
+    const getLoyalty = () =>
+      apiRequest('/api/users/me/loyalty', {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+      });
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Loyalty Hotel',
+          city: 'Kochi',
+          country: 'India',
+        }),
+      });
+      loyaltyHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${loyaltyHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1701',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
+      });
+      loyaltyRoomId = roomRes.body.data.id;
+      
+      startingBalance = (await getLoyalty()).body.data.balance;
+    });
+    
+    test('should earn points when a stay is checked out', async () => {
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify(stay(0, 1)),
+      });
+      const bookingId = bookingRes.body.data.id;
+      
+      for (const action of ['check-in', 'check-out']) {
+        await apiRequest(`/api/hotels/${loyaltyHotelId}/bookings/${bookingId}/${action}`, {
+          method: 'PUT',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+        });
+      }
+      
+      const { status, body } = await getLoyalty();
+      
+      expect(status).toBe(200);
+      expect(body.data.balance).toBeGreaterThanOrEqual(startingBalance + 1000);
+      expect(body.data.completedStays).toBeGreaterThanOrEqual(1);
+      expect(body.data.tier).toBeDefined();
+      expect(body.data.transactions[0].kind).toBe('earned');
+      expect(body.data.transactions[0].bookingId).toBe(bookingId);
+    });
+    
+    test('should reject spending more points than the balance', async () => {
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify(stay(70, 71, 10000000)),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INSUFFICIENT_POINTS');
+    });
+    
+    test('should not spend points in a currency without an exchange rate', async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Unrated Hotel',
+          city: 'Kochi',
+          country: 'India',
+          currency: 'XTS',
+        }),
+      });
+      
+      const roomRes = await apiRequest(`/api/hotels/${hotelRes.body.data.id}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1703',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
+      });
+      
+      const { status, body } = await apiRequest('/api/bookings/quote', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({ ...stay(70, 71, 1), roomId: roomRes.body.data.id }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('POINTS_CURRENCY_UNSUPPORTED');
+    });
+    
+    test('should discount a booking and give the points back on cancellation', async () => {
+      const before = (await getLoyalty()).body.data.balance;
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify(stay(70, 71, 500)),
+      });
+      
+      expect(bookingRes.status).toBe(201);
+      expect(bookingRes.body.data.discounts[0].name).toBe('Loyalty points');
+      expect(parseFloat(bookingRes.body.data.discounts[0].amount)).toBe(5);
+      expect((await getLoyalty()).body.data.balance).toBe(before - 500);
+      
+      await apiRequest(`/api/bookings/${bookingRes.body.data.id}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({}),
+      });
+      
+      const { body } = await getLoyalty();
+      
+      expect(body.data.balance).toBe(before);
+      expect(body.data.transactions[0].kind).toBe('reversed');
+      expect(body.data.transactions[0].points).toBe(500);
+    });
+    
+    test('should return the points a shorter stay cannot use', async () => {
+      const roomRes = await apiRequest(`/api/hotels/${loyaltyHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1702',
+          roomType: 'Single',
+          pricePerNight: '5',
+          maxOccupancy: 1,
+        }),
+      });
+      
+      const before = (await getLoyalty()).body.data.balance;
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({ ...stay(115, 117, 1000), roomId: roomRes.body.data.id }),
+      });
+      
+      expect(bookingRes.status).toBe(201);
+      expect(parseFloat(bookingRes.body.data.totalPrice)).toBe(0);
+      
+      const { status, body } = await apiRequest(`/api/bookings/${bookingRes.body.data.id}`, {
+        method: 'PATCH',
+        headers: {
+          Authorization: `Bearer ${customer2Token}`,
+        },
+        body: JSON.stringify({
+          checkOutDate: isoDate(116),
+        }),
+      });
+      
+      expect(status).toBe(200);
+      expect(parseFloat(body.data.totalPrice)).toBe(0);
+      expect(parseFloat(body.data.discounts[0].amount)).toBe(5);
+      expect(body.data.pointsReturned).toBe(500);
+      
+      const loyalty = (await getLoyalty()).body.data;
+      
+      expect(loyalty.balance).toBe(before - 500);
+      expect(loyalty.transactions[0].kind).toBe('returned');
+      expect(loyalty.transactions[0].points).toBe(500);
+    });
+  });
+  
+  describe('Room calendars', () => {
+    let calendarHotelId: string;
+    let calendarRoomId: string;
+    let calendarServer: Server;
+    let calendarUrl: string;
+    let remoteEvents: string[] = [];
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const icalDate = (offset: number) => isoDate(offset).replace(/-/g, '');
+    
+    const remoteEvent = (uid: string, start: number, end: number) =>
+      ['BEGIN:VEVENT', `UID:${uid}`, `DTSTART;VALUE=DATE:${icalDate(start)}`, `DTEND;VALUE=DATE:${icalDate(end)}`, 'END:VEVENT'].join('\r\n');
+    
+    const calendarPath = (suffix = '') =>
+      `/api/hotels/${calendarHotelId}/rooms/${calendarRoomId}/calendar${suffix}`;
+    
+    beforeAll(async () => {
+      calendarServer = createServer((req, res) => {
+        if (req.url === '/endless.ics') {
+          // No Content-Length, so only a running count can stop it.
+          res.writeHead(200, { 'Content-Type': 'text/calendar' });
+          const chunk = 'X'.repeat(64 * 1024);
+          const write = (left: number) => {
+            if (left === 0 || res.destroyed) {
+              res.end();
+              return;
+            }
+            res.write(chunk, () => write(left - 1));
+          };
+          write(64);
+          return;
+        }
+        res.writeHead(200, { 'Content-Type': 'text/calendar' });
+        res.end(['BEGIN:VCALENDAR', 'VERSION:2.0', ...remoteEvents, 'END:VCALENDAR', ''].join('\r\n'));
+      });
+      await new Promise<void>((resolve) => calendarServer.listen(0, '127.0.0.1', resolve));
+      calendarUrl = `http://127.0.0.1:${(calendarServer.address() as AddressInfo).port}/room.ics`;
+      
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Calendar Hotel',
+          city: 'Shimla',
+          country: 'India',
+        }),
+      });
+      calendarHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${calendarHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1801',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
+      });
+      calendarRoomId = roomRes.body.data.id;
+    });
+    
+    afterAll(() => {
+      calendarServer.close();
+    });
+    
+    test('should export confirmed bookings in the room feed', async () => {
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: calendarRoomId,
+          checkInDate: isoDate(85),
+          checkOutDate: isoDate(87),
+          guests: 1,
+        }),
+      });
+      
+      const feedRes = await apiRequest(calendarPath('/feed'), {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      expect(feedRes.status).toBe(200);
+      
+      const token = feedRes.body.data.url.split('/').pop();
+      const response = await fetch(`${BASE_URL}/api/calendars/${token}`);
+      const text = await response.text();
+      
+      expect(response.status).toBe(200);
+      expect(response.headers.get('content-type')).toContain('text/calendar');
+      expect(text).toContain('BEGIN:VCALENDAR');
+      expect(text).toContain(`UID:booking-${bookingRes.body.data.id}`);
+      expect(text).toContain(`DTSTART;VALUE=DATE:${icalDate(85)}`);
+      expect(text).toContain(`DTEND;VALUE=DATE:${icalDate(87)}`);
+    });
+    
+    test('should stop serving the old feed URL after rotation', async () => {
+      const before = await apiRequest(calendarPath('/feed'), {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      const rotated = await apiRequest(calendarPath('/feed/rotate'), {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      expect(rotated.status).toBe(200);
+      expect(rotated.body.data.url).not.toBe(before.body.data.url);
+      
+      const { status, body } = await apiRequest(`/api/calendars/${before.body.data.url.split('/').pop()}`, {
+        method: 'GET',
+      });
+      
+      expect(status).toBe(404);
+      expect(body.error).toBe('CALENDAR_NOT_FOUND');
+    });
+    
+    test('should block nights imported from another calendar', async () => {
+      remoteEvents = [remoteEvent('remote-1', 90, 92)];
+      
+      const importRes = await apiRequest(calendarPath('/imports'), {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({ name: 'Other platform', url: calendarUrl }),
+      });
+      
+      expect(importRes.status).toBe(201);
+      expect(importRes.body.data.lastSyncedAt).not.toBeNull();
+      expect(importRes.body.data.lastError).toBeNull();
+      
+      const { status, body } = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: calendarRoomId,
+          checkInDate: isoDate(91),
+          checkOutDate: isoDate(93),
+          guests: 1,
+        }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('ROOM_NOT_AVAILABLE');
+    });
+    
+    test('should reject importing the same calendar twice', async () => {
+      const { status, body } = await apiRequest(calendarPath('/imports'), {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({ name: 'Again', url: calendarUrl }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('CALENDAR_IMPORT_EXISTS');
+    });
+    
+    test('should release nights removed from the imported calendar on sync', async () => {
+      const imports = await apiRequest(calendarPath('/imports'), {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      const importId = imports.body.data[0].id;
+      
+      remoteEvents = [];
+      
+      const { status, body } = await apiRequest(calendarPath(`/imports/${importId}/sync`), {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+      
+      expect(status).toBe(200);
+      expect(body.data.events).toBe(0);
+      expect(body.data.removed).toBe(1);
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: calendarRoomId,
+          checkInDate: isoDate(91),
+          checkOutDate: isoDate(93),
+          guests: 1,
+        }),
+      });
+      
+      expect(bookingRes.status).toBe(201);
+    });
+    
+    test('should not let customers manage room calendars', async () => {
+      const { status, body } = await apiRequest(calendarPath('/imports'), {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should reject calendars on private addresses', async () => {
+      const { status, body } = await apiRequest(calendarPath('/imports'), {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({ name: 'Metadata', url: 'http://169.254.169.254/latest/meta-data' }),
+      });
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_CALENDAR_URL');
+    });
+    
+    test('should stop reading a calendar past the size cap', async () => {
+      const { status, body } = await apiRequest(calendarPath('/imports'), {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({ name: 'Endless', url: calendarUrl.replace('/room.ics', '/endless.ics') }),
+      });
+      
+      expect(status).toBe(201);
+      expect(body.data.lastSyncedAt).toBeNull();
+      expect(body.data.lastError).toContain('larger than');
+    });
+  });
+  
+  // The receivers below listen on 127.0.0.1, so the server must run with
+  // OUTBOUND_ALLOWED_HOSTS=127.0.0.1.
+  describe('Webhooks', () => {
+    let webhookHotelId: string;
+    let webhookRoomId: string;
+    let receiver: Server;
+    let receiverUrl: string;
+    let failingReceiver: Server;
+    let failingUrl: string;
+    const received: { headers: Record<string, any>; body: string }[] = [];
+    const subscriptionIds: string[] = [];
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const listen = async (status: number) => {
+      const server = createServer((req, res) => {
+        let body = '';
+        req.on('data', (chunk) => (body += chunk));
+        req.on('end', () => {
+          if (status < 300) {
+            received.push({ headers: req.headers, body });
+          }
+          res.writeHead(status);
+          res.end();
+        });
+      });
+      await new Promise<void>((resolve) => server.listen(0, '127.0.0.1', resolve));
+      return { server, url: `http://127.0.0.1:${(server.address() as AddressInfo).port}/hooks` };
+    };
+    
+    const subscribe = async (url: string, events: string[]) => {
+      const res = await apiRequest('/api/webhooks', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({ url, events }),
+      });
+      if (res.status === 201) {
+        subscriptionIds.push(res.body.data.id);
+      }
+      return res;
+    };
+    
+    const deliveries = (subscriptionId: string) =>
+      apiRequest(`/api/webhooks/${subscriptionId}/deliveries`, {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+    
+    const waitFor = async <T>(check: () => Promise<T | undefined>): Promise<T | undefined> => {
+      for (let i = 0; i < 40; i++) {
+        const result = await check();
+        if (result !== undefined) {
+          return result;
+        }
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      return undefined;
+    };
+    
+    const book = async (checkIn: number, checkOut: number) => {
+      const res = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: webhookRoomId,
+          checkInDate: isoDate(checkIn),
+          checkOutDate: isoDate(checkOut),
+          guests: 1,
+        }),
+      });
+      return res.body.data.id as string;
+    };
+    
+    beforeAll(async () => {
+      ({ server: receiver, url: receiverUrl } = await listen(200));
+      ({ server: failingReceiver, url: failingUrl } = await listen(500));
+      
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Webhook Hotel',
+          city: 'Pune',
+          country: 'India',
+        }),
+      });
+      webhookHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${webhookHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1901',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
+      });
+      webhookRoomId = roomRes.body.data.id;
+    });
+    
+    afterAll(async () => {
+      for (const id of subscriptionIds) {
+        await apiRequest(`/api/webhooks/${id}`, {
+          method: 'DELETE',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+        });
+      }
+      receiver.close();
+      failingReceiver.close();
+    });
+    
+    test('should reject unknown event types', async () => {
+      const { status, body } = await subscribe(receiverUrl, ['booking.exploded']);
+      
+      expect(status).toBe(400);
+      expect(body.error).toBe('INVALID_EVENT_TYPE');
+    });
+    
+    test('should reject endpoints on private addresses', async () => {
+      for (const url of ['http://169.254.169.254/latest/meta-data', 'http://10.0.0.1/hooks', 'http://[::1]/hooks']) {
+        const { status, body } = await subscribe(url, ['booking.created']);
+        
+        expect(status).toBe(400);
+        expect(body.error).toBe('INVALID_WEBHOOK_URL');
+      }
+    });
+    
+    test('should not let customers subscribe', async () => {
+      const { status, body } = await apiRequest('/api/webhooks', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({ url: receiverUrl, events: ['booking.created'] }),
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should deliver signed booking events', async () => {
+      const subscription = await subscribe(receiverUrl, ['booking.created', 'booking.cancelled']);
+      
+      expect(subscription.status).toBe(201);
+      expect(subscription.body.data.secret).toBeDefined();
+      
+      const secret = subscription.body.data.secret;
+      const bookingId = await book(95, 96);
+      
+      await apiRequest(`/api/bookings/${bookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({}),
+      });
+      
+      const events = await waitFor(async () => {
+        const matching = received.filter((r) => JSON.parse(r.body).data.id === bookingId);
+        return matching.length === 2 ? matching : undefined;
+      });
+      
+      expect(events).toBeDefined();
+      
+      const types = events!.map((e) => JSON.parse(e.body).type).sort();
+      expect(types).toEqual(['booking.cancelled', 'booking.created']);
+      
+      for (const event of events!) {
+        const signature = createHmac('sha256', secret).update(event.body).digest('hex');
+        expect(event.headers['x-webhook-signature']).toBe(signature);
+        expect(event.headers['x-webhook-event']).toBe(JSON.parse(event.body).type);
+      }
+      
+      const { status, body } = await deliveries(subscription.body.data.id);
+      
+      expect(status).toBe(200);
+      
+      const delivered = body.data.filter((d: any) => d.payload.data.id === bookingId);
+      expect(delivered.length).toBe(2);
+      expect(delivered.every((d: any) => d.status === 'delivered')).toBe(true);
+      
+      const replay = await apiRequest(
+        `/api/webhooks/${subscription.body.data.id}/deliveries/${delivered[0].id}/replay`,
+        {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${ownerToken}`,
+          },
+        }
+      );
+      
+      expect(replay.status).toBe(400);
+      expect(replay.body.error).toBe('DELIVERY_NOT_FAILED');
+    });
+    
+    test('should keep retrying deliveries the endpoint rejects', async () => {
+      const subscription = await subscribe(failingUrl, ['booking.created']);
+      const bookingId = await book(97, 98);
+      
+      const delivery = await waitFor(async () => {
+        const { body } = await deliveries(subscription.body.data.id);
+        return body.data.find((d: any) => d.payload.data.id === bookingId && d.attempts > 0);
+      });
+      
+      expect(delivery).toBeDefined();
+      expect(delivery.status).toBe('pending');
+      expect(delivery.lastStatusCode).toBe(500);
+      expect(delivery.nextAttemptAt).toBeDefined();
+    });
+    
+    test('should not announce cancelled holds', async () => {
+      const subscription = await subscribe(receiverUrl, ['booking.cancelled']);
+      
+      const hold = await apiRequest('/api/bookings/hold', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: webhookRoomId,
+          checkInDate: isoDate(110),
+          checkOutDate: isoDate(111),
+          guests: 1,
+        }),
+      });
+      const confirmedId = await book(112, 113);
+      
+      for (const id of [hold.body.data.id, confirmedId]) {
+        await apiRequest(`/api/bookings/${id}/cancel`, {
+          method: 'PUT',
+          headers: {
+            Authorization: `Bearer ${customerToken}`,
+          },
+          body: JSON.stringify({}),
+        });
+      }
+      
+      const { body } = await deliveries(subscription.body.data.id);
+      const bookingIds = body.data.map((d: any) => d.payload.data.id);
+      
+      expect(bookingIds).toContain(confirmedId);
+      expect(bookingIds).not.toContain(hold.body.data.id);
+    });
+  });
+  
+  describe('GET /api/hotels/:hotelId/events', () => {
+    let eventsHotelId: string;
+    let eventsRoomId: string;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    // Reads server-sent events off a streaming fetch response.
+    const openStream = async (token: string) => {
+      const controller = new AbortController();
+      const response = await fetch(`${BASE_URL}/api/hotels/${eventsHotelId}/events`, {
+        headers: { Authorization: `Bearer ${token}` },
+        signal: controller.signal,
+      });
+      const reader = response.body!.getReader();
+      const decoder = new TextDecoder();
+      let buffer = '';
+      
+      const next = async (type: string, matches: (data: any) => boolean = () => true) => {
+        while (true) {
+          let end;
+          while ((end = buffer.indexOf('\n\n')) !== -1) {
+            const block = buffer.slice(0, end);
+            buffer = buffer.slice(end + 2);
+            const event = block.match(/^event: (.*)$/m)?.[1];
+            const data = block.match(/^data: (.*)$/m)?.[1];
+            if (event === type && data && matches(JSON.parse(data))) {
+              return JSON.parse(data);
+            }
+          }
+          const { value, done } = await reader.read();
+          if (done) {
+            return undefined;
+          }
+          buffer += decoder.decode(value, { stream: true });
+        }
+      };
+      
+      return { response, next, close: () => controller.abort() };
+    };
+    
+    beforeAll(async () => {
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Front Desk Hotel',
+          city: 'Jaipur',
+          country: 'India',
+        }),
+      });
+      eventsHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${eventsHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '2001',
+          roomType: 'Standard',
+          pricePerNight: '1000',
+          maxOccupancy: 2,
+        }),
+      });
+      eventsRoomId = roomRes.body.data.id;
+    });
+    
+    test('should push booking and availability changes to the owner', async () => {
+      const stream = await openStream(ownerToken);
+      
+      expect(stream.response.status).toBe(200);
+      expect(stream.response.headers.get('content-type')).toContain('text/event-stream');
+      expect(await stream.next('ready')).toEqual({ type: 'ready', hotelId: eventsHotelId });
+      
+      const bookingRes = await apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({
+          roomId: eventsRoomId,
+          checkInDate: isoDate(100),
+          checkOutDate: isoDate(102),
+          guests: 1,
+        }),
+      });
+      const bookingId = bookingRes.body.data.id;
+      
+      const created = await stream.next('booking.created', (d) => d.bookingId === bookingId);
+      
+      expect(created.roomId).toBe(eventsRoomId);
+      expect(created.checkInDate).toBe(isoDate(100));
+      
+      await apiRequest(`/api/bookings/${bookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+        body: JSON.stringify({}),
+      });
+      
+      expect(await stream.next('booking.cancelled', (d) => d.bookingId === bookingId)).toBeDefined();
+      
+      await apiRequest(`/api/hotels/${eventsHotelId}/rooms/${eventsRoomId}/blocks`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          startDate: isoDate(105),
+          endDate: isoDate(106),
+          reason: 'Maintenance',
+        }),
+      });
+      
+      const changed = await stream.next('availability.changed', (d) => d.from === isoDate(105));
+      
+      expect(changed.roomId).toBe(eventsRoomId);
+      expect(changed.to).toBe(isoDate(107));
+      
+      stream.close();
+    });
+    
+    test('should not stream to customers', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${eventsHotelId}/events`, {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${customerToken}`,
+        },
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
+    });
+    
+    test('should require authentication', async () => {
+      const { status } = await apiRequest(`/api/hotels/${eventsHotelId}/events`, {
+        method: 'GET',
+      });
+      
+      expect(status).toBe(401);
+    });
+  });
+  
+  // Reads what the file mailer writes, so the backend must share this
+  // MAIL_FILE_DIR (an absolute path) and the retry settings below.
+  describe.skipIf(!process.env.MAIL_FILE_DIR)('Booking emails', () => {
+    const mailDir = process.env.MAIL_FILE_DIR ?? '';
+    const retryBaseSeconds = Number(process.env.MAIL_RETRY_BASE_SECONDS ?? 30);
+    const maxAttempts = Number(process.env.MAIL_MAX_ATTEMPTS ?? 8);
+    let mailHotelId: string;
+    let mailRoomId: string;
+    let guestEmail: string;
+    let guestToken: string;
+    let confirmedBookingId: string;
+    
+    const isoDate = (offset: number) => {
+      const date = new Date();
+      date.setDate(date.getDate() + offset);
+      return date.toISOString().split('T')[0];
+    };
+    
+    const book = (checkIn: number, checkOut: number) =>
+      apiRequest('/api/bookings', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${guestToken}`,
+        },
+        body: JSON.stringify({
+          roomId: mailRoomId,
+          checkInDate: isoDate(checkIn),
+          checkOutDate: isoDate(checkOut),
+          guests: 1,
+        }),
+      });
+    
+    const emails = (bookingId: string) =>
+      apiRequest(`/api/hotels/${mailHotelId}/bookings/${bookingId}/emails`, {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+      });
+    
+    const waitForEmail = async (
+      bookingId: string,
+      template: string,
+      done: (email: any) => boolean,
+      tries = 40
+    ) => {
+      for (let i = 0; i < tries; i++) {
+        const email = (await emails(bookingId)).body.data.find((e: any) => e.template === template);
+        if (email && done(email)) {
+          return email;
+        }
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      return undefined;
+    };
+    
+    const header = (message: string, name: string) =>
+      message.split('\r\n\r\n')[0].split('\r\n').find((line) => line.startsWith(`${name}: `))?.slice(name.length + 2);
+    
+    beforeAll(async () => {
+      guestEmail = `mailguest${Date.now()}@example.com`;
+      
+      await apiRequest('/api/auth/signup', {
+        method: 'POST',
+        body: JSON.stringify({
+          name: 'Mail Guest',
+          email: guestEmail,
+          password: 'guest123',
+          role: 'customer',
+        }),
+      });
+      
+      const loginRes = await apiRequest('/api/auth/login', {
+        method: 'POST',
+        body: JSON.stringify({
+          email: guestEmail,
+          password: 'guest123',
+        }),
+      });
+      guestToken = loginRes.body.data.token;
+      
+      const hotelRes = await apiRequest('/api/hotels', {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          name: 'Mail Hotel',
+          city: 'Shillong',
+          country: 'India',
+        }),
+      });
+      mailHotelId = hotelRes.body.data.id;
+      
+      const roomRes = await apiRequest(`/api/hotels/${mailHotelId}/rooms`, {
+        method: 'POST',
+        headers: {
+          Authorization: `Bearer ${ownerToken}`,
+        },
+        body: JSON.stringify({
+          roomNumber: '1801',
+          roomType: 'Standard',
+          pricePerNight: '1500',
+          maxOccupancy: 2,
+        }),
+      });
+      mailRoomId = roomRes.body.data.id;
+    });
+    
+    test('should write the confirmation to the guest', async () => {
+      const bookingRes = await book(118, 119);
+      confirmedBookingId = bookingRes.body.data.id;
+      
+      const email = await waitForEmail(confirmedBookingId, 'booking_confirmed', (e) => e.status === 'sent');
+      
+      expect(email).toBeDefined();
+      expect(email.recipient).toBe(guestEmail);
+      expect(email.attempts).toBe(1);
+      
+      const message = await readFile(join(mailDir, `${email.id}.eml`), 'utf8');
+      
+      expect(header(message, 'To')).toBe(guestEmail);
+      expect(header(message, 'Subject')).toBe('Your booking at Mail Hotel is confirmed');
+      expect(message).toContain(`Booking reference: ${confirmedBookingId}`);
+    });
+    
+    test('should write the cancellation to the guest', async () => {
+      await apiRequest(`/api/bookings/${confirmedBookingId}/cancel`, {
+        method: 'PUT',
+        headers: {
+          Authorization: `Bearer ${guestToken}`,
+        },
+        body: JSON.stringify({}),
+      });
+      
+      const email = await waitForEmail(confirmedBookingId, 'booking_cancelled', (e) => e.status === 'sent');
+      
+      expect(email).toBeDefined();
+      expect(email.recipient).toBe(guestEmail);
+      
+      const message = await readFile(join(mailDir, `${email.id}.eml`), 'utf8');
+      
+      expect(header(message, 'To')).toBe(guestEmail);
+      expect(header(message, 'Subject')).toBe('Your booking at Mail Hotel has been cancelled');
+      expect(message).toContain(`Booking reference: ${confirmedBookingId}`);
+    });
+    
+    test('should retry a failing send with backoff and then mark it failed', async () => {
+      // A file where the directory should be makes every write fail.
+      await rename(mailDir, `${mailDir}.moved`);
+      await writeFile(mailDir, '');
+      
+      try {
+        const bookingRes = await book(120, 121);
+        const bookingId = bookingRes.body.data.id;
+        
+        const retrying = await waitForEmail(bookingId, 'booking_confirmed', (e) => e.attempts === 1);
+        
+        expect(retrying).toBeDefined();
+        expect(retrying.lastError).toBeTruthy();
+        
+        if (maxAttempts > 1) {
+          expect(retrying.status).toBe('pending');
+          expect(Date.parse(retrying.nextAttemptAt) - Date.parse(retrying.lastAttemptAt)).toBe(
+            retryBaseSeconds * 1000
+          );
+          
+          const second = await waitForEmail(bookingId, 'booking_confirmed', (e) => e.attempts >= 2, 80);
+          
+          expect(second).toBeDefined();
+          if (second.status === 'pending') {
+            expect(Date.parse(second.nextAttemptAt) - Date.parse(second.lastAttemptAt)).toBe(
+              Math.min(retryBaseSeconds * 2, 3600) * 1000
+            );
+          }
+        }
+        
+        const failed = await waitForEmail(bookingId, 'booking_confirmed', (e) => e.status !== 'pending', 120);
+        
+        expect(failed).toBeDefined();
+        expect(failed.status).toBe('failed');
+        expect(failed.attempts).toBe(maxAttempts);
+        expect(failed.nextAttemptAt).toBeNull();
+        expect(failed.sentAt).toBeNull();
+      } finally {
+        await rm(mailDir, { force: true });
+        await rename(`${mailDir}.moved`, mailDir);
+      }
+    }, 90000);
+    
+    test('should not show the emails to customers', async () => {
+      const { status, body } = await apiRequest(`/api/hotels/${mailHotelId}/bookings/${confirmedBookingId}/emails`, {
+        method: 'GET',
+        headers: {
+          Authorization: `Bearer ${guestToken}`,
+        },
+      });
+      
+      expect(status).toBe(403);
+      expect(body.error).toBe('FORBIDDEN');
     });
   });
   